@@ -0,0 +1,500 @@
+//! Headless front end to the controller manager, for scripting and
+//! production line QA without a GUI.
+//!
+//! Everything meant for machines goes to stdout as JSON, prompts and
+//! diagnostics go to stderr. Backends are chosen with the same
+//! `SLOPTESTER_*` environment variables as the app.
+//!
+//! Backend logs are filtered with `SLOPTESTER_LOG` (default `warn`), in
+//! `RUST_LOG` syntax.
+//!
+//! Exit codes: 0 passed, 1 a test failed, 2 bad arguments, 3 no matching
+//! device, 4 the device has none of the controls the test needs.
+
+use serde::Serialize;
+use serde_json::json;
+use sloptester_core::analysis::{
+    analyze_circularity, analyze_debounce, analyze_drift, analyze_triggers, GateShape,
+    TriggerThresholds, DEFAULT_ANGLE_BINS, DEFAULT_BOUNCE_THRESHOLD_US, DEFAULT_DRIFT_DURATION_US,
+    TRIGGER_HOLD_US, TRIGGER_PRESS_US, TRIGGER_REST_US,
+};
+use sloptester_core::backends::HotplugEvent;
+use sloptester_core::error::ControllerError;
+use sloptester_core::managers::{ControllerManager, ManagerEvent};
+use sloptester_core::models::{DeviceKey, GamepadInfo};
+use std::io::Write;
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing_subscriber::EnvFilter;
+
+const USAGE: &str = "\
+Usage: sloptester-cli <command> [options]
+
+Commands:
+  list                     Print connected controllers as JSON
+  stream                   Print every state change as one JSON line
+  test <name>              Run a measurement and exit 0 on pass, 1 on fail
+
+Tests:
+  drift                    Leave the controller untouched
+  report-rate              Keep moving a stick or mashing buttons
+  circularity              Rotate both sticks slowly against the gate
+  debounce                 Press every button a few times
+  triggers                 Release, slowly press, then hold both triggers
+
+Options:
+  --device <type>:<id>     Controller to use, e.g. sdl:0 (default: the first found)
+  --duration <seconds>     How long to stream or measure
+  --rate <hz>              Polling rate
+  --max-deadzone <value>   drift: largest acceptable suggested deadzone (default 0.1)
+  --min-hz <value>         report-rate: lowest acceptable rate (default 125)
+  --max-error <value>      circularity: largest acceptable average error (default 0.1)
+  --threshold-ms <value>   debounce: presses shorter than this are bounce (default 10)";
+
+const EXIT_FAILED: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_NO_DEVICE: u8 = 3;
+const EXIT_NOT_APPLICABLE: u8 = 4;
+
+/// How long backends get to report devices that are already plugged in.
+const DISCOVERY_TIME: Duration = Duration::from_millis(500);
+
+const DEFAULT_MAX_DEADZONE: f32 = 0.1;
+const DEFAULT_MIN_REPORT_HZ: f64 = 125.0;
+const DEFAULT_REPORT_RATE_DURATION: Duration = Duration::from_secs(5);
+const DEFAULT_MAX_CIRCULARITY_ERROR: f32 = 0.1;
+const DEFAULT_CIRCULARITY_DURATION: Duration = Duration::from_secs(10);
+const DEFAULT_DEBOUNCE_DURATION: Duration = Duration::from_secs(10);
+
+enum CliError {
+    Usage(String),
+    NoDevice(String),
+    /// The device's layout lacks what the test measures.
+    NotApplicable(String),
+}
+
+/// The device went away or its backend isn't running.
+impl From<ControllerError> for CliError {
+    fn from(error: ControllerError) -> Self {
+        Self::NoDevice(error.to_string())
+    }
+}
+
+enum Command {
+    List,
+    Stream,
+    Test(Test),
+}
+
+/// A measurement `test` can run, checked before any backend starts.
+#[derive(Clone, Copy)]
+enum Test {
+    Drift,
+    ReportRate,
+    Circularity,
+    Debounce,
+    Triggers,
+}
+
+impl Test {
+    fn parse(name: &str) -> Result<Self, CliError> {
+        match name {
+            "drift" => Ok(Self::Drift),
+            "report-rate" => Ok(Self::ReportRate),
+            "circularity" => Ok(Self::Circularity),
+            "debounce" => Ok(Self::Debounce),
+            "triggers" => Ok(Self::Triggers),
+            other => Err(CliError::Usage(format!("Unknown test '{}'", other))),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    device: Option<DeviceKey>,
+    duration: Option<Duration>,
+    rate_hz: Option<u32>,
+    max_deadzone: Option<f32>,
+    min_hz: Option<f64>,
+    max_error: Option<f32>,
+    threshold_ms: Option<f64>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, CliError> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| CliError::Usage(format!("{} needs a value", flag)))?;
+            match flag.as_str() {
+                "--device" => options.device = Some(parse_device(value)?),
+                "--duration" => {
+                    let seconds: f64 = parse_value(flag, value)?;
+                    let duration = Duration::try_from_secs_f64(seconds).map_err(|_| {
+                        CliError::Usage(format!("Invalid {} value '{}'", flag, value))
+                    })?;
+                    options.duration = Some(duration);
+                }
+                "--rate" => options.rate_hz = Some(parse_value(flag, value)?),
+                "--max-deadzone" => options.max_deadzone = Some(parse_value(flag, value)?),
+                "--min-hz" => options.min_hz = Some(parse_value(flag, value)?),
+                "--max-error" => options.max_error = Some(parse_value(flag, value)?),
+                "--threshold-ms" => options.threshold_ms = Some(parse_value(flag, value)?),
+                other => return Err(CliError::Usage(format!("Unknown option '{}'", other))),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError::Usage(format!("Invalid {} value '{}'", flag, value)))
+}
+
+fn parse_device(value: &str) -> Result<DeviceKey, CliError> {
+    let (controller_type, id) = value
+        .split_once(':')
+        .ok_or_else(|| CliError::Usage(format!("Expected <type>:<id>, got '{}'", value)))?;
+    Ok(DeviceKey::new(
+        controller_type,
+        parse_value("--device", id)?,
+    ))
+}
+
+/// The outcome of a test, printed as the last line of output.
+#[derive(Serialize)]
+struct TestResult<T: Serialize> {
+    test: &'static str,
+    device: GamepadInfo,
+    passed: bool,
+    report: T,
+}
+
+fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_env("SLOPTESTER_LOG").unwrap_or_else(|_| EnvFilter::new("warn")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(EXIT_FAILED),
+        Err(CliError::Usage(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            ExitCode::from(EXIT_USAGE)
+        }
+        Err(CliError::NoDevice(message)) => {
+            eprintln!("{}", message);
+            ExitCode::from(EXIT_NO_DEVICE)
+        }
+        Err(CliError::NotApplicable(message)) => {
+            eprintln!("{}", message);
+            ExitCode::from(EXIT_NOT_APPLICABLE)
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<bool, CliError> {
+    let Some(command) = args.first() else {
+        return Err(CliError::Usage("Missing command".to_string()));
+    };
+    let (command, rest) = match command.as_str() {
+        "test" => match args.get(1) {
+            Some(name) => (Command::Test(Test::parse(name)?), &args[2..]),
+            None => return Err(CliError::Usage("Missing test name".to_string())),
+        },
+        "list" => (Command::List, &args[1..]),
+        "stream" => (Command::Stream, &args[1..]),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            return Ok(true);
+        }
+        other => return Err(CliError::Usage(format!("Unknown command '{}'", other))),
+    };
+    let options = Options::parse(rest)?;
+
+    let mut manager = ControllerManager::new();
+    if let Some(rate_hz) = options.rate_hz {
+        manager.set_poll_rate(rate_hz);
+    }
+    poll_for(&mut manager, DISCOVERY_TIME, |_, _| {});
+
+    match command {
+        Command::List => {
+            print_json(&manager.gamepads());
+            Ok(true)
+        }
+        Command::Stream => {
+            stream(&mut manager, &options);
+            Ok(true)
+        }
+        Command::Test(test) => {
+            let device = find_device(&manager, options.device.as_ref())?;
+            run_test(&mut manager, test, device, &options)
+        }
+    }
+}
+
+/// Polls at the manager's rate for `duration`, handing each poll's events
+/// to `on_events`. Polls back to back while a report rate measurement runs, like the
+/// app's input thread.
+fn poll_for(
+    manager: &mut ControllerManager,
+    duration: Duration,
+    mut on_events: impl FnMut(&ControllerManager, Vec<ManagerEvent>),
+) {
+    let started = Instant::now();
+    while started.elapsed() < duration {
+        let poll_started = Instant::now();
+        let events = manager.poll();
+        on_events(manager, events);
+        if manager.is_measuring_report_rate() {
+            thread::yield_now();
+        } else {
+            let interval = manager.poller_config().interval();
+            thread::sleep(interval.saturating_sub(poll_started.elapsed()));
+        }
+    }
+}
+
+fn find_device(
+    manager: &ControllerManager,
+    key: Option<&DeviceKey>,
+) -> Result<GamepadInfo, CliError> {
+    let gamepads = manager.gamepads();
+    let device = match key {
+        Some(key) => gamepads
+            .into_iter()
+            .find(|info| info.id == key.id && info.controller_type == key.controller_type),
+        None => gamepads.into_iter().next(),
+    };
+    device.ok_or_else(|| match key {
+        Some(key) => CliError::NoDevice(format!(
+            "No {} controller with id {}",
+            key.controller_type, key.id
+        )),
+        None => CliError::NoDevice("No controllers found".to_string()),
+    })
+}
+
+/// Writes one JSON line to stdout, exiting quietly once stdout is closed,
+/// e.g. when piped into `head`.
+fn print_json(value: &impl Serialize) {
+    let mut stdout = std::io::stdout().lock();
+    let result = serde_json::to_writer(&mut stdout, value)
+        .map_err(std::io::Error::from)
+        .and_then(|_| writeln!(stdout));
+    if result.is_err() {
+        std::process::exit(0);
+    }
+}
+
+/// Prints hotplug events and the full state of every device that changed,
+/// optionally filtered to one device.
+fn stream(manager: &mut ControllerManager, options: &Options) {
+    let wanted = |key: &DeviceKey| options.device.as_ref().is_none_or(|device| device == key);
+
+    for info in manager.gamepads() {
+        let key = DeviceKey::new(&info.controller_type, info.id);
+        if wanted(&key) {
+            print_json(&json!({ "type": "connected", "info": info }));
+        }
+    }
+
+    let duration = options.duration.unwrap_or(Duration::MAX);
+    poll_for(manager, duration, |manager, events| {
+        for event in events {
+            match event {
+                ManagerEvent::Hotplug(HotplugEvent::Connected(info)) => {
+                    if wanted(&DeviceKey::new(&info.controller_type, info.id)) {
+                        print_json(&json!({ "type": "connected", "info": info }));
+                    }
+                }
+                ManagerEvent::Hotplug(HotplugEvent::Disconnected {
+                    id,
+                    controller_type,
+                }) => {
+                    let device = DeviceKey::new(&controller_type, id);
+                    if wanted(&device) {
+                        print_json(&json!({ "type": "disconnected", "device": device }));
+                    }
+                }
+                ManagerEvent::State(delta) => {
+                    let device = DeviceKey::new(&delta.controller_type, delta.id);
+                    if !wanted(&device) {
+                        continue;
+                    }
+                    if let Ok(state) = manager.gamepad_state(delta.id, &delta.controller_type) {
+                        print_json(&json!({
+                            "type": "state",
+                            "timestamp_us": delta.timestamp_us,
+                            "device": device,
+                            "state": state,
+                        }));
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn run_test(
+    manager: &mut ControllerManager,
+    test: Test,
+    device: GamepadInfo,
+    options: &Options,
+) -> Result<bool, CliError> {
+    let controller_type = device.controller_type.clone();
+    let id = device.id;
+    eprintln!("Testing {} ({}:{})", device.name, controller_type, id);
+
+    let passed = match test {
+        Test::Drift => {
+            let duration = options
+                .duration
+                .unwrap_or(Duration::from_micros(DEFAULT_DRIFT_DURATION_US));
+            let max_deadzone = options.max_deadzone.unwrap_or(DEFAULT_MAX_DEADZONE);
+            eprintln!("Don't touch the controller...");
+            let since_us = manager.timestamp_us();
+            poll_for(manager, duration, |_, _| {});
+
+            let report = analyze_drift(&manager.samples_since(id, &controller_type, since_us)?);
+            let passed = !report.axes.is_empty()
+                && report
+                    .axes
+                    .iter()
+                    .all(|axis| axis.suggested_deadzone <= max_deadzone);
+            print_result("drift", device, passed, report)
+        }
+        Test::ReportRate => {
+            let duration = options.duration.unwrap_or(DEFAULT_REPORT_RATE_DURATION);
+            let min_hz = options.min_hz.unwrap_or(DEFAULT_MIN_REPORT_HZ);
+            eprintln!("Keep moving a stick or pressing buttons...");
+            manager.start_report_rate(id, &controller_type)?;
+            poll_for(manager, duration, |_, _| {});
+
+            let stats = manager.stop_report_rate();
+            let passed = stats
+                .as_ref()
+                .is_some_and(|stats| stats.estimated_hz >= min_hz);
+            print_result("report_rate", device, passed, stats)
+        }
+        Test::Circularity => {
+            if device.layout.sticks().is_empty() {
+                return Err(CliError::NotApplicable(format!(
+                    "{} has no sticks to test",
+                    device.name
+                )));
+            }
+            let duration = options.duration.unwrap_or(DEFAULT_CIRCULARITY_DURATION);
+            let max_error = options.max_error.unwrap_or(DEFAULT_MAX_CIRCULARITY_ERROR);
+            eprintln!("Rotate both sticks slowly against the gate...");
+            let since_us = manager.timestamp_us();
+            poll_for(manager, duration, |_, _| {});
+
+            let samples = manager.samples_since(id, &controller_type, since_us)?;
+            let report = analyze_circularity(&samples, &device.layout, DEFAULT_ANGLE_BINS);
+            let passed = report.applicable
+                && report.sticks.iter().all(|stick| {
+                    stick.gate != GateShape::Unknown && stick.average_error <= max_error
+                });
+            print_result("circularity", device, passed, report)
+        }
+        Test::Debounce => {
+            let duration = options.duration.unwrap_or(DEFAULT_DEBOUNCE_DURATION);
+            let threshold_us = options
+                .threshold_ms
+                .map(|ms| (ms * 1000.0) as u64)
+                .unwrap_or(DEFAULT_BOUNCE_THRESHOLD_US);
+            eprintln!("Press every button a few times...");
+            let since_us = manager.timestamp_us();
+            poll_for(manager, duration, |_, _| {});
+
+            let samples = manager.samples_since(id, &controller_type, since_us)?;
+            let report = analyze_debounce(&samples, threshold_us);
+            // Without a single press there was nothing to check
+            let pressed = report.buttons.iter().any(|button| button.presses > 0);
+            let passed = pressed && report.buttons.iter().all(|button| button.bounces == 0);
+            print_result("debounce", device, passed, report)
+        }
+        Test::Triggers => {
+            if device.layout.triggers().is_empty() {
+                return Err(CliError::NotApplicable(format!(
+                    "{} has no analog triggers to test",
+                    device.name
+                )));
+            }
+            let started_us = manager.timestamp_us();
+            let phases = [
+                ("Release both triggers...", TRIGGER_REST_US),
+                (
+                    "Slowly press both triggers all the way...",
+                    TRIGGER_PRESS_US,
+                ),
+                ("Hold them down...", TRIGGER_HOLD_US),
+            ];
+            for (prompt, phase_us) in phases {
+                eprintln!("{}", prompt);
+                poll_for(manager, Duration::from_micros(phase_us), |_, _| {});
+            }
+
+            let samples = manager.samples_since(id, &controller_type, started_us)?;
+            let report = analyze_triggers(
+                &samples,
+                &device.layout,
+                started_us,
+                TriggerThresholds::default(),
+            );
+            let passed = report.passed;
+            print_result("triggers", device, passed, report)
+        }
+    };
+    Ok(passed)
+}
+
+fn print_result(
+    test: &'static str,
+    device: GamepadInfo,
+    passed: bool,
+    report: impl Serialize,
+) -> bool {
+    eprintln!("{}", if passed { "PASS" } else { "FAIL" });
+    print_json(&TestResult {
+        test,
+        device,
+        passed,
+        report,
+    });
+    passed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn unknown_test_is_a_usage_error() {
+        // Rejected before any backend looks for devices
+        assert!(matches!(
+            run(&args(&["test", "drfit"])),
+            Err(CliError::Usage(message)) if message == "Unknown test 'drfit'"
+        ));
+        assert!(matches!(run(&args(&["test"])), Err(CliError::Usage(_))));
+        assert!(matches!(
+            run(&args(&["test", "drift", "--bogus", "1"])),
+            Err(CliError::Usage(_))
+        ));
+    }
+}
@@ -0,0 +1,262 @@
+use crate::models::{DeviceLayout, InputSample};
+use serde::Serialize;
+use std::f32::consts::{FRAC_PI_4, FRAC_PI_8, TAU};
+
+/// 5 degree bins.
+pub const DEFAULT_ANGLE_BINS: usize = 72;
+
+/// Samples closer to the center than this are the stick passing through or
+/// resting, not pushing against the gate.
+const MIN_RADIUS: f32 = 0.25;
+
+/// Gate shape is only guessed once this fraction of bins has been reached.
+const MIN_COVERAGE: f32 = 0.75;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GateShape {
+    Round,
+    Octagonal,
+    Square,
+    /// Not enough of the circle was covered to tell.
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StickCircularity {
+    /// `left` and `right` on the standard layout, axis names otherwise.
+    pub stick: String,
+    /// Samples that were far enough from center to be used.
+    pub samples: usize,
+    /// Largest radius seen in each angle bin, counter-clockwise from +X.
+    /// `None` for bins the stick never reached.
+    pub max_radius: Vec<Option<f32>>,
+    /// Fraction of bins that received at least one sample.
+    pub coverage: f32,
+    /// Mean of `|max_radius - 1|` over the covered bins. 0 is a perfect
+    /// unit circle.
+    pub average_error: f32,
+    pub gate: GateShape,
+    /// RMS error of the best matching gate model relative to its size.
+    pub gate_fit_error: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CircularityReport {
+    pub bins: usize,
+    /// See [`duration_us`](super::duration_us).
+    pub duration_us: u64,
+    pub samples: usize,
+    /// False if the device's layout has no stick, `sticks` is empty then.
+    pub applicable: bool,
+    pub sticks: Vec<StickCircularity>,
+}
+
+/// Bins stick positions by angle and keeps the outermost point per bin,
+/// which traces the gate when the stick is rotated against it. Every stick
+/// in `layout` is analyzed.
+pub fn analyze_circularity(
+    samples: &[InputSample],
+    layout: &DeviceLayout,
+    bins: usize,
+) -> CircularityReport {
+    let bins = bins.max(8);
+    let duration_us = super::duration_us(samples);
+
+    let sticks: Vec<StickCircularity> = layout
+        .sticks()
+        .into_iter()
+        .map(|(stick, x_axis, y_axis)| analyze_stick(samples, stick, x_axis, y_axis, bins))
+        .collect();
+
+    CircularityReport {
+        bins,
+        duration_us,
+        samples: samples.len(),
+        applicable: !sticks.is_empty(),
+        sticks,
+    }
+}
+
+fn analyze_stick(
+    samples: &[InputSample],
+    stick: String,
+    x_axis: usize,
+    y_axis: usize,
+    bins: usize,
+) -> StickCircularity {
+    let mut max_radius: Vec<Option<f32>> = vec![None; bins];
+    let mut used = 0;
+
+    for sample in samples {
+        let (Some(x), Some(y)) = (sample.state.axes.get(x_axis), sample.state.axes.get(y_axis))
+        else {
+            continue;
+        };
+        let radius = x.hypot(*y);
+        if radius < MIN_RADIUS {
+            continue;
+        }
+        used += 1;
+
+        let angle = y.atan2(*x).rem_euclid(TAU);
+        let bin = ((angle / TAU * bins as f32) as usize).min(bins - 1);
+        let max = max_radius[bin].get_or_insert(radius);
+        *max = max.max(radius);
+    }
+
+    // (bin center angle, radius) for every covered bin
+    let points: Vec<(f32, f32)> = max_radius
+        .iter()
+        .enumerate()
+        .filter_map(|(bin, radius)| radius.map(|r| ((bin as f32 + 0.5) / bins as f32 * TAU, r)))
+        .collect();
+
+    let coverage = points.len() as f32 / bins as f32;
+    let average_error = if points.is_empty() {
+        0.0
+    } else {
+        points.iter().map(|(_, r)| (r - 1.0).abs()).sum::<f32>() / points.len() as f32
+    };
+
+    let (gate, gate_fit_error) = if coverage >= MIN_COVERAGE {
+        let (gate, error) = [
+            (GateShape::Round, fit_error(&points, round_profile)),
+            (GateShape::Octagonal, fit_error(&points, octagon_profile)),
+            (GateShape::Square, fit_error(&points, square_profile)),
+        ]
+        .into_iter()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((GateShape::Unknown, 0.0));
+        (gate, Some(error))
+    } else {
+        (GateShape::Unknown, None)
+    };
+
+    StickCircularity {
+        stick,
+        samples: used,
+        max_radius,
+        coverage,
+        average_error,
+        gate,
+        gate_fit_error,
+    }
+}
+
+/// Fits `scale * profile(angle)` to the points by least squares and returns
+/// the RMS residual divided by the fitted scale.
+fn fit_error(points: &[(f32, f32)], profile: fn(f32) -> f32) -> f32 {
+    let (dot, norm) = points.iter().fold((0.0, 0.0), |(dot, norm), (angle, r)| {
+        let p = profile(*angle);
+        (dot + r * p, norm + p * p)
+    });
+    if norm == 0.0 || dot == 0.0 {
+        return f32::INFINITY;
+    }
+    let scale = dot / norm;
+    let squared: f32 = points
+        .iter()
+        .map(|(angle, r)| (r - scale * profile(*angle)).powi(2))
+        .sum();
+    (squared / points.len() as f32).sqrt() / scale
+}
+
+fn round_profile(_angle: f32) -> f32 {
+    1.0
+}
+
+/// Octagon with its corners on the cardinal and diagonal directions, like
+/// the notched gates on most pads.
+fn octagon_profile(angle: f32) -> f32 {
+    let offset = angle.rem_euclid(FRAC_PI_4) - FRAC_PI_8;
+    FRAC_PI_8.cos() / offset.cos()
+}
+
+/// Axis-aligned square, i.e. each axis saturating independently.
+fn square_profile(angle: f32) -> f32 {
+    1.0 / angle.cos().abs().max(angle.sin().abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AxisKind, GamepadState};
+
+    fn layout() -> DeviceLayout {
+        let mut layout = DeviceLayout::default();
+        layout.push_axis("left_x", AxisKind::Stick);
+        layout.push_axis("left_y", AxisKind::Stick);
+        layout
+    }
+
+    /// One slow rotation, `steps` samples 1ms apart, pushed out to
+    /// `profile(angle)`.
+    fn rotation(steps: usize, profile: fn(f32) -> f32) -> Vec<InputSample> {
+        (0..steps)
+            .map(|step| {
+                let angle = step as f32 / steps as f32 * TAU;
+                let radius = profile(angle);
+                InputSample {
+                    timestamp_us: step as u64 * 1_000,
+                    state: GamepadState {
+                        buttons: Vec::new(),
+                        axes: vec![radius * angle.cos(), radius * angle.sin()],
+                        raw_axes: Vec::new(),
+                        axis_normalization: Vec::new(),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn circular_gate() {
+        let samples = rotation(720, round_profile);
+        let report = analyze_circularity(&samples, &layout(), DEFAULT_ANGLE_BINS);
+        assert!(report.applicable);
+        assert_eq!(report.duration_us, 719_000);
+
+        let stick = &report.sticks[0];
+        assert_eq!(stick.stick, "left");
+        assert_eq!(stick.samples, 720);
+        assert_eq!(stick.coverage, 1.0);
+        assert!(stick.average_error < 0.001, "{}", stick.average_error);
+        assert_eq!(stick.gate, GateShape::Round);
+    }
+
+    #[test]
+    fn square_gate() {
+        let samples = rotation(720, square_profile);
+        let report = analyze_circularity(&samples, &layout(), DEFAULT_ANGLE_BINS);
+
+        let stick = &report.sticks[0];
+        assert_eq!(stick.gate, GateShape::Square);
+        // The corners reach sqrt(2)
+        assert!(stick.average_error > 0.1, "{}", stick.average_error);
+        let max = stick
+            .max_radius
+            .iter()
+            .flatten()
+            .fold(0.0_f32, |a, b| a.max(*b));
+        assert!((max - 2.0_f32.sqrt()).abs() < 0.01, "{}", max);
+    }
+
+    #[test]
+    fn partial_rotation() {
+        // Half a circle isn't enough to tell the gate
+        let samples: Vec<InputSample> =
+            rotation(720, round_profile).into_iter().take(360).collect();
+        let report = analyze_circularity(&samples, &layout(), DEFAULT_ANGLE_BINS);
+        let stick = &report.sticks[0];
+        assert_eq!(stick.coverage, 0.5);
+        assert_eq!(stick.gate, GateShape::Unknown);
+        assert_eq!(stick.gate_fit_error, None);
+
+        // Without sticks there is nothing to analyze
+        let report =
+            analyze_circularity(&samples, &DeviceLayout::generic(2, 2), DEFAULT_ANGLE_BINS);
+        assert!(!report.applicable);
+        assert!(report.sticks.is_empty());
+    }
+}
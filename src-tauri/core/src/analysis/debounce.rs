@@ -0,0 +1,191 @@
+use crate::models::InputSample;
+use serde::Serialize;
+
+/// Presses or releases shorter than this are treated as switch bounce when
+/// no threshold is given. Human taps are rarely under 20ms.
+pub const DEFAULT_BOUNCE_THRESHOLD_US: u64 = 10_000;
+
+/// How many short presses are listed per button.
+const MAX_FLAGGED_PRESSES: usize = 100;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FlaggedPress {
+    pub pressed_at_us: u64,
+    pub duration_us: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ButtonDebounceReport {
+    pub index: usize,
+    pub presses: usize,
+    /// Presses that were released again within the threshold.
+    pub short_presses: usize,
+    /// Releases that were pressed again within the threshold.
+    pub short_releases: usize,
+    /// `short_presses + short_releases`.
+    pub bounces: usize,
+    pub shortest_press_us: Option<u64>,
+    pub shortest_release_us: Option<u64>,
+    pub flagged_presses: Vec<FlaggedPress>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DebounceReport {
+    pub threshold_us: u64,
+    /// See [`duration_us`](super::duration_us).
+    pub duration_us: u64,
+    pub samples: usize,
+    pub buttons: Vec<ButtonDebounceReport>,
+}
+
+/// Finds button chatter by timing every press and release edge in `samples`.
+///
+/// Timing resolution is the sample interval, so bounce shorter than one poll
+/// period can be missed entirely.
+pub fn analyze_debounce(samples: &[InputSample], threshold_us: u64) -> DebounceReport {
+    let button_count = samples
+        .iter()
+        .map(|sample| sample.state.buttons.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut buttons: Vec<ButtonDebounceReport> = (0..button_count)
+        .map(|index| ButtonDebounceReport {
+            index,
+            presses: 0,
+            short_presses: 0,
+            short_releases: 0,
+            bounces: 0,
+            shortest_press_us: None,
+            shortest_release_us: None,
+            flagged_presses: Vec::new(),
+        })
+        .collect();
+    let mut last_press: Vec<Option<u64>> = vec![None; button_count];
+    let mut last_release: Vec<Option<u64>> = vec![None; button_count];
+
+    for pair in samples.windows(2) {
+        let (previous, current) = (&pair[0], &pair[1]);
+        let t = current.timestamp_us;
+
+        for (index, report) in buttons.iter_mut().enumerate() {
+            let was = previous.state.buttons.get(index).copied().unwrap_or(false);
+            let is = current.state.buttons.get(index).copied().unwrap_or(false);
+
+            if !was && is {
+                report.presses += 1;
+                if let Some(released_at) = last_release[index] {
+                    let interval = t.saturating_sub(released_at);
+                    report.shortest_release_us =
+                        Some(min_opt(report.shortest_release_us, interval));
+                    if interval < threshold_us {
+                        report.short_releases += 1;
+                    }
+                }
+                last_press[index] = Some(t);
+            } else if was && !is {
+                if let Some(pressed_at) = last_press[index] {
+                    let duration = t.saturating_sub(pressed_at);
+                    report.shortest_press_us = Some(min_opt(report.shortest_press_us, duration));
+                    if duration < threshold_us {
+                        report.short_presses += 1;
+                        if report.flagged_presses.len() < MAX_FLAGGED_PRESSES {
+                            report.flagged_presses.push(FlaggedPress {
+                                pressed_at_us: pressed_at,
+                                duration_us: duration,
+                            });
+                        }
+                    }
+                }
+                last_release[index] = Some(t);
+            }
+        }
+    }
+
+    for report in buttons.iter_mut() {
+        report.bounces = report.short_presses + report.short_releases;
+    }
+
+    let duration_us = super::duration_us(samples);
+
+    DebounceReport {
+        threshold_us,
+        duration_us,
+        samples: samples.len(),
+        buttons,
+    }
+}
+
+fn min_opt(current: Option<u64>, value: u64) -> u64 {
+    current.map_or(value, |current| current.min(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GamepadState;
+
+    fn sample(timestamp_us: u64, buttons: [bool; 2]) -> InputSample {
+        InputSample {
+            timestamp_us,
+            state: GamepadState {
+                buttons: buttons.to_vec(),
+                axes: Vec::new(),
+                raw_axes: Vec::new(),
+                axis_normalization: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn finds_chatter() {
+        // Button 0 is pressed cleanly twice, button 1 chatters on its press
+        let samples = [
+            sample(0, [false, false]),
+            sample(10_000, [true, true]),
+            sample(12_000, [true, false]),
+            sample(14_000, [true, true]),
+            sample(60_000, [false, false]),
+            sample(150_000, [true, false]),
+            sample(200_000, [false, false]),
+        ];
+        let report = analyze_debounce(&samples, DEFAULT_BOUNCE_THRESHOLD_US);
+        assert_eq!(report.duration_us, 200_000);
+        assert_eq!(report.samples, samples.len());
+
+        let clean = &report.buttons[0];
+        assert_eq!((clean.presses, clean.bounces), (2, 0));
+        assert_eq!(clean.shortest_press_us, Some(50_000));
+        assert_eq!(clean.shortest_release_us, Some(90_000));
+        assert!(clean.flagged_presses.is_empty());
+
+        let chattering = &report.buttons[1];
+        assert_eq!(chattering.presses, 2);
+        assert_eq!(
+            (chattering.short_presses, chattering.short_releases),
+            (1, 1)
+        );
+        assert_eq!(chattering.bounces, 2);
+        assert_eq!(chattering.shortest_press_us, Some(2_000));
+        assert_eq!(chattering.shortest_release_us, Some(2_000));
+        assert_eq!(chattering.flagged_presses.len(), 1);
+        assert_eq!(chattering.flagged_presses[0].pressed_at_us, 10_000);
+        assert_eq!(chattering.flagged_presses[0].duration_us, 2_000);
+    }
+
+    #[test]
+    fn out_of_order_timestamps() {
+        // A sample stamped before the previous one doesn't underflow
+        let samples = [
+            sample(5_000, [false, false]),
+            sample(6_000, [true, false]),
+            sample(1_000, [false, false]),
+            sample(500, [true, false]),
+        ];
+        let report = analyze_debounce(&samples, DEFAULT_BOUNCE_THRESHOLD_US);
+        assert_eq!(report.duration_us, 0);
+        assert_eq!(report.buttons[0].shortest_press_us, Some(0));
+        assert_eq!(report.buttons[0].shortest_release_us, Some(0));
+        assert_eq!(report.buttons[0].bounces, 2);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::models::InputSample;
+use serde::Serialize;
+
+/// How long the sticks should be left alone for a drift measurement.
+pub const DEFAULT_DRIFT_DURATION_US: u64 = 3_000_000;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AxisDrift {
+    pub index: usize,
+    /// Resting offset from center (or from released, for triggers).
+    pub mean: f32,
+    /// Noise around the resting offset.
+    pub stddev: f32,
+    pub min: f32,
+    pub max: f32,
+    pub raw_mean: f64,
+    pub raw_stddev: f64,
+    pub raw_min: i32,
+    pub raw_max: i32,
+    /// Smallest deadzone, in normalized units, that hides every value seen
+    /// while idle.
+    pub suggested_deadzone: f32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DriftReport {
+    /// See [`duration_us`](super::duration_us).
+    pub duration_us: u64,
+    pub samples: usize,
+    pub axes: Vec<AxisDrift>,
+}
+
+/// Summarizes how far each axis wanders from rest. `samples` should only
+/// cover a period where the controller was not being touched.
+pub fn analyze_drift(samples: &[InputSample]) -> DriftReport {
+    let duration_us = super::duration_us(samples);
+    let axis_count = samples
+        .iter()
+        .map(|sample| sample.state.axes.len())
+        .max()
+        .unwrap_or(0);
+
+    let axes = (0..axis_count)
+        .filter_map(|index| axis_drift(samples, index))
+        .collect();
+
+    DriftReport {
+        duration_us,
+        samples: samples.len(),
+        axes,
+    }
+}
+
+fn axis_drift(samples: &[InputSample], index: usize) -> Option<AxisDrift> {
+    let values: Vec<(f32, i32)> = samples
+        .iter()
+        .filter_map(|sample| {
+            let value = *sample.state.axes.get(index)?;
+            let raw = sample.state.raw_axes.get(index).copied().unwrap_or(0);
+            Some((value, raw))
+        })
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+
+    let (mean, stddev) = mean_stddev(values.iter().map(|(value, _)| f64::from(*value)));
+    let (raw_mean, raw_stddev) = mean_stddev(values.iter().map(|(_, raw)| f64::from(*raw)));
+    let min = values
+        .iter()
+        .map(|(value, _)| *value)
+        .fold(f32::MAX, f32::min);
+    let max = values
+        .iter()
+        .map(|(value, _)| *value)
+        .fold(f32::MIN, f32::max);
+
+    Some(AxisDrift {
+        index,
+        mean: mean as f32,
+        stddev: stddev as f32,
+        min,
+        max,
+        raw_mean,
+        raw_stddev,
+        raw_min: values.iter().map(|(_, raw)| *raw).min().unwrap_or(0),
+        raw_max: values.iter().map(|(_, raw)| *raw).max().unwrap_or(0),
+        suggested_deadzone: min.abs().max(max.abs()),
+    })
+}
+
+fn mean_stddev(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let count = values.clone().count().max(1) as f64;
+    let mean = values.clone().sum::<f64>() / count;
+    let variance = values.map(|value| (value - mean).powi(2)).sum::<f64>() / count;
+    (mean, variance.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GamepadState;
+
+    fn sample(timestamp_us: u64, raw_axes: Vec<i32>) -> InputSample {
+        InputSample {
+            timestamp_us,
+            state: GamepadState {
+                buttons: Vec::new(),
+                axes: raw_axes.iter().map(|raw| *raw as f32 / 100.0).collect(),
+                raw_axes,
+                axis_normalization: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn idle_drift() {
+        // Axis 0 rests off center and wobbles, axis 1 is dead still
+        let samples = [
+            sample(0, vec![4, 0]),
+            sample(1_000, vec![6, 0]),
+            sample(2_000, vec![4, 0]),
+            sample(3_000, vec![6, 0]),
+        ];
+        let report = analyze_drift(&samples);
+        assert_eq!(report.duration_us, 3_000);
+        assert_eq!(report.samples, 4);
+
+        let drifting = &report.axes[0];
+        assert!((drifting.mean - 0.05).abs() < 1e-6, "{}", drifting.mean);
+        assert!((drifting.stddev - 0.01).abs() < 1e-6, "{}", drifting.stddev);
+        assert_eq!((drifting.raw_min, drifting.raw_max), (4, 6));
+        assert_eq!(drifting.raw_mean, 5.0);
+        assert_eq!(drifting.raw_stddev, 1.0);
+        assert_eq!(drifting.suggested_deadzone, 0.06);
+
+        let still = &report.axes[1];
+        assert_eq!((still.mean, still.stddev), (0.0, 0.0));
+        assert_eq!(still.suggested_deadzone, 0.0);
+    }
+
+    #[test]
+    fn deadzone_covers_both_directions() {
+        // The deadzone is the largest excursion, whichever side it is on
+        let samples = [
+            sample(0, vec![2]),
+            sample(1_000, vec![-9]),
+            sample(2_000, vec![3]),
+        ];
+        let report = analyze_drift(&samples);
+        assert_eq!(report.axes[0].min, -0.09);
+        assert_eq!(report.axes[0].max, 0.03);
+        assert_eq!(report.axes[0].suggested_deadzone, 0.09);
+
+        assert!(analyze_drift(&[]).axes.is_empty());
+    }
+}
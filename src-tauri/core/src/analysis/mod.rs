@@ -0,0 +1,29 @@
+pub mod circularity;
+pub mod debounce;
+pub mod drift;
+pub mod quantization;
+pub mod report_rate;
+pub mod socd;
+pub mod trigger;
+
+use crate::models::InputSample;
+
+pub use circularity::{analyze_circularity, CircularityReport, GateShape, DEFAULT_ANGLE_BINS};
+pub use debounce::{analyze_debounce, DebounceReport, DEFAULT_BOUNCE_THRESHOLD_US};
+pub use drift::{analyze_drift, DriftReport, DEFAULT_DRIFT_DURATION_US};
+pub use quantization::{analyze_quantization, QuantizationReport};
+pub use report_rate::ReportRateStats;
+pub use socd::{SocdTest, SocdTestStatus, SOCD_SAMPLE_WINDOW_US};
+pub use trigger::{
+    analyze_triggers, TriggerReport, TriggerThresholds, TRIGGER_HOLD_US, TRIGGER_PRESS_US,
+    TRIGGER_REST_US, TRIGGER_TEST_US,
+};
+
+/// Time from the first to the last of `samples`, zero if there are fewer
+/// than two or they are out of order.
+pub fn duration_us(samples: &[InputSample]) -> u64 {
+    match (samples.first(), samples.last()) {
+        (Some(first), Some(last)) => last.timestamp_us.saturating_sub(first.timestamp_us),
+        _ => 0,
+    }
+}
@@ -0,0 +1,181 @@
+use crate::models::InputSample;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Distinct values listed per axis. A good 16-bit stick swept end to end can
+/// produce tens of thousands.
+const MAX_HISTOGRAM_VALUES: usize = 4096;
+
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct RawValueCount {
+    pub value: i32,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AxisQuantization {
+    pub index: usize,
+    pub distinct_values: usize,
+    pub observed_min: i32,
+    pub observed_max: i32,
+    /// Most common gap between neighbouring observed values, in raw units.
+    pub step_size: Option<u32>,
+    /// Bits needed to cover the axis' full source range at `step_size`.
+    pub effective_bits: Option<f32>,
+    /// Values between `observed_min` and `observed_max` that are a multiple
+    /// of `step_size` away from the minimum but were never seen.
+    pub missing_codes: usize,
+    /// Sample count per observed value, ascending. Cut off after
+    /// `MAX_HISTOGRAM_VALUES` entries.
+    pub histogram: Vec<RawValueCount>,
+    pub histogram_truncated: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct QuantizationReport {
+    /// See [`duration_us`](super::duration_us).
+    pub duration_us: u64,
+    pub samples: usize,
+    pub axes: Vec<AxisQuantization>,
+}
+
+/// Collects every raw value each axis reported in `samples` to estimate the
+/// resolution the device really has. Axes should be swept slowly over their
+/// full range for the result to mean anything.
+pub fn analyze_quantization(samples: &[InputSample]) -> QuantizationReport {
+    let duration_us = super::duration_us(samples);
+    let axis_count = samples
+        .iter()
+        .map(|sample| sample.state.raw_axes.len())
+        .max()
+        .unwrap_or(0);
+
+    let axes = (0..axis_count)
+        .filter_map(|index| axis_quantization(samples, index))
+        .collect();
+
+    QuantizationReport {
+        duration_us,
+        samples: samples.len(),
+        axes,
+    }
+}
+
+fn axis_quantization(samples: &[InputSample], index: usize) -> Option<AxisQuantization> {
+    let mut counts: BTreeMap<i32, usize> = BTreeMap::new();
+    for sample in samples {
+        if let Some(raw) = sample.state.raw_axes.get(index) {
+            *counts.entry(*raw).or_default() += 1;
+        }
+    }
+    let observed_min = *counts.keys().next()?;
+    let observed_max = *counts.keys().next_back()?;
+
+    let mut gaps: BTreeMap<u32, usize> = BTreeMap::new();
+    for (low, high) in counts.keys().zip(counts.keys().skip(1)) {
+        *gaps.entry(high.abs_diff(*low)).or_default() += 1;
+    }
+    // Ties go to the smaller gap
+    let step_size = gaps
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+        .map(|(gap, _)| *gap);
+
+    let range = samples
+        .iter()
+        .rev()
+        .find_map(|sample| sample.state.axis_normalization.get(index))
+        .map(|normalization| normalization.max.abs_diff(normalization.min));
+    let effective_bits = match (step_size, range) {
+        (Some(step), Some(range)) => Some((f64::from(range / step) + 1.0).log2() as f32),
+        _ => None,
+    };
+
+    let missing_codes = step_size
+        .map(|step| {
+            let expected = (observed_max.abs_diff(observed_min) / step) as usize + 1;
+            let on_grid = counts
+                .keys()
+                .filter(|value| value.abs_diff(observed_min) % step == 0)
+                .count();
+            expected.saturating_sub(on_grid)
+        })
+        .unwrap_or(0);
+
+    let histogram: Vec<RawValueCount> = counts
+        .iter()
+        .take(MAX_HISTOGRAM_VALUES)
+        .map(|(value, count)| RawValueCount {
+            value: *value,
+            count: *count,
+        })
+        .collect();
+
+    Some(AxisQuantization {
+        index,
+        distinct_values: counts.len(),
+        observed_min,
+        observed_max,
+        step_size,
+        effective_bits,
+        missing_codes,
+        histogram_truncated: counts.len() > histogram.len(),
+        histogram,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AxisNormalization, GamepadState};
+
+    const FULL_RANGE: AxisNormalization = AxisNormalization::new(-32768, 32767, 32767.0);
+
+    fn samples(raw_values: impl IntoIterator<Item = i32>) -> Vec<InputSample> {
+        raw_values
+            .into_iter()
+            .enumerate()
+            .map(|(i, raw)| InputSample {
+                timestamp_us: i as u64 * 1_000,
+                state: GamepadState {
+                    buttons: Vec::new(),
+                    axes: vec![FULL_RANGE.apply(raw)],
+                    raw_axes: vec![raw],
+                    axis_normalization: vec![FULL_RANGE],
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn eight_bits_in_a_sixteen_bit_range() {
+        // Every 256th value, with -32768 seen twice and 0 never
+        let values = (-128..128).map(|step| step * 256).filter(|raw| *raw != 0);
+        let report = analyze_quantization(&samples(values.chain([-32768])));
+        assert_eq!(report.samples, 256);
+        assert_eq!(report.duration_us, 255_000);
+
+        let axis = &report.axes[0];
+        assert_eq!(axis.distinct_values, 255);
+        assert_eq!((axis.observed_min, axis.observed_max), (-32768, 32512));
+        assert_eq!(axis.step_size, Some(256));
+        assert_eq!(axis.effective_bits, Some(8.0));
+        assert_eq!(axis.missing_codes, 1);
+        assert_eq!(axis.histogram.len(), 255);
+        assert!(!axis.histogram_truncated);
+        assert_eq!(axis.histogram[0].value, -32768);
+        assert_eq!(axis.histogram[0].count, 2);
+        assert_eq!(axis.histogram[1].count, 1);
+    }
+
+    #[test]
+    fn histogram_is_truncated() {
+        let report = analyze_quantization(&samples(0..5_000));
+        let axis = &report.axes[0];
+        assert_eq!(axis.distinct_values, 5_000);
+        assert_eq!(axis.step_size, Some(1));
+        assert_eq!(axis.missing_codes, 0);
+        assert_eq!(axis.histogram.len(), MAX_HISTOGRAM_VALUES);
+        assert!(axis.histogram_truncated);
+    }
+}
@@ -0,0 +1,160 @@
+use serde::Serialize;
+
+/// USB interrupt polling rates controllers commonly run at.
+const STANDARD_RATES_HZ: [u32; 7] = [125, 250, 500, 1000, 2000, 4000, 8000];
+
+/// Intervals longer than this are the controller sitting idle (no input
+/// changes to report), not a slow report rate, and are left out of the stats.
+pub const IDLE_GAP_US: u64 = 50_000;
+
+/// Width of each interval histogram bucket.
+pub const HISTOGRAM_BUCKET_US: u64 = 50;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HistogramBucket {
+    pub start_us: u64,
+    pub end_us: u64,
+    pub count: usize,
+}
+
+/// Report interval statistics for one device.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportRateStats {
+    /// Number of reports timestamped.
+    pub reports: usize,
+    /// Number of intervals the stats were computed from.
+    pub intervals: usize,
+    /// Intervals dropped for exceeding `IDLE_GAP_US`.
+    pub idle_gaps: usize,
+    pub min_interval_us: u64,
+    pub avg_interval_us: f64,
+    pub max_interval_us: u64,
+    /// Standard deviation of the intervals.
+    pub jitter_us: f64,
+    /// `1 / avg_interval`.
+    pub estimated_hz: f64,
+    /// The standard USB rate closest to `estimated_hz`.
+    pub nominal_hz: u32,
+    pub histogram: Vec<HistogramBucket>,
+}
+
+impl ReportRateStats {
+    /// Computes stats from report timestamps in microseconds, oldest first.
+    /// Returns `None` until there are at least two reports less than
+    /// `IDLE_GAP_US` apart.
+    pub fn from_timestamps(timestamps: &[u64]) -> Option<Self> {
+        let all_intervals = timestamps.windows(2).map(|w| w[1].saturating_sub(w[0]));
+        let (intervals, gaps): (Vec<u64>, Vec<u64>) =
+            all_intervals.partition(|interval| *interval <= IDLE_GAP_US);
+
+        if intervals.is_empty() {
+            return None;
+        }
+
+        let count = intervals.len() as f64;
+        let min_interval_us = *intervals.iter().min()?;
+        let max_interval_us = *intervals.iter().max()?;
+        let avg_interval_us = intervals.iter().sum::<u64>() as f64 / count;
+        let variance = intervals
+            .iter()
+            .map(|i| (*i as f64 - avg_interval_us).powi(2))
+            .sum::<f64>()
+            / count;
+        let estimated_hz = if avg_interval_us > 0.0 {
+            1_000_000.0 / avg_interval_us
+        } else {
+            0.0
+        };
+        let nominal_hz = STANDARD_RATES_HZ
+            .iter()
+            .copied()
+            .min_by(|a, b| {
+                let da = (*a as f64 - estimated_hz).abs();
+                let db = (*b as f64 - estimated_hz).abs();
+                da.total_cmp(&db)
+            })
+            .unwrap_or(0);
+
+        Some(Self {
+            reports: timestamps.len(),
+            intervals: intervals.len(),
+            idle_gaps: gaps.len(),
+            min_interval_us,
+            avg_interval_us,
+            max_interval_us,
+            jitter_us: variance.sqrt(),
+            estimated_hz,
+            nominal_hz,
+            histogram: histogram(&intervals, min_interval_us, max_interval_us),
+        })
+    }
+}
+
+fn histogram(intervals: &[u64], min: u64, max: u64) -> Vec<HistogramBucket> {
+    let first = min / HISTOGRAM_BUCKET_US;
+    let last = max / HISTOGRAM_BUCKET_US;
+    let mut buckets: Vec<HistogramBucket> = (first..=last)
+        .map(|bucket| HistogramBucket {
+            start_us: bucket * HISTOGRAM_BUCKET_US,
+            end_us: (bucket + 1) * HISTOGRAM_BUCKET_US,
+            count: 0,
+        })
+        .collect();
+
+    for interval in intervals {
+        let index = (interval / HISTOGRAM_BUCKET_US - first) as usize;
+        buckets[index].count += 1;
+    }
+
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jitter_on_irregular_timestamps() {
+        // Alternating 900us and 1100us intervals, with one idle pause
+        let mut timestamps = vec![0];
+        for i in 0..100 {
+            let interval = if i % 2 == 0 { 900 } else { 1_100 };
+            timestamps.push(timestamps[i] + interval);
+        }
+        timestamps.push(timestamps[100] + 200_000);
+        timestamps.push(timestamps[101] + 1_000);
+
+        let stats = ReportRateStats::from_timestamps(&timestamps).unwrap();
+        assert_eq!(stats.reports, 103);
+        assert_eq!(stats.intervals, 101);
+        assert_eq!(stats.idle_gaps, 1);
+        assert_eq!((stats.min_interval_us, stats.max_interval_us), (900, 1_100));
+        assert_eq!(stats.avg_interval_us, 1_000.0);
+        assert!((stats.jitter_us - 99.5).abs() < 0.1, "{}", stats.jitter_us);
+        assert_eq!(stats.estimated_hz, 1_000.0);
+        assert_eq!(stats.nominal_hz, 1_000);
+
+        let counts: Vec<(u64, usize)> = stats
+            .histogram
+            .iter()
+            .map(|bucket| (bucket.start_us, bucket.count))
+            .collect();
+        assert_eq!(
+            counts,
+            [(900, 50), (950, 0), (1_000, 1), (1_050, 0), (1_100, 50)]
+        );
+    }
+
+    #[test]
+    fn steady_timestamps() {
+        let timestamps: Vec<u64> = (0..50).map(|i| i * 4_000).collect();
+        let stats = ReportRateStats::from_timestamps(&timestamps).unwrap();
+        assert_eq!(stats.jitter_us, 0.0);
+        assert_eq!(stats.nominal_hz, 250);
+        assert_eq!(stats.histogram.len(), 1);
+
+        // Too few reports, or only idle gaps, give no stats
+        assert!(ReportRateStats::from_timestamps(&[5]).is_none());
+        assert!(ReportRateStats::from_timestamps(&[0, 60_000, 120_000]).is_none());
+    }
+}
@@ -0,0 +1,407 @@
+//! Guided SOCD (simultaneous opposing cardinal directions) check.
+//!
+//! SOCD cleaning happens in the controller firmware, so all we can observe is
+//! its output. The test has the user hold one direction, add the opposite
+//! one, and records what the D-pad/left stick reports. Doing this in both
+//! orders on both axes is enough to tell the common modes apart.
+//!
+//! The D-pad and left stick are found in the device's layout, so the test
+//! works on any device with one of them and is not applicable otherwise.
+
+use crate::models::{DeviceLayout, GamepadState, InputSample};
+use serde::Serialize;
+
+/// How far back from "now" samples are read when a step is recorded.
+pub const SOCD_SAMPLE_WINDOW_US: u64 = 100_000;
+
+/// Stick deflection that counts as a digital direction.
+const STICK_THRESHOLD: f32 = 0.5;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Neutral,
+    Left,
+    Right,
+    Up,
+    Down,
+    /// Both opposing directions reported at once.
+    Both,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SocdMode {
+    /// Opposing directions cancel out.
+    Neutral,
+    LastInputWins,
+    FirstInputWins,
+    /// Up wins over down regardless of order (vertical axis only).
+    UpPriority,
+    /// No cleaning, both directions are passed through.
+    Passthrough,
+    /// The two orders disagree in a way no known mode explains.
+    Inconsistent,
+    /// The device has neither a D-pad nor a stick.
+    NotApplicable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepKind {
+    /// Only `expected` is held, to confirm the input reaches us at all.
+    Baseline { expected: Direction },
+    /// `first` is held, then `second` is added.
+    Conflict { first: Direction, second: Direction },
+}
+
+struct SocdStep {
+    prompt: &'static str,
+    vertical: bool,
+    kind: StepKind,
+}
+
+impl SocdStep {
+    /// The direction held first and the one added second.
+    fn directions(&self) -> (Direction, Direction) {
+        match self.kind {
+            StepKind::Baseline { expected } => (expected, expected),
+            StepKind::Conflict { first, second } => (first, second),
+        }
+    }
+}
+
+const STEPS: [SocdStep; 8] = [
+    SocdStep {
+        prompt: "Hold LEFT",
+        vertical: false,
+        kind: StepKind::Baseline {
+            expected: Direction::Left,
+        },
+    },
+    SocdStep {
+        prompt: "Keep holding LEFT and also press RIGHT",
+        vertical: false,
+        kind: StepKind::Conflict {
+            first: Direction::Left,
+            second: Direction::Right,
+        },
+    },
+    SocdStep {
+        prompt: "Release everything, then hold RIGHT",
+        vertical: false,
+        kind: StepKind::Baseline {
+            expected: Direction::Right,
+        },
+    },
+    SocdStep {
+        prompt: "Keep holding RIGHT and also press LEFT",
+        vertical: false,
+        kind: StepKind::Conflict {
+            first: Direction::Right,
+            second: Direction::Left,
+        },
+    },
+    SocdStep {
+        prompt: "Release everything, then hold UP",
+        vertical: true,
+        kind: StepKind::Baseline {
+            expected: Direction::Up,
+        },
+    },
+    SocdStep {
+        prompt: "Keep holding UP and also press DOWN",
+        vertical: true,
+        kind: StepKind::Conflict {
+            first: Direction::Up,
+            second: Direction::Down,
+        },
+    },
+    SocdStep {
+        prompt: "Release everything, then hold DOWN",
+        vertical: true,
+        kind: StepKind::Baseline {
+            expected: Direction::Down,
+        },
+    },
+    SocdStep {
+        prompt: "Keep holding DOWN and also press UP",
+        vertical: true,
+        kind: StepKind::Conflict {
+            first: Direction::Down,
+            second: Direction::Up,
+        },
+    },
+];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StepObservation {
+    pub step: usize,
+    pub prompt: &'static str,
+    /// What the controller reported on the axis under test.
+    pub output: Direction,
+    /// The output did not change during the recording window.
+    pub stable: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SocdResult {
+    pub horizontal: SocdMode,
+    pub vertical: SocdMode,
+    /// Human-readable problems found along the way.
+    pub issues: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SocdTestStatus {
+    pub step: usize,
+    pub total_steps: usize,
+    /// What the user should do next, `None` once the test is complete.
+    pub prompt: Option<&'static str>,
+    pub observations: Vec<StepObservation>,
+    pub result: Option<SocdResult>,
+}
+
+/// Where a device reports the directions under test.
+#[derive(Debug, Clone, Copy)]
+struct Controls {
+    /// Up, down, left and right buttons.
+    dpad: Option<[usize; 4]>,
+    /// X and Y axes of the first stick.
+    stick: Option<(usize, usize)>,
+}
+
+/// A guided SOCD test in progress.
+pub struct SocdTest {
+    controls: Controls,
+    observations: Vec<StepObservation>,
+}
+
+impl SocdTest {
+    pub fn new(layout: &DeviceLayout) -> Self {
+        Self {
+            controls: Controls {
+                dpad: layout.dpad(),
+                stick: layout.sticks().first().map(|(_, x, y)| (*x, *y)),
+            },
+            observations: Vec::new(),
+        }
+    }
+
+    /// Whether the device has a D-pad or stick to test.
+    pub fn is_applicable(&self) -> bool {
+        self.controls.dpad.is_some() || self.controls.stick.is_some()
+    }
+
+    /// Also true right away when the test is not applicable.
+    pub fn is_complete(&self) -> bool {
+        !self.is_applicable() || self.observations.len() >= STEPS.len()
+    }
+
+    /// Records the current step from the samples of the last
+    /// `SOCD_SAMPLE_WINDOW_US` and moves on to the next one.
+    pub fn record_step(&mut self, samples: &[InputSample]) {
+        let step = self.observations.len();
+        let Some(definition) = STEPS.get(step).filter(|_| self.is_applicable()) else {
+            return;
+        };
+
+        let outputs: Vec<Direction> = samples
+            .iter()
+            .map(|sample| axis_output(&sample.state, &self.controls, definition.vertical))
+            .collect();
+        let output = outputs.last().copied().unwrap_or(Direction::Neutral);
+
+        self.observations.push(StepObservation {
+            step,
+            prompt: definition.prompt,
+            output,
+            stable: !outputs.is_empty() && outputs.iter().all(|o| *o == output),
+        });
+    }
+
+    pub fn status(&self) -> SocdTestStatus {
+        let step = self.observations.len();
+        SocdTestStatus {
+            step,
+            total_steps: STEPS.len(),
+            prompt: STEPS
+                .get(step)
+                .filter(|_| self.is_applicable())
+                .map(|s| s.prompt),
+            observations: self.observations.clone(),
+            result: self.is_complete().then(|| self.result()),
+        }
+    }
+
+    fn result(&self) -> SocdResult {
+        if !self.is_applicable() {
+            return SocdResult {
+                horizontal: SocdMode::NotApplicable,
+                vertical: SocdMode::NotApplicable,
+                issues: vec!["The device has no D-pad or stick to test".to_string()],
+            };
+        }
+
+        let mut issues = Vec::new();
+
+        for observation in &self.observations {
+            if !observation.stable {
+                issues.push(format!(
+                    "Output changed while recording \"{}\"",
+                    observation.prompt
+                ));
+            }
+            if let StepKind::Baseline { expected } = STEPS[observation.step].kind {
+                if observation.output != expected {
+                    issues.push(format!(
+                        "\"{}\" reported {:?} instead of {:?}, check the button mapping",
+                        observation.prompt, observation.output, expected
+                    ));
+                }
+            }
+        }
+
+        let horizontal = self.infer_axis(1, 3, false, &mut issues);
+        let vertical = self.infer_axis(5, 7, true, &mut issues);
+
+        SocdResult {
+            horizontal,
+            vertical,
+            issues,
+        }
+    }
+
+    /// Infers the mode of one axis from its two conflict steps, which hold
+    /// the opposing directions in opposite orders.
+    fn infer_axis(&self, a: usize, b: usize, vertical: bool, issues: &mut Vec<String>) -> SocdMode {
+        let (first_a, second_a) = STEPS[a].directions();
+        let (first_b, second_b) = STEPS[b].directions();
+        let (out_a, out_b) = (self.observations[a].output, self.observations[b].output);
+
+        let mode = match (out_a, out_b) {
+            (Direction::Neutral, Direction::Neutral) => SocdMode::Neutral,
+            (Direction::Both, Direction::Both) => SocdMode::Passthrough,
+            _ if out_a == second_a && out_b == second_b => SocdMode::LastInputWins,
+            _ if out_a == first_a && out_b == first_b => SocdMode::FirstInputWins,
+            (Direction::Up, Direction::Up) if vertical => SocdMode::UpPriority,
+            _ => SocdMode::Inconsistent,
+        };
+
+        if mode == SocdMode::Inconsistent {
+            let axis = if vertical { "Vertical" } else { "Horizontal" };
+            issues.push(format!(
+                "{} output was {:?} and {:?} for the two press orders, which matches no known SOCD mode",
+                axis, out_a, out_b
+            ));
+        }
+
+        mode
+    }
+}
+
+/// Reads the digital direction on one axis from the D-pad, falling back to
+/// the first stick for controllers in analog mode.
+fn axis_output(state: &GamepadState, controls: &Controls, vertical: bool) -> Direction {
+    let pressed = |index: Option<usize>| {
+        index.is_some_and(|index| state.buttons.get(index).copied().unwrap_or(false))
+    };
+    let axis = |index: Option<usize>| {
+        index.map_or(0.0, |index| state.axes.get(index).copied().unwrap_or(0.0))
+    };
+    let [up, down, left, right] = controls.dpad.map_or([None; 4], |dpad| dpad.map(Some));
+
+    let (negative, positive, value, negative_dir, positive_dir) = if vertical {
+        // Normalized Y is positive up
+        (
+            pressed(down),
+            pressed(up),
+            axis(controls.stick.map(|(_, y)| y)),
+            Direction::Down,
+            Direction::Up,
+        )
+    } else {
+        (
+            pressed(left),
+            pressed(right),
+            axis(controls.stick.map(|(x, _)| x)),
+            Direction::Left,
+            Direction::Right,
+        )
+    };
+
+    match (
+        negative || value <= -STICK_THRESHOLD,
+        positive || value >= STICK_THRESHOLD,
+    ) {
+        (true, true) => Direction::Both,
+        (true, false) => negative_dir,
+        (false, true) => positive_dir,
+        (false, false) => Direction::Neutral,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AxisKind;
+
+    fn sample(buttons: Vec<bool>, axes: Vec<f32>) -> InputSample {
+        InputSample {
+            timestamp_us: 0,
+            state: GamepadState {
+                raw_axes: vec![0; axes.len()],
+                axis_normalization: Vec::new(),
+                buttons,
+                axes,
+            },
+        }
+    }
+
+    #[test]
+    fn reads_the_layouts_dpad() {
+        // A hat after two buttons: up, right, down, left at 2 to 5
+        let mut layout = DeviceLayout::generic(2, 0);
+        layout.push_hat_buttons(0);
+        let mut test = SocdTest::new(&layout);
+        assert!(test.is_applicable());
+
+        let left = vec![false, false, false, false, false, true];
+        let right = vec![false, false, false, true, false, false];
+        let neutral = vec![false; 6];
+        for buttons in [&left, &neutral, &right, &neutral] {
+            test.record_step(&[sample(buttons.clone(), Vec::new())]);
+        }
+        let up = vec![false, false, true, false, false, false];
+        let down = vec![false, false, false, false, true, false];
+        for buttons in [&up, &up, &down, &up] {
+            test.record_step(&[sample(buttons.clone(), Vec::new())]);
+        }
+
+        let result = test.status().result.unwrap();
+        assert_eq!(result.horizontal, SocdMode::Neutral);
+        assert_eq!(result.vertical, SocdMode::UpPriority);
+        assert!(result.issues.is_empty(), "{:?}", result.issues);
+    }
+
+    #[test]
+    fn falls_back_to_the_stick() {
+        let mut layout = DeviceLayout::default();
+        layout.push_axis("x", AxisKind::Stick);
+        layout.push_axis("y", AxisKind::Stick);
+        let mut test = SocdTest::new(&layout);
+
+        test.record_step(&[sample(Vec::new(), vec![-1.0, 0.0])]);
+        assert_eq!(test.status().observations[0].output, Direction::Left);
+    }
+
+    #[test]
+    fn not_applicable_without_dpad_or_stick() {
+        let test = SocdTest::new(&DeviceLayout::generic(8, 2));
+        assert!(!test.is_applicable());
+        let status = test.status();
+        assert_eq!(status.prompt, None);
+        let result = status.result.unwrap();
+        assert_eq!(result.horizontal, SocdMode::NotApplicable);
+        assert_eq!(result.vertical, SocdMode::NotApplicable);
+    }
+}
@@ -0,0 +1,333 @@
+//! Guided trigger range and linearity test.
+//!
+//! The test is timed from the moment it starts: the triggers rest for
+//! [`TRIGGER_REST_US`], are pressed slowly and evenly to the bottom over
+//! [`TRIGGER_PRESS_US`] while the UI shows a filling bar to follow, then are
+//! held down for [`TRIGGER_HOLD_US`].
+
+use crate::models::{DeviceLayout, InputSample};
+use serde::{Deserialize, Serialize};
+
+pub const TRIGGER_REST_US: u64 = 1_000_000;
+pub const TRIGGER_PRESS_US: u64 = 3_000_000;
+pub const TRIGGER_HOLD_US: u64 = 1_000_000;
+pub const TRIGGER_TEST_US: u64 = TRIGGER_REST_US + TRIGGER_PRESS_US + TRIGGER_HOLD_US;
+
+/// How far above the resting value the trigger must be to count as moving,
+/// and how close to the maximum to count as bottomed out.
+const MOVEMENT_EPSILON: f32 = 0.02;
+
+/// Points kept in the value-vs-time curve.
+const MAX_CURVE_POINTS: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct TriggerThresholds {
+    /// Highest acceptable value while released.
+    pub max_resting: f32,
+    /// Lowest acceptable value when fully pressed.
+    pub min_full_press: f32,
+    /// Largest acceptable fraction of the press spent before the value moves.
+    pub max_dead_start: f32,
+    /// Largest acceptable fraction of the press spent already at the maximum.
+    pub max_dead_end: f32,
+    /// Largest acceptable deviation from a straight ramp, as a fraction of
+    /// the used range.
+    pub max_linearity_error: f32,
+}
+
+impl Default for TriggerThresholds {
+    fn default() -> Self {
+        Self {
+            max_resting: 0.05,
+            min_full_press: 0.95,
+            max_dead_start: 0.25,
+            max_dead_end: 0.25,
+            max_linearity_error: 0.15,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct CurvePoint {
+    /// Microseconds since the press phase started.
+    pub t_us: u64,
+    pub value: f32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TriggerResult {
+    /// `left` and `right` on the standard layout, the axis name otherwise.
+    pub trigger: String,
+    pub index: usize,
+    pub resting_value: f32,
+    pub resting_raw: Option<i32>,
+    pub max_value: f32,
+    pub max_raw: Option<i32>,
+    /// Fraction of the press phase before the value left rest.
+    pub dead_start: f32,
+    /// Fraction of the press phase during which the value was already at
+    /// its maximum.
+    pub dead_end: f32,
+    /// Largest deviation from a straight line between leaving rest and
+    /// reaching the maximum, as a fraction of `max_value - resting_value`.
+    pub linearity_error: f32,
+    pub curve: Vec<CurvePoint>,
+    pub passed: bool,
+    pub failures: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TriggerReport {
+    pub thresholds: TriggerThresholds,
+    pub samples: usize,
+    /// False if the device's layout has no trigger axis.
+    pub applicable: bool,
+    pub triggers: Vec<TriggerResult>,
+    pub passed: bool,
+}
+
+/// Analyzes a trigger test whose rest phase started at `started_us`, for
+/// every trigger in `layout`.
+pub fn analyze_triggers(
+    samples: &[InputSample],
+    layout: &DeviceLayout,
+    started_us: u64,
+    thresholds: TriggerThresholds,
+) -> TriggerReport {
+    let axes = layout.triggers();
+    let triggers: Vec<TriggerResult> = axes
+        .iter()
+        .filter_map(|(trigger, index)| {
+            analyze_trigger(samples, started_us, trigger, *index, &thresholds)
+        })
+        .collect();
+
+    TriggerReport {
+        thresholds,
+        samples: samples.len(),
+        applicable: !axes.is_empty(),
+        passed: !triggers.is_empty() && triggers.iter().all(|result| result.passed),
+        triggers,
+    }
+}
+
+fn analyze_trigger(
+    samples: &[InputSample],
+    started_us: u64,
+    trigger: &str,
+    index: usize,
+    thresholds: &TriggerThresholds,
+) -> Option<TriggerResult> {
+    let press_start = started_us.saturating_add(TRIGGER_REST_US);
+    let press_end = press_start.saturating_add(TRIGGER_PRESS_US);
+
+    // (timestamp, value, raw) for every sample that has this axis
+    let points: Vec<(u64, f32, Option<i32>)> = samples
+        .iter()
+        .filter(|sample| sample.timestamp_us >= started_us)
+        .filter_map(|sample| {
+            let value = *sample.state.axes.get(index)?;
+            let raw = sample.state.raw_axes.get(index).copied();
+            Some((sample.timestamp_us, value, raw))
+        })
+        .collect();
+    let rest: Vec<&(u64, f32, Option<i32>)> =
+        points.iter().filter(|(t, _, _)| *t < press_start).collect();
+    let press: Vec<&(u64, f32, Option<i32>)> = points
+        .iter()
+        .filter(|(t, _, _)| (press_start..press_end).contains(t))
+        .collect();
+    if rest.is_empty() || press.is_empty() {
+        return None;
+    }
+
+    let (_, resting_value, resting_raw) = **rest.iter().min_by(|a, b| a.1.total_cmp(&b.1))?;
+    let rest_noise = rest
+        .iter()
+        .map(|(_, value, _)| *value)
+        .fold(resting_value, f32::max);
+    let (_, max_value, max_raw) = *points.iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    let left_rest_at = press
+        .iter()
+        .find(|(_, value, _)| *value > rest_noise + MOVEMENT_EPSILON)
+        .map(|(t, value, _)| (*t, *value));
+    let bottomed_at = press
+        .iter()
+        .find(|(_, value, _)| *value >= max_value - MOVEMENT_EPSILON)
+        .map(|(t, value, _)| (*t, *value));
+
+    let fraction = |us: u64| us as f32 / TRIGGER_PRESS_US as f32;
+    let dead_start = left_rest_at.map_or(1.0, |(t, _)| fraction(t - press_start));
+    let dead_end = bottomed_at.map_or(0.0, |(t, _)| fraction(press_end.saturating_sub(t)));
+
+    let range = max_value - resting_value;
+    let linearity_error = match (left_rest_at, bottomed_at) {
+        (Some((t0, _)), Some((t1, _))) if t1 > t0 && range > 0.0 => press
+            .iter()
+            .filter(|(t, _, _)| (t0..=t1).contains(t))
+            .map(|(t, value, _)| {
+                let progress = (t - t0) as f32 / (t1 - t0) as f32;
+                let expected = resting_value + progress * range;
+                (value - expected).abs() / range
+            })
+            .fold(0.0, f32::max),
+        _ => 0.0,
+    };
+
+    let stride = press.len().div_ceil(MAX_CURVE_POINTS);
+    let curve = press
+        .iter()
+        .step_by(stride)
+        .map(|(t, value, _)| CurvePoint {
+            t_us: t - press_start,
+            value: *value,
+        })
+        .collect();
+
+    let mut failures = Vec::new();
+    if resting_value > thresholds.max_resting {
+        failures.push(format!(
+            "Rests at {:.3}, above {:.3}",
+            resting_value, thresholds.max_resting
+        ));
+    }
+    if max_value < thresholds.min_full_press {
+        failures.push(format!(
+            "Only reaches {:.3}, below {:.3}",
+            max_value, thresholds.min_full_press
+        ));
+    }
+    if dead_start > thresholds.max_dead_start {
+        failures.push(format!(
+            "{:.0}% of the press before the value moved",
+            dead_start * 100.0
+        ));
+    }
+    if dead_end > thresholds.max_dead_end {
+        failures.push(format!(
+            "{:.0}% of the press already at maximum",
+            dead_end * 100.0
+        ));
+    }
+    if linearity_error > thresholds.max_linearity_error {
+        failures.push(format!(
+            "Deviates {:.0}% from a linear ramp",
+            linearity_error * 100.0
+        ));
+    }
+
+    Some(TriggerResult {
+        trigger: trigger.to_string(),
+        index,
+        resting_value,
+        resting_raw,
+        max_value,
+        max_raw,
+        dead_start,
+        dead_end,
+        linearity_error,
+        curve,
+        passed: failures.is_empty(),
+        failures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AxisKind, GamepadState};
+
+    const STARTED_US: u64 = 10_000_000;
+
+    /// A full test sampled every 10ms, with the trigger at `press(progress)`
+    /// while it is being pressed.
+    fn run(press: fn(f32) -> f32) -> TriggerReport {
+        let mut layout = DeviceLayout::default();
+        layout.push_axis("left_trigger", AxisKind::Trigger);
+        let samples: Vec<InputSample> = (0..=TRIGGER_TEST_US / 10_000)
+            .map(|step| {
+                let t = step * 10_000;
+                let value = match t.checked_sub(TRIGGER_REST_US) {
+                    None => 0.0,
+                    Some(pressed) => press((pressed as f32 / TRIGGER_PRESS_US as f32).min(1.0)),
+                };
+                InputSample {
+                    timestamp_us: STARTED_US + t,
+                    state: GamepadState {
+                        buttons: Vec::new(),
+                        axes: vec![value],
+                        raw_axes: vec![(value * 255.0).round() as i32],
+                        axis_normalization: Vec::new(),
+                    },
+                }
+            })
+            .collect();
+        analyze_triggers(&samples, &layout, STARTED_US, TriggerThresholds::default())
+    }
+
+    #[test]
+    fn linear_press() {
+        let report = run(|progress| progress);
+        assert!(report.applicable);
+        assert!(report.passed);
+
+        let trigger = &report.triggers[0];
+        assert_eq!(trigger.trigger, "left");
+        assert_eq!((trigger.resting_value, trigger.resting_raw), (0.0, Some(0)));
+        assert_eq!((trigger.max_value, trigger.max_raw), (1.0, Some(255)));
+        assert!(trigger.dead_start < 0.05, "{}", trigger.dead_start);
+        assert!(trigger.dead_end < 0.05, "{}", trigger.dead_end);
+        assert!(
+            trigger.linearity_error < 0.05,
+            "{}",
+            trigger.linearity_error
+        );
+        // 300 press samples thinned to every other one
+        assert_eq!(trigger.curve.len(), 150);
+        assert_eq!(trigger.curve[0].t_us, 0);
+        assert!(trigger.failures.is_empty());
+    }
+
+    #[test]
+    fn curved_press() {
+        // Slow to start, then rushing to the bottom
+        let report = run(|progress| progress * progress);
+        assert!(!report.passed);
+
+        let trigger = &report.triggers[0];
+        assert!(trigger.dead_start < 0.25, "{}", trigger.dead_start);
+        assert!(
+            trigger.linearity_error > 0.15,
+            "{}",
+            trigger.linearity_error
+        );
+        assert_eq!(trigger.failures.len(), 1);
+        assert!(
+            trigger.failures[0].contains("linear"),
+            "{:?}",
+            trigger.failures
+        );
+    }
+
+    #[test]
+    fn hair_trigger() {
+        // Bottoms out a fifth of the way in
+        let report = run(|progress| (progress * 5.0).min(1.0));
+        let trigger = &report.triggers[0];
+        assert!(trigger.dead_end > 0.75, "{}", trigger.dead_end);
+        assert!(!trigger.passed);
+        assert!(trigger.failures[0].contains("already at maximum"));
+
+        // A layout without triggers has nothing to check
+        let report = analyze_triggers(
+            &[],
+            &DeviceLayout::generic(1, 1),
+            STARTED_US,
+            TriggerThresholds::default(),
+        );
+        assert!(!report.applicable);
+        assert!(!report.passed);
+    }
+}
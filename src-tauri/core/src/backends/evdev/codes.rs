@@ -0,0 +1,150 @@
+//! Event types and codes from `linux/input-event-codes.h` that matter for
+//! game controllers, and their kernel names.
+
+use crate::models::{AxisKind, ButtonKind};
+
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const EV_ABS: u16 = 0x03;
+
+pub const SYN_REPORT: u16 = 0x00;
+pub const SYN_DROPPED: u16 = 0x03;
+
+/// `BTN_JOYSTICK` up to `BTN_DEAD` and `BTN_GAMEPAD` up to `BTN_THUMBR`.
+/// Devices with any of these keys are treated as controllers, the same rule
+/// udev uses for `ID_INPUT_JOYSTICK`.
+const CONTROLLER_KEYS: std::ops::RangeInclusive<u16> = 0x120..=0x13f;
+
+const ABS_NAMES: [(u16, &str); 27] = [
+    (0x00, "ABS_X"),
+    (0x01, "ABS_Y"),
+    (0x02, "ABS_Z"),
+    (0x03, "ABS_RX"),
+    (0x04, "ABS_RY"),
+    (0x05, "ABS_RZ"),
+    (0x06, "ABS_THROTTLE"),
+    (0x07, "ABS_RUDDER"),
+    (0x08, "ABS_WHEEL"),
+    (0x09, "ABS_GAS"),
+    (0x0a, "ABS_BRAKE"),
+    (0x10, "ABS_HAT0X"),
+    (0x11, "ABS_HAT0Y"),
+    (0x12, "ABS_HAT1X"),
+    (0x13, "ABS_HAT1Y"),
+    (0x14, "ABS_HAT2X"),
+    (0x15, "ABS_HAT2Y"),
+    (0x16, "ABS_HAT3X"),
+    (0x17, "ABS_HAT3Y"),
+    (0x18, "ABS_PRESSURE"),
+    (0x19, "ABS_DISTANCE"),
+    (0x1a, "ABS_TILT_X"),
+    (0x1b, "ABS_TILT_Y"),
+    (0x1c, "ABS_TOOL_WIDTH"),
+    (0x20, "ABS_VOLUME"),
+    (0x21, "ABS_PROFILE"),
+    (0x28, "ABS_MISC"),
+];
+
+const KEY_NAMES: [(u16, &str); 45] = [
+    (0x071, "KEY_MUTE"),
+    (0x072, "KEY_VOLUMEDOWN"),
+    (0x073, "KEY_VOLUMEUP"),
+    (0x074, "KEY_POWER"),
+    (0x08b, "KEY_MENU"),
+    (0x09e, "KEY_BACK"),
+    (0x0a7, "KEY_RECORD"),
+    (0x0ac, "KEY_HOMEPAGE"),
+    (0x110, "BTN_LEFT"),
+    (0x111, "BTN_RIGHT"),
+    (0x112, "BTN_MIDDLE"),
+    (0x113, "BTN_SIDE"),
+    (0x114, "BTN_EXTRA"),
+    (0x120, "BTN_TRIGGER"),
+    (0x121, "BTN_THUMB"),
+    (0x122, "BTN_THUMB2"),
+    (0x123, "BTN_TOP"),
+    (0x124, "BTN_TOP2"),
+    (0x125, "BTN_PINKIE"),
+    (0x126, "BTN_BASE"),
+    (0x127, "BTN_BASE2"),
+    (0x128, "BTN_BASE3"),
+    (0x129, "BTN_BASE4"),
+    (0x12a, "BTN_BASE5"),
+    (0x12b, "BTN_BASE6"),
+    (0x12f, "BTN_DEAD"),
+    (0x130, "BTN_SOUTH"),
+    (0x131, "BTN_EAST"),
+    (0x132, "BTN_C"),
+    (0x133, "BTN_NORTH"),
+    (0x134, "BTN_WEST"),
+    (0x135, "BTN_Z"),
+    (0x136, "BTN_TL"),
+    (0x137, "BTN_TR"),
+    (0x138, "BTN_TL2"),
+    (0x139, "BTN_TR2"),
+    (0x13a, "BTN_SELECT"),
+    (0x13b, "BTN_START"),
+    (0x13c, "BTN_MODE"),
+    (0x13d, "BTN_THUMBL"),
+    (0x13e, "BTN_THUMBR"),
+    (0x220, "BTN_DPAD_UP"),
+    (0x221, "BTN_DPAD_DOWN"),
+    (0x222, "BTN_DPAD_LEFT"),
+    (0x223, "BTN_DPAD_RIGHT"),
+];
+
+/// `BTN_TRIGGER_HAPPY1` to `BTN_TRIGGER_HAPPY40`, used for extra buttons
+/// such as paddles.
+const TRIGGER_HAPPY: std::ops::RangeInclusive<u16> = 0x2c0..=0x2e7;
+
+/// Kernel name of an `EV_KEY` code, or `KEY_0x...` for codes not listed.
+pub fn key_name(code: u16) -> String {
+    if let Some((_, name)) = KEY_NAMES.iter().find(|(known, _)| *known == code) {
+        return name.to_string();
+    }
+    if TRIGGER_HAPPY.contains(&code) {
+        return format!("BTN_TRIGGER_HAPPY{}", code - TRIGGER_HAPPY.start() + 1);
+    }
+    if (0x100..=0x109).contains(&code) {
+        return format!("BTN_{}", code - 0x100);
+    }
+    format!("KEY_{:#05x}", code)
+}
+
+/// Kernel name of an `EV_ABS` code, or `ABS_0x...` for codes not listed.
+pub fn abs_name(code: u16) -> String {
+    ABS_NAMES
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("ABS_{:#04x}", code))
+}
+
+/// Whether a device with these `EV_KEY` codes is a joystick or gamepad.
+pub fn is_controller(keys: impl IntoIterator<Item = u16>) -> bool {
+    keys.into_iter().any(|code| CONTROLLER_KEYS.contains(&code))
+}
+
+/// What an `EV_KEY` code is on a gamepad, following the kernel's gamepad
+/// documentation.
+pub fn key_kind(code: u16) -> ButtonKind {
+    match code {
+        0x130..=0x135 => ButtonKind::Face,
+        0x136..=0x139 => ButtonKind::Shoulder,
+        0x13a..=0x13c | 0x08b | 0x09e | 0x0a7 | 0x0ac => ButtonKind::Menu,
+        0x13d | 0x13e => ButtonKind::Stick,
+        0x220..=0x223 => ButtonKind::Dpad,
+        _ => ButtonKind::Other,
+    }
+}
+
+/// What an `EV_ABS` code is on a gamepad. `ABS_Z` and `ABS_RZ` are the
+/// analog triggers there, `ABS_GAS` and `ABS_BRAKE` the pedals of wheels.
+pub fn abs_kind(code: u16) -> AxisKind {
+    match code {
+        0x00 | 0x01 | 0x03 | 0x04 => AxisKind::Stick,
+        0x02 | 0x05 | 0x09 | 0x0a => AxisKind::Trigger,
+        0x10..=0x17 => AxisKind::Hat,
+        _ => AxisKind::Other,
+    }
+}
@@ -0,0 +1,198 @@
+//! Reads device recordings made with `evemu-record`, so the evdev backend
+//! can be driven from a captured event stream without the hardware.
+//!
+//! Only the lines the backend needs are interpreted:
+//!
+//! ```text
+//! N: Microsoft X-Box 360 pad                 device name
+//! I: 0003 045e 028e 0114                     bus, vendor, product, version (hex)
+//! B: 01 00 00 00 00 00 00 00 00              event code bitmask for a type (hex bytes)
+//! A: 00 -32768 32767 16 128 0                abs code, min, max, fuzz, flat, resolution
+//! E: 0.004012 0003 0000 -1234                time, type, code, value
+//! ```
+//!
+//! `B:` lines for one type continue each other's bytes. Comments (`#`) and
+//! other line kinds are skipped.
+
+use super::codes::{EV_ABS, EV_KEY};
+use super::{AbsInfo, EvdevDescription, RawEvent};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A parsed `evemu-record` capture.
+#[derive(Debug, Clone)]
+pub struct EvemuRecording {
+    pub description: EvdevDescription,
+    /// Events in recorded order, timestamps as recorded.
+    pub events: Vec<RawEvent>,
+}
+
+impl EvemuRecording {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut name = String::new();
+        let mut ids = (None, None);
+        let mut bitmasks: BTreeMap<u16, Vec<u8>> = BTreeMap::new();
+        let mut axes: Vec<(u16, AbsInfo)> = Vec::new();
+        let mut events = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let Some((kind, rest)) = line.split_once(':') else {
+                continue;
+            };
+            // Event lines end in a comment naming the code
+            let rest = rest.split('#').next().unwrap_or("");
+            let fail = |e: String| format!("Invalid evemu recording, line {}: {}", index + 1, e);
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            match kind {
+                "N" => name = line[2..].trim().to_string(),
+                "I" => {
+                    let vendor = fields.get(1).map(|field| parse_hex(field)).transpose();
+                    let product = fields.get(2).map(|field| parse_hex(field)).transpose();
+                    ids = (vendor.map_err(fail)?, product.map_err(fail)?);
+                }
+                "B" => {
+                    let (event_type, bytes) = fields
+                        .split_first()
+                        .ok_or_else(|| fail("missing event type".to_string()))?;
+                    let event_type = parse_hex(event_type).map_err(fail)?;
+                    let bytes = bytes
+                        .iter()
+                        .map(|byte| u8::from_str_radix(byte, 16).map_err(|e| e.to_string()))
+                        .collect::<Result<Vec<u8>, String>>()
+                        .map_err(fail)?;
+                    bitmasks.entry(event_type).or_default().extend(bytes);
+                }
+                "A" => axes.push(parse_absinfo(&fields).map_err(fail)?),
+                "E" => events.push(parse_event(&fields).map_err(fail)?),
+                _ => {}
+            }
+        }
+
+        // Axes also appear in the EV_ABS bitmask, but only `A:` lines carry
+        // their ranges
+        let keys = bitmasks
+            .get(&EV_KEY)
+            .map(|bits| set_bits(bits).map(|code| (code, false)).collect())
+            .unwrap_or_default();
+        if axes.is_empty() {
+            if let Some(bits) = bitmasks.get(&EV_ABS) {
+                axes = set_bits(bits)
+                    .map(|code| (code, AbsInfo::default()))
+                    .collect();
+            }
+        }
+        axes.sort_by_key(|(code, _)| *code);
+
+        Ok(Self {
+            description: EvdevDescription {
+                name,
+                vendor_id: ids.0,
+                product_id: ids.1,
+                keys,
+                axes,
+            },
+            events,
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&text)
+    }
+}
+
+fn parse_hex(field: &str) -> Result<u16, String> {
+    u16::from_str_radix(field, 16).map_err(|e| format!("'{}': {}", field, e))
+}
+
+fn parse_int(field: Option<&&str>) -> Result<i32, String> {
+    let field = field.ok_or("missing value")?;
+    field.parse().map_err(|e| format!("'{}': {}", field, e))
+}
+
+fn parse_absinfo(fields: &[&str]) -> Result<(u16, AbsInfo), String> {
+    let code = parse_hex(fields.first().ok_or("missing axis code")?)?;
+    let min = parse_int(fields.get(1))?;
+    let max = parse_int(fields.get(2))?;
+    // Older evemu versions don't write the resolution
+    let resolution = match fields.get(5) {
+        Some(_) => parse_int(fields.get(5))?,
+        None => 0,
+    };
+    Ok((
+        code,
+        AbsInfo {
+            // Recordings don't include the value at the time they started
+            value: 0.max(min).min(max),
+            min,
+            max,
+            fuzz: parse_int(fields.get(3))?,
+            flat: parse_int(fields.get(4))?,
+            resolution,
+        },
+    ))
+}
+
+fn parse_event(fields: &[&str]) -> Result<RawEvent, String> {
+    let [time, event_type, code, value] = fields else {
+        return Err("expected time, type, code and value".to_string());
+    };
+    let (seconds, micros) = time.split_once('.').unwrap_or((time, "0"));
+    let seconds: u64 = seconds.parse().map_err(|e| format!("'{}': {}", time, e))?;
+    let micros: u64 = micros.parse().map_err(|e| format!("'{}': {}", time, e))?;
+    let time_us = seconds
+        .checked_mul(1_000_000)
+        .and_then(|us| us.checked_add(micros))
+        .ok_or_else(|| format!("'{}': time out of range", time))?;
+    Ok(RawEvent {
+        time_us,
+        event_type: parse_hex(event_type)?,
+        code: parse_hex(code)?,
+        value: value.parse().map_err(|e| format!("'{}': {}", value, e))?,
+    })
+}
+
+/// Codes of the bits set in a little-endian bitmask.
+fn set_bits(bytes: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    bytes.iter().enumerate().flat_map(|(index, byte)| {
+        (0..8)
+            .filter(move |bit| byte & (1 << bit) != 0)
+            .map(move |bit| (index * 8 + bit) as u16)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_times() {
+        let event = parse_event(&["12.004012", "0001", "0130", "0001"]).unwrap();
+        assert_eq!(event.time_us, 12_004_012);
+        assert_eq!(
+            (event.event_type, event.code, event.value),
+            (EV_KEY, 0x130, 1)
+        );
+        assert_eq!(
+            parse_event(&["3", "0000", "0000", "0"]).unwrap().time_us,
+            3_000_000
+        );
+    }
+
+    #[test]
+    fn event_times_out_of_range() {
+        // Overflows the multiplication, then only the addition
+        for time in ["18446744073710.000000", "18446744073709.999999"] {
+            let error = EvemuRecording::parse(&format!("E: {} 0000 0000 0000", time)).unwrap_err();
+            assert_eq!(
+                error,
+                format!(
+                    "Invalid evemu recording, line 1: '{}': time out of range",
+                    time
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Reading real device nodes through the `evdev` crate.
+
+use super::codes;
+use super::{AbsInfo, CurrentState, EvdevDescription, RawEvent};
+use evdev::raw_stream::RawDevice;
+use nix::libc;
+use std::collections::{BTreeSet, HashSet};
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+const INPUT_DIR: &str = "/dev/input";
+
+/// How often `/dev/input` is checked for new device nodes.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(super) struct LiveDevice {
+    /// Read raw, so `SYN_DROPPED` reaches the decoder.
+    device: RawDevice,
+    path: PathBuf,
+}
+
+impl LiveDevice {
+    /// Opens `path` without blocking reads, or `None` if it isn't a
+    /// controller.
+    fn open(path: &Path) -> io::Result<Option<(Self, EvdevDescription)>> {
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)?;
+        let device = RawDevice::from_fd(file.into())?;
+        let keys: Vec<u16> = device
+            .supported_keys()
+            .map(|keys| keys.iter().map(|key| key.code()).collect())
+            .unwrap_or_default();
+        if !codes::is_controller(keys.iter().copied()) {
+            return Ok(None);
+        }
+
+        let pressed = device.get_key_state()?;
+        let axes = device
+            .get_absinfo()?
+            .map(|(code, info)| {
+                let info = AbsInfo {
+                    value: info.value(),
+                    min: info.minimum(),
+                    max: info.maximum(),
+                    fuzz: info.fuzz(),
+                    flat: info.flat(),
+                    resolution: info.resolution(),
+                };
+                (code.0, info)
+            })
+            .collect();
+        let id = device.input_id();
+        let description = EvdevDescription {
+            name: device.name().unwrap_or("Unknown evdev device").to_string(),
+            vendor_id: Some(id.vendor()),
+            product_id: Some(id.product()),
+            keys: keys
+                .into_iter()
+                .map(|code| (code, pressed.contains(evdev::KeyCode(code))))
+                .collect(),
+            axes,
+        };
+
+        Ok(Some((
+            Self {
+                device,
+                path: path.to_path_buf(),
+            },
+            description,
+        )))
+    }
+
+    /// Events the kernel has queued, without blocking, `SYN_DROPPED`
+    /// included. An error means the device is gone.
+    pub(super) fn read_events(&mut self) -> io::Result<Vec<RawEvent>> {
+        match self.device.fetch_events() {
+            Ok(events) => Ok(events
+                .map(|event| RawEvent {
+                    time_us: event
+                        .timestamp()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |time| time.as_micros() as u64),
+                    event_type: event.event_type().0,
+                    code: event.code(),
+                    value: event.value(),
+                })
+                .collect()),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads every key and axis with `EVIOCGKEY` and `EVIOCGABS`.
+    pub(super) fn current_state(&self) -> io::Result<CurrentState> {
+        let pressed = self.device.get_key_state()?;
+        let keys = self
+            .device
+            .supported_keys()
+            .map(|keys| {
+                keys.iter()
+                    .map(|key| (key.code(), pressed.contains(key)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let axes = self
+            .device
+            .get_absinfo()?
+            .map(|(code, info)| (code.0, info.value()))
+            .collect();
+        Ok(CurrentState { keys, axes })
+    }
+
+    pub(super) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Finds controller device nodes that haven't been opened yet.
+pub(super) struct Scanner {
+    /// Nodes found not to be controllers.
+    ignored: HashSet<PathBuf>,
+    last_scan: Option<Instant>,
+}
+
+impl Scanner {
+    pub(super) fn new() -> Option<Self> {
+        if let Err(e) = std::fs::read_dir(INPUT_DIR) {
+            warn!(error = %e, "Can't read {}, evdev backend disabled", INPUT_DIR);
+            return None;
+        }
+        Some(Self {
+            ignored: HashSet::new(),
+            last_scan: None,
+        })
+    }
+
+    /// Whether the next scan is due.
+    pub(super) fn due(&self) -> bool {
+        self.last_scan
+            .is_none_or(|at| at.elapsed() >= SCAN_INTERVAL)
+    }
+
+    /// Opens the controllers among the nodes that aren't in `open` yet.
+    pub(super) fn scan(&mut self, open: &HashSet<PathBuf>) -> Vec<(LiveDevice, EvdevDescription)> {
+        self.last_scan = Some(Instant::now());
+        let Ok(entries) = std::fs::read_dir(INPUT_DIR) else {
+            return Vec::new();
+        };
+        let present: BTreeSet<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("event"))
+            })
+            .collect();
+        // Nodes that went away may come back as a different device
+        self.ignored.retain(|path| present.contains(path));
+
+        let mut opened = Vec::new();
+        for path in present {
+            if open.contains(&path) || self.ignored.contains(&path) {
+                continue;
+            }
+            match LiveDevice::open(&path) {
+                Ok(Some(device)) => opened.push(device),
+                Ok(None) => {
+                    self.ignored.insert(path);
+                }
+                // Usually missing permissions (not in the `input` group),
+                // retried on the next scan in case that changes
+                Err(e) => debug!(path = %path.display(), error = %e, "Can't open evdev device"),
+            }
+        }
+        opened
+    }
+}
@@ -0,0 +1,719 @@
+//! Raw Linux input devices (`/dev/input/event*`).
+//!
+//! SDL's GameController layer maps devices onto a fixed layout and drops the
+//! kernel's event codes and timestamps. This backend keeps them: every
+//! `EV_KEY` code a device advertises becomes a button and every `EV_ABS` code
+//! an axis, both in code order, axes are normalized by their absinfo range,
+//! and each `SYN_REPORT` is an input report stamped with the kernel's event
+//! time. `raw_layout` tells which code sits at which index.
+//!
+//! Devices are read either live (Linux only) or from `evemu-record`
+//! captures, which play back through the same decoding against a clock, so
+//! the backend can be exercised without hardware.
+//!
+//! When the kernel's buffer overflows it sends `SYN_DROPPED`. Events up to
+//! the next `SYN_REPORT` are discarded, then live devices re-read every key
+//! and axis from the kernel. A recording can't be asked for its state, so
+//! after a drop it keeps what the lost events left behind, e.g. a button
+//! stuck pressed because its release was dropped.
+
+use super::{BackendCapabilities, HotplugEvent, InputBackend, InputReport, ScriptClock};
+use crate::models::{
+    AxisKind, AxisNormalization, DeviceLayout, GamepadInfo, GamepadState, RawAxis, RawButton,
+    RawInputLayout,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+pub mod codes;
+pub mod evemu;
+#[cfg(target_os = "linux")]
+mod live;
+
+pub use evemu::EvemuRecording;
+
+use codes::{EV_ABS, EV_KEY, EV_SYN, SYN_DROPPED, SYN_REPORT};
+
+/// One kernel `struct input_event`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawEvent {
+    /// Kernel timestamp in microseconds.
+    pub time_us: u64,
+    pub event_type: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+/// Mirrors the kernel's `struct input_absinfo`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AbsInfo {
+    pub value: i32,
+    pub min: i32,
+    pub max: i32,
+    pub fuzz: i32,
+    pub flat: i32,
+    pub resolution: i32,
+}
+
+/// What a device reports about itself when opened.
+#[derive(Debug, Clone)]
+pub struct EvdevDescription {
+    pub name: String,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    /// Every `EV_KEY` code and whether it is currently pressed, in code order.
+    pub keys: Vec<(u16, bool)>,
+    /// Every `EV_ABS` code and its absinfo, in code order.
+    pub axes: Vec<(u16, AbsInfo)>,
+}
+
+/// Every key and axis value of a device, read from the kernel rather than
+/// from events.
+#[derive(Debug, Clone, Default)]
+struct CurrentState {
+    /// `EV_KEY` codes and whether they are pressed.
+    keys: Vec<(u16, bool)>,
+    /// `EV_ABS` codes and their values.
+    axes: Vec<(u16, i32)>,
+}
+
+/// Plays a recording's events back at their recorded pace.
+struct Playback {
+    events: Vec<RawEvent>,
+    /// Index of the next event to return.
+    cursor: usize,
+    /// Timestamp of the first event, playback time 0.
+    start_us: u64,
+}
+
+impl Playback {
+    fn new(events: Vec<RawEvent>) -> Self {
+        Self {
+            start_us: events.first().map_or(0, |event| event.time_us),
+            events,
+            cursor: 0,
+        }
+    }
+
+    /// Events due by `elapsed` that haven't been returned yet.
+    fn events_until(&mut self, elapsed: Duration) -> Vec<RawEvent> {
+        let until_us = self
+            .start_us
+            .saturating_add(u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX));
+        let due = self.events[self.cursor..]
+            .iter()
+            .take_while(|event| event.time_us <= until_us)
+            .count();
+        let events = self.events[self.cursor..self.cursor + due].to_vec();
+        self.cursor += due;
+        events
+    }
+}
+
+enum Source {
+    #[cfg(target_os = "linux")]
+    Live(Box<live::LiveDevice>),
+    Recorded(Playback),
+}
+
+impl Source {
+    /// Events since the previous read, `elapsed` being the backend's
+    /// playback clock. An error means the device is gone.
+    fn read_events(&mut self, elapsed: Duration) -> std::io::Result<Vec<RawEvent>> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Self::Live(live) => live.read_events(),
+            Self::Recorded(playback) => Ok(playback.events_until(elapsed)),
+        }
+    }
+
+    /// Every key's and axis' current value, or `None` for recordings, which
+    /// only have their events.
+    fn current_state(&self) -> Option<CurrentState> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Self::Live(live) => live
+                .current_state()
+                .inspect_err(|e| debug!(error = %e, "Can't resync evdev device"))
+                .ok(),
+            Self::Recorded(_) => None,
+        }
+    }
+}
+
+/// One device's state, updated a whole `SYN_REPORT` at a time the way the
+/// kernel intends events to be consumed.
+struct EvdevDevice {
+    source: Source,
+    name: String,
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+    layout: RawInputLayout,
+    /// `layout` as lowercase kernel names with their kinds.
+    device_layout: DeviceLayout,
+    normalization: Vec<AxisNormalization>,
+    button_index: HashMap<u16, usize>,
+    axis_index: HashMap<u16, usize>,
+    buttons: Vec<bool>,
+    raw_axes: Vec<i32>,
+    /// Events since the last `SYN_REPORT`, applied when it arrives.
+    pending: Vec<RawEvent>,
+    /// Set by `SYN_DROPPED`: the kernel buffer overflowed and events up to
+    /// the next `SYN_REPORT` are incomplete. The state is read back from the
+    /// device there.
+    dropping: bool,
+}
+
+impl EvdevDevice {
+    fn new(source: Source, description: EvdevDescription) -> Self {
+        let layout = RawInputLayout {
+            buttons: description
+                .keys
+                .iter()
+                .map(|(code, _)| RawButton {
+                    code: *code,
+                    name: codes::key_name(*code),
+                })
+                .collect(),
+            axes: description
+                .axes
+                .iter()
+                .map(|(code, info)| RawAxis {
+                    code: *code,
+                    name: codes::abs_name(*code),
+                    min: info.min,
+                    max: info.max,
+                    fuzz: info.fuzz,
+                    flat: info.flat,
+                    resolution: info.resolution,
+                })
+                .collect(),
+        };
+
+        let mut device_layout = DeviceLayout::default();
+        for button in &layout.buttons {
+            device_layout.push_button(button.name.to_lowercase(), codes::key_kind(button.code));
+        }
+        for axis in &layout.axes {
+            device_layout.push_axis(axis.name.to_lowercase(), codes::abs_kind(axis.code));
+        }
+
+        // Sticks land in -1..1 around the middle of their range, as
+        // hid-generic pads report 0..255 ones. Other signed axes are centered
+        // on 0 and land in -1..1, unsigned ones (triggers) in 0..1
+        let normalization = description
+            .axes
+            .iter()
+            .map(|(code, info)| {
+                if codes::abs_kind(*code) == AxisKind::Stick && info.min >= 0 {
+                    return AxisNormalization::centered(info.min, info.max);
+                }
+                let divisor = info.min.unsigned_abs().max(info.max.unsigned_abs()).max(1);
+                AxisNormalization::new(info.min, info.max, divisor as f32)
+            })
+            .collect();
+
+        Self {
+            source,
+            name: description.name,
+            vendor_id: description.vendor_id,
+            product_id: description.product_id,
+            button_index: layout
+                .buttons
+                .iter()
+                .enumerate()
+                .map(|(index, button)| (button.code, index))
+                .collect(),
+            axis_index: layout
+                .axes
+                .iter()
+                .enumerate()
+                .map(|(index, axis)| (axis.code, index))
+                .collect(),
+            layout,
+            device_layout,
+            normalization,
+            buttons: description
+                .keys
+                .iter()
+                .map(|(_, pressed)| *pressed)
+                .collect(),
+            raw_axes: description
+                .axes
+                .iter()
+                .map(|(_, info)| info.value)
+                .collect(),
+            pending: Vec::new(),
+            dropping: false,
+        }
+    }
+
+    /// Feeds one event through, returning the kernel time of the report it
+    /// completed, if any.
+    fn process(&mut self, event: RawEvent) -> Option<u64> {
+        match (event.event_type, event.code) {
+            (EV_SYN, SYN_REPORT) => {
+                let pending = std::mem::take(&mut self.pending);
+                if std::mem::take(&mut self.dropping) {
+                    if let Some(current) = self.source.current_state() {
+                        self.set_state(&current);
+                    }
+                    return None;
+                }
+                for change in pending {
+                    self.apply(change);
+                }
+                Some(event.time_us)
+            }
+            (EV_SYN, SYN_DROPPED) => {
+                debug!(name = %self.name, "Kernel dropped events");
+                self.dropping = true;
+                None
+            }
+            (EV_KEY, _) | (EV_ABS, _) => {
+                self.pending.push(event);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Overwrites the state with values read from the device.
+    fn set_state(&mut self, current: &CurrentState) {
+        for (code, pressed) in &current.keys {
+            if let Some(&index) = self.button_index.get(code) {
+                self.buttons[index] = *pressed;
+            }
+        }
+        for (code, value) in &current.axes {
+            if let Some(&index) = self.axis_index.get(code) {
+                self.raw_axes[index] = *value;
+            }
+        }
+    }
+
+    fn apply(&mut self, event: RawEvent) {
+        let index = match event.event_type {
+            EV_KEY => self.button_index.get(&event.code),
+            _ => self.axis_index.get(&event.code),
+        };
+        let Some(&index) = index else {
+            return;
+        };
+        match event.event_type {
+            // Key repeat (2) counts as still pressed
+            EV_KEY => self.buttons[index] = event.value != 0,
+            _ => self.raw_axes[index] = event.value,
+        }
+    }
+
+    fn state(&self) -> GamepadState {
+        GamepadState {
+            buttons: self.buttons.clone(),
+            axes: self
+                .raw_axes
+                .iter()
+                .zip(&self.normalization)
+                .map(|(raw, normalization)| normalization.apply(*raw))
+                .collect(),
+            raw_axes: self.raw_axes.clone(),
+            axis_normalization: self.normalization.clone(),
+        }
+    }
+}
+
+pub struct EvdevBackend {
+    devices: BTreeMap<usize, EvdevDevice>,
+    next_id: usize,
+    /// Looks for device nodes appearing and disappearing in `/dev/input`.
+    #[cfg(target_os = "linux")]
+    scanner: Option<live::Scanner>,
+    /// Recordings that connect on the first poll.
+    queued: Vec<EvemuRecording>,
+    /// Playback clock for recorded devices.
+    clock: ScriptClock,
+    pending_reports: Vec<InputReport>,
+}
+
+impl EvdevBackend {
+    /// Opens every controller in `/dev/input` and watches for new ones.
+    /// Returns `None` if the directory can't be read.
+    #[cfg(target_os = "linux")]
+    pub fn new() -> Option<Self> {
+        let scanner = live::Scanner::new()?;
+        let mut backend = Self::with_clock(Vec::new(), ScriptClock::realtime());
+        backend.scanner = Some(scanner);
+        backend.scan();
+        tracing::info!(count = backend.devices.len(), "evdev controllers found");
+        Some(backend)
+    }
+
+    /// Plays back `evemu-record` captures as evdev devices.
+    pub fn from_recordings(recordings: Vec<EvemuRecording>) -> Self {
+        Self::with_clock(recordings, ScriptClock::realtime())
+    }
+
+    /// Like [`EvdevBackend::from_recordings`], but the clock only moves
+    /// through [`EvdevBackend::advance`].
+    pub fn manual(recordings: Vec<EvemuRecording>) -> Self {
+        Self::with_clock(recordings, ScriptClock::manual())
+    }
+
+    fn with_clock(recordings: Vec<EvemuRecording>, clock: ScriptClock) -> Self {
+        Self {
+            devices: BTreeMap::new(),
+            next_id: 0,
+            #[cfg(target_os = "linux")]
+            scanner: None,
+            queued: recordings,
+            clock,
+            pending_reports: Vec::new(),
+        }
+    }
+
+    /// Moves a manual clock forward. Has no effect on a realtime clock.
+    pub fn advance(&mut self, by: Duration) {
+        self.clock.advance(by);
+    }
+
+    fn add_device(&mut self, source: Source, description: EvdevDescription) -> GamepadInfo {
+        let id = self.next_id;
+        self.next_id += 1;
+        let device = EvdevDevice::new(source, description);
+        debug!(
+            id,
+            name = %device.name,
+            buttons = device.buttons.len(),
+            axes = device.raw_axes.len(),
+            "Opened evdev device"
+        );
+        let info = self.info_for(id, &device);
+        self.devices.insert(id, device);
+        info
+    }
+
+    /// Opens device nodes that appeared since the last scan, returning their
+    /// info.
+    #[cfg(target_os = "linux")]
+    fn scan(&mut self) -> Vec<GamepadInfo> {
+        let Some(scanner) = self.scanner.as_mut() else {
+            return Vec::new();
+        };
+        let open_paths = self
+            .devices
+            .values()
+            .filter_map(|device| match &device.source {
+                Source::Live(live) => Some(live.path().to_path_buf()),
+                Source::Recorded(_) => None,
+            })
+            .collect();
+        let opened = scanner.scan(&open_paths);
+        opened
+            .into_iter()
+            .map(|(device, description)| {
+                self.add_device(Source::Live(Box::new(device)), description)
+            })
+            .collect()
+    }
+
+    fn info_for(&self, id: usize, device: &EvdevDevice) -> GamepadInfo {
+        GamepadInfo {
+            id,
+            name: device.name.clone(),
+            controller_type: self.kind().to_string(),
+            vendor_id: device.vendor_id,
+            product_id: device.product_id,
+            joystick: None,
+            layout: device.device_layout.clone(),
+        }
+    }
+}
+
+impl InputBackend for EvdevBackend {
+    fn kind(&self) -> &'static str {
+        "evdev"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            hotplug_events: false,
+            vendor_product_ids: true,
+            max_devices: None,
+            report_timestamps: true,
+        }
+    }
+
+    fn poll(&mut self) -> Vec<HotplugEvent> {
+        let mut hotplug: Vec<HotplugEvent> = Vec::new();
+
+        for recording in std::mem::take(&mut self.queued) {
+            let source = Source::Recorded(Playback::new(recording.events));
+            let info = self.add_device(source, recording.description);
+            hotplug.push(HotplugEvent::Connected(info));
+        }
+
+        #[cfg(target_os = "linux")]
+        if self.scanner.as_ref().is_some_and(live::Scanner::due) {
+            hotplug.extend(self.scan().into_iter().map(HotplugEvent::Connected));
+        }
+
+        let elapsed = self.clock.elapsed();
+        let received_at = Instant::now();
+        let mut removed = Vec::new();
+        for (id, device) in self.devices.iter_mut() {
+            let events = match device.source.read_events(elapsed) {
+                Ok(events) => events,
+                Err(e) => {
+                    debug!(id, error = %e, "evdev device closed");
+                    removed.push(*id);
+                    continue;
+                }
+            };
+            for event in events {
+                if let Some(time_us) = device.process(event) {
+                    self.pending_reports.push(InputReport {
+                        id: *id,
+                        received_at,
+                        device_time_us: Some(time_us),
+                    });
+                }
+            }
+        }
+
+        for id in removed {
+            self.devices.remove(&id);
+            hotplug.push(HotplugEvent::Disconnected {
+                id,
+                controller_type: self.kind().to_string(),
+            });
+        }
+
+        hotplug
+    }
+
+    fn enumerate(&self) -> Vec<GamepadInfo> {
+        self.devices
+            .iter()
+            .map(|(id, device)| self.info_for(*id, device))
+            .collect()
+    }
+
+    fn read_state(&mut self, id: usize) -> Option<GamepadState> {
+        self.devices.get(&id).map(EvdevDevice::state)
+    }
+
+    fn drain_reports(&mut self) -> Vec<InputReport> {
+        std::mem::take(&mut self.pending_reports)
+    }
+
+    fn raw_layout(&self, id: usize) -> Option<RawInputLayout> {
+        self.devices.get(&id).map(|device| device.layout.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ButtonKind;
+
+    const XBOX_360: &str = include_str!("fixtures/xbox360.evemu");
+
+    fn connected(backend: &mut EvdevBackend) -> GamepadInfo {
+        match backend.poll().as_slice() {
+            [HotplugEvent::Connected(info)] => info.clone(),
+            events => panic!("expected one connection, got {:?}", events),
+        }
+    }
+
+    fn report_times(backend: &mut EvdevBackend) -> Vec<Option<u64>> {
+        backend
+            .drain_reports()
+            .iter()
+            .map(|report| report.device_time_us)
+            .collect()
+    }
+
+    #[test]
+    fn recording_description() {
+        let recording = EvemuRecording::parse(XBOX_360).unwrap();
+        let description = &recording.description;
+        assert_eq!(description.name, "Microsoft X-Box 360 pad");
+        assert_eq!(description.vendor_id, Some(0x045e));
+        assert_eq!(description.product_id, Some(0x028e));
+        assert_eq!(description.keys.len(), 11);
+        assert_eq!(recording.events.len(), 12);
+    }
+
+    #[test]
+    fn layout_and_absinfo() {
+        let recording = EvemuRecording::parse(XBOX_360).unwrap();
+        let mut backend = EvdevBackend::manual(vec![recording]);
+        let info = connected(&mut backend);
+        assert_eq!(info.controller_type, "evdev");
+
+        let layout = backend.raw_layout(info.id).unwrap();
+        let buttons: Vec<_> = layout.buttons.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(
+            buttons,
+            [
+                "BTN_SOUTH",
+                "BTN_EAST",
+                "BTN_NORTH",
+                "BTN_WEST",
+                "BTN_TL",
+                "BTN_TR",
+                "BTN_SELECT",
+                "BTN_START",
+                "BTN_MODE",
+                "BTN_THUMBL",
+                "BTN_THUMBR"
+            ]
+        );
+        let axes: Vec<_> = layout
+            .axes
+            .iter()
+            .map(|a| (a.code, a.min, a.max, a.fuzz, a.flat))
+            .collect();
+        assert_eq!(
+            axes,
+            [
+                (0x00, -32768, 32767, 16, 128),
+                (0x01, -32768, 32767, 16, 128),
+                (0x02, 0, 255, 0, 0),
+                (0x03, -32768, 32767, 16, 128),
+                (0x04, -32768, 32767, 16, 128),
+                (0x05, 0, 255, 0, 0),
+                (0x10, -1, 1, 0, 0),
+                (0x11, -1, 1, 0, 0),
+            ]
+        );
+
+        assert_eq!(info.layout.buttons[0].name, "btn_south");
+        assert_eq!(info.layout.buttons[0].kind, ButtonKind::Face);
+        assert_eq!(info.layout.axes[2].name, "abs_z");
+        assert_eq!(info.layout.axes[2].kind, AxisKind::Trigger);
+        assert_eq!(info.layout.axes[6].kind, AxisKind::Hat);
+    }
+
+    #[test]
+    fn unsigned_sticks_rest_at_center() {
+        // A hid-generic pad with 0..255 sticks
+        let recording = EvemuRecording::parse(
+            "N: Generic USB Joystick\n\
+             I: 0003 0079 0006 0110\n\
+             B: 00 0b 00 00 00 00 00 00 00\n\
+             B: 03 03 00 00 00 00 00 00 00\n\
+             A: 00 0 255 0 15 0\n\
+             A: 01 0 255 0 15 0\n\
+             E: 0.000001 0003 0000 0128\n\
+             E: 0.000001 0003 0001 0127\n\
+             E: 0.000001 0000 0000 0000\n\
+             E: 0.001000 0003 0000 0255\n\
+             E: 0.001000 0003 0001 0000\n\
+             E: 0.001000 0000 0000 0000\n",
+        )
+        .unwrap();
+        let mut backend = EvdevBackend::manual(vec![recording]);
+        let id = connected(&mut backend).id;
+
+        let state = backend.read_state(id).unwrap();
+        assert!(
+            state.axes.iter().all(|value| value.abs() < 0.01),
+            "{:?}",
+            state.axes
+        );
+        assert_eq!(state.axis_normalization[0].center, 127.5);
+
+        backend.advance(Duration::from_millis(1));
+        backend.poll();
+        assert_eq!(backend.read_state(id).unwrap().axes, [1.0, -1.0]);
+    }
+
+    #[test]
+    fn dropped_release_sticks_in_recordings() {
+        // The fixture's description with new events: a press, a drop that
+        // swallows its release, then a report after the drop
+        let mut text: String = XBOX_360
+            .lines()
+            .filter(|line| !line.starts_with("E:"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        text.push_str(
+            "E: 0.000001 0001 0130 0001\n\
+             E: 0.000001 0000 0000 0000\n\
+             E: 0.001000 0001 0130 0000\n\
+             E: 0.001000 0000 0003 0000\n\
+             E: 0.002000 0003 0000 0100\n\
+             E: 0.002000 0000 0000 0000\n\
+             E: 0.003000 0003 0000 0200\n\
+             E: 0.003000 0000 0000 0000\n",
+        );
+        let recording = EvemuRecording::parse(&text).unwrap();
+        let mut backend = EvdevBackend::manual(vec![recording]);
+        let id = connected(&mut backend).id;
+        assert_eq!(report_times(&mut backend), [Some(1)]);
+        assert!(backend.read_state(id).unwrap().buttons[0]);
+
+        // A recording has no state to resync from
+        backend.advance(Duration::from_millis(3));
+        backend.poll();
+        assert_eq!(report_times(&mut backend), [Some(3000)]);
+        let state = backend.read_state(id).unwrap();
+        assert!(state.buttons[0]);
+        assert_eq!(state.raw_axes[0], 200);
+
+        // What a live device does with the state read back after the drop
+        let device = backend.devices.get_mut(&id).unwrap();
+        device.set_state(&CurrentState {
+            keys: vec![(0x130, false), (0x999, true)],
+            axes: vec![(0x00, -5), (0x3f, 1)],
+        });
+        let state = backend.read_state(id).unwrap();
+        assert!(!state.buttons[0]);
+        assert_eq!(state.raw_axes[0], -5);
+    }
+
+    #[test]
+    fn playback() {
+        let recording = EvemuRecording::parse(XBOX_360).unwrap();
+        let mut backend = EvdevBackend::manual(vec![recording]);
+        let id = connected(&mut backend).id;
+
+        // The connecting poll plays the report due at playback time 0
+        assert_eq!(report_times(&mut backend), [Some(1)]);
+        let state = backend.read_state(id).unwrap();
+        assert_eq!(state.raw_axes[0], -1234);
+        assert!((state.axes[0] - -1234.0 / 32768.0).abs() < 1e-6);
+
+        // Events apply a whole SYN_REPORT at a time, at their recorded time
+        backend.advance(Duration::from_millis(4));
+        backend.poll();
+        assert!(report_times(&mut backend).is_empty());
+        backend.advance(Duration::from_micros(11));
+        backend.poll();
+        assert_eq!(report_times(&mut backend), [Some(4012)]);
+        let state = backend.read_state(id).unwrap();
+        assert!(state.buttons[0]);
+        assert_eq!(state.raw_axes[7], -1);
+        assert_eq!(state.axes[7], -1.0);
+
+        // SYN_DROPPED discards the incomplete report before it and the one
+        // it ends in
+        backend.advance(Duration::from_millis(8));
+        backend.poll();
+        assert!(report_times(&mut backend).is_empty());
+        let state = backend.read_state(id).unwrap();
+        assert_eq!(state.raw_axes[0], -1234);
+        assert_eq!(state.raw_axes[5], 0);
+
+        // Reports after it apply again
+        backend.advance(Duration::from_secs(1));
+        backend.poll();
+        assert_eq!(report_times(&mut backend), [Some(16000)]);
+        let state = backend.read_state(id).unwrap();
+        assert!(!state.buttons[0]);
+        assert_eq!(state.raw_axes[0], 32767);
+        assert_eq!(state.axes[0], 32767.0 / 32768.0);
+    }
+}
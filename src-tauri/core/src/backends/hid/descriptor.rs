@@ -0,0 +1,730 @@
+//! HID report descriptor parsing (HID 1.11, section 6.2.2).
+//!
+//! A descriptor is a list of items that describe the device's reports field
+//! by field. [`ReportDescriptor::parse`] walks it keeping the global and local
+//! item state the spec defines, and turns every Input, Output and Feature
+//! main item into a [`ReportField`] with its position in the report, so
+//! reports can be decoded without knowing anything about the device.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub const USAGE_PAGE_GENERIC_DESKTOP: u16 = 0x01;
+pub const USAGE_PAGE_SIMULATION: u16 = 0x02;
+pub const USAGE_PAGE_BUTTON: u16 = 0x09;
+
+pub const USAGE_JOYSTICK: u16 = 0x04;
+pub const USAGE_GAMEPAD: u16 = 0x05;
+pub const USAGE_MULTI_AXIS_CONTROLLER: u16 = 0x08;
+pub const USAGE_HAT_SWITCH: u16 = 0x39;
+
+/// Items nested deeper than this are rejected, no real device comes close.
+const MAX_COLLECTION_DEPTH: usize = 32;
+/// Guards against descriptors with absurd report counts.
+const MAX_REPORT_BITS: u32 = 8 * 4096;
+/// Elements one main item may have, `HID_MAX_USAGES` in Linux. Bounded on
+/// its own since elements of zero bits don't count towards the report length.
+const MAX_REPORT_COUNT: u32 = 12288;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Usage {
+    pub page: u16,
+    pub id: u16,
+}
+
+impl Usage {
+    pub const fn new(page: u16, id: u16) -> Self {
+        Self { page, id }
+    }
+
+    /// A 4-byte usage carries its own page in the high 16 bits, shorter ones
+    /// use the current Usage Page.
+    fn from_item(data: u32, size: usize, page: u16) -> Self {
+        if size == 4 {
+            Self::new((data >> 16) as u16, data as u16)
+        } else {
+            Self::new(page, data as u16)
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportKind {
+    Input,
+    Output,
+    Feature,
+}
+
+/// One Input, Output or Feature main item: `count` elements of `size` bits
+/// each, starting at `bit_offset`.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportField {
+    pub kind: ReportKind,
+    /// `None` if the descriptor doesn't use report IDs.
+    pub report_id: Option<u8>,
+    /// Bit position of the first element, not counting the report ID byte.
+    pub bit_offset: u32,
+    /// Bits per element.
+    pub size: u32,
+    pub count: u32,
+    /// For variable items, the usage of each element. For arrays, the usages
+    /// the element values select from, starting at `logical_min`.
+    pub usages: Vec<Usage>,
+    pub logical_min: i32,
+    pub logical_max: i32,
+    pub physical_min: i32,
+    pub physical_max: i32,
+    pub unit: u32,
+    pub unit_exponent: i32,
+    /// Padding or fixed data rather than controls.
+    pub constant: bool,
+    /// Each element is its own control. Otherwise the elements form an array
+    /// listing which of `usages` are active.
+    pub variable: bool,
+    pub relative: bool,
+    /// Values outside the logical range mean "no value", e.g. a centered hat.
+    pub null_state: bool,
+}
+
+impl ReportField {
+    /// The usage of element `index` of a variable item. Items with fewer
+    /// usages than elements repeat the last one.
+    pub fn usage(&self, index: u32) -> Option<Usage> {
+        self.usages
+            .get(index as usize)
+            .or_else(|| self.usages.last())
+            .copied()
+    }
+
+    /// Reads element `index` from `payload`, the report without its ID byte.
+    /// Sign-extended when the logical range goes below zero. `None` if the
+    /// report is too short.
+    pub fn value(&self, payload: &[u8], index: u32) -> Option<i32> {
+        let start = self.bit_offset + index * self.size;
+        let raw = read_bits(payload, start, self.size)?;
+        let signed = self.logical_min < 0 && self.size > 0 && self.size < 32;
+        Some(if signed {
+            let shift = 32 - self.size;
+            ((raw << shift) as i32) >> shift
+        } else {
+            raw as i32
+        })
+    }
+
+    /// Whether `value` is inside the logical range.
+    pub fn in_range(&self, value: i32) -> bool {
+        (self.logical_min..=self.logical_max).contains(&value)
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionKind {
+    Physical,
+    Application,
+    Logical,
+    Report,
+    NamedArray,
+    UsageSwitch,
+    UsageModifier,
+    Other(u8),
+}
+
+impl From<u32> for CollectionKind {
+    fn from(value: u32) -> Self {
+        match value {
+            0x00 => Self::Physical,
+            0x01 => Self::Application,
+            0x02 => Self::Logical,
+            0x03 => Self::Report,
+            0x04 => Self::NamedArray,
+            0x05 => Self::UsageSwitch,
+            0x06 => Self::UsageModifier,
+            other => Self::Other(other as u8),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Collection {
+    pub kind: CollectionKind,
+    pub usage: Option<Usage>,
+    pub collections: Vec<Collection>,
+    /// Indices into `ReportDescriptor::fields` of the main items directly in
+    /// this collection.
+    pub fields: Vec<usize>,
+}
+
+/// Length of one report, not counting its report ID byte.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportSize {
+    pub kind: ReportKind,
+    pub report_id: Option<u8>,
+    pub bits: u32,
+    /// `bits` rounded up to whole bytes, as the device sends them.
+    pub bytes: u32,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ReportDescriptor {
+    /// Top-level collections, normally one Application collection per
+    /// function of the device.
+    pub collections: Vec<Collection>,
+    pub fields: Vec<ReportField>,
+    /// Every report the fields add up to, by kind and report ID.
+    pub reports: Vec<ReportSize>,
+    /// Reports start with a report ID byte.
+    pub uses_report_ids: bool,
+}
+
+/// Global item state, saved and restored by Push and Pop.
+#[derive(Clone, Default)]
+struct Globals {
+    usage_page: u16,
+    logical_min: i32,
+    logical_max: i32,
+    /// Item size of Logical Maximum, to undo sign extension.
+    logical_max_size: usize,
+    physical_min: i32,
+    physical_max: i32,
+    unit: u32,
+    unit_exponent: i32,
+    report_size: u32,
+    report_id: Option<u8>,
+    report_count: u32,
+}
+
+/// Local item state, cleared after every main item.
+#[derive(Default)]
+struct Locals {
+    usages: Vec<Usage>,
+    usage_min: Option<Usage>,
+    usage_max: Option<Usage>,
+}
+
+impl Locals {
+    /// Explicit usages plus any Usage Minimum..Maximum range.
+    fn take_usages(&mut self) -> Vec<Usage> {
+        let mut usages = std::mem::take(&mut self.usages);
+        if let (Some(min), Some(max)) = (self.usage_min.take(), self.usage_max.take()) {
+            usages.extend((min.id..=max.id).map(|id| Usage::new(min.page, id)));
+        }
+        usages
+    }
+}
+
+impl ReportDescriptor {
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let mut descriptor = Self::default();
+        let mut globals = Globals::default();
+        let mut stack: Vec<Globals> = Vec::new();
+        let mut locals = Locals::default();
+        // Open collections, innermost last
+        let mut open: Vec<Collection> = Vec::new();
+        // Next free bit per (kind, report ID)
+        let mut offsets: HashMap<(ReportKind, Option<u8>), u32> = HashMap::new();
+
+        let mut position = 0;
+        while position < bytes.len() {
+            let prefix = bytes[position];
+            let start = position;
+            let fail = move |message: &str| {
+                format!("Invalid report descriptor at byte {}: {}", start, message)
+            };
+
+            // Long items carry vendor data, the length is in the next byte
+            if prefix == 0xfe {
+                let size = *bytes
+                    .get(position + 1)
+                    .ok_or_else(|| fail("truncated long item"))?;
+                position += 3 + size as usize;
+                continue;
+            }
+
+            let size = match prefix & 0x03 {
+                3 => 4,
+                size => size as usize,
+            };
+            let data_bytes = bytes
+                .get(position + 1..position + 1 + size)
+                .ok_or_else(|| fail("truncated item"))?;
+            let data = data_bytes
+                .iter()
+                .rev()
+                .fold(0u32, |value, byte| value << 8 | u32::from(*byte));
+            let signed = sign_extend(data, size);
+            let tag = prefix >> 4;
+            position += 1 + size;
+
+            match (prefix >> 2) & 0x03 {
+                // Main items
+                0 => match tag {
+                    0x8 | 0x9 | 0xb => {
+                        let kind = match tag {
+                            0x8 => ReportKind::Input,
+                            0x9 => ReportKind::Output,
+                            _ => ReportKind::Feature,
+                        };
+                        if globals.report_count > MAX_REPORT_COUNT {
+                            return Err(fail("report count too large"));
+                        }
+                        let offset = offsets.entry((kind, globals.report_id)).or_default();
+                        let bit_offset = *offset;
+                        *offset = offset.saturating_add(
+                            globals.report_size.saturating_mul(globals.report_count),
+                        );
+                        if *offset > MAX_REPORT_BITS {
+                            return Err(fail("report too long"));
+                        }
+
+                        // Logical Maximum is often written as e.g. 0xff in one
+                        // byte and meant unsigned
+                        let logical_max = if globals.logical_min >= 0 && globals.logical_max < 0 {
+                            unsigned(globals.logical_max, globals.logical_max_size)
+                        } else {
+                            globals.logical_max
+                        };
+
+                        let field = ReportField {
+                            kind,
+                            report_id: globals.report_id,
+                            bit_offset,
+                            size: globals.report_size,
+                            count: globals.report_count,
+                            usages: locals.take_usages(),
+                            logical_min: globals.logical_min,
+                            logical_max,
+                            physical_min: globals.physical_min,
+                            physical_max: globals.physical_max,
+                            unit: globals.unit,
+                            unit_exponent: globals.unit_exponent,
+                            constant: data & 0x01 != 0,
+                            variable: data & 0x02 != 0,
+                            relative: data & 0x04 != 0,
+                            null_state: data & 0x40 != 0,
+                        };
+                        // Nothing to read in zero-bit elements
+                        if field.size > 0 {
+                            let index = descriptor.fields.len();
+                            descriptor.fields.push(field);
+                            if let Some(collection) = open.last_mut() {
+                                collection.fields.push(index);
+                            }
+                        }
+                    }
+                    0xa => {
+                        if open.len() == MAX_COLLECTION_DEPTH {
+                            return Err(fail("collections nested too deep"));
+                        }
+                        let usage = locals.take_usages().first().copied();
+                        open.push(Collection {
+                            kind: data.into(),
+                            usage,
+                            collections: Vec::new(),
+                            fields: Vec::new(),
+                        });
+                    }
+                    0xc => {
+                        let collection = open
+                            .pop()
+                            .ok_or_else(|| fail("End Collection without Collection"))?;
+                        match open.last_mut() {
+                            Some(parent) => parent.collections.push(collection),
+                            None => descriptor.collections.push(collection),
+                        }
+                    }
+                    // Reserved tags, skipped like Linux does
+                    _ => {}
+                },
+                // Global items
+                1 => match tag {
+                    0x0 => globals.usage_page = data as u16,
+                    0x1 => globals.logical_min = signed,
+                    0x2 => {
+                        globals.logical_max = signed;
+                        globals.logical_max_size = size;
+                    }
+                    0x3 => globals.physical_min = signed,
+                    0x4 => globals.physical_max = signed,
+                    0x5 => globals.unit_exponent = unit_exponent(data),
+                    0x6 => globals.unit = data,
+                    0x7 => globals.report_size = data,
+                    0x8 => {
+                        if data == 0 || data > 0xff {
+                            return Err(fail("report ID out of range"));
+                        }
+                        globals.report_id = Some(data as u8);
+                        descriptor.uses_report_ids = true;
+                    }
+                    0x9 => globals.report_count = data,
+                    0xa => stack.push(globals.clone()),
+                    0xb => globals = stack.pop().ok_or_else(|| fail("Pop without Push"))?,
+                    _ => {}
+                },
+                // Local items. Designators, strings and delimiters only
+                // matter for physical descriptors and are skipped.
+                2 => match tag {
+                    0x0 => locals
+                        .usages
+                        .push(Usage::from_item(data, size, globals.usage_page)),
+                    0x1 => {
+                        locals.usage_min = Some(Usage::from_item(data, size, globals.usage_page))
+                    }
+                    0x2 => {
+                        locals.usage_max = Some(Usage::from_item(data, size, globals.usage_page))
+                    }
+                    _ => {}
+                },
+                _ => return Err(fail("reserved item type")),
+            }
+
+            // Local items only apply to the next main item
+            if prefix & 0x0c == 0 {
+                locals = Locals::default();
+            }
+        }
+
+        if !open.is_empty() {
+            return Err("Invalid report descriptor: unclosed collection".to_string());
+        }
+        descriptor.reports = offsets
+            .into_iter()
+            .map(|((kind, report_id), bits)| ReportSize {
+                kind,
+                report_id,
+                bits,
+                bytes: bits.div_ceil(8),
+            })
+            .collect();
+        descriptor
+            .reports
+            .sort_by_key(|report| (report.kind, report.report_id));
+        Ok(descriptor)
+    }
+
+    /// Whether any top-level Application collection is a joystick, gamepad
+    /// or multi-axis controller.
+    pub fn is_controller(&self) -> bool {
+        self.collections.iter().any(|collection| {
+            collection.kind == CollectionKind::Application
+                && collection.usage.is_some_and(|usage| {
+                    usage.page == USAGE_PAGE_GENERIC_DESKTOP
+                        && matches!(
+                            usage.id,
+                            USAGE_JOYSTICK | USAGE_GAMEPAD | USAGE_MULTI_AXIS_CONTROLLER
+                        )
+                })
+        })
+    }
+
+    /// Splits a report as read from the device into its report ID and
+    /// payload.
+    pub fn split_report<'a>(&self, report: &'a [u8]) -> Option<(Option<u8>, &'a [u8])> {
+        if self.uses_report_ids {
+            let (id, payload) = report.split_first()?;
+            Some((Some(*id), payload))
+        } else {
+            Some((None, report))
+        }
+    }
+
+    /// The Input fields of the report with `report_id`.
+    pub fn input_fields(&self, report_id: Option<u8>) -> impl Iterator<Item = &ReportField> {
+        self.fields
+            .iter()
+            .filter(move |field| field.kind == ReportKind::Input && field.report_id == report_id)
+    }
+}
+
+/// Reads `size` bits starting at bit `start`, least significant bit first as
+/// HID packs them.
+fn read_bits(bytes: &[u8], start: u32, size: u32) -> Option<u32> {
+    if size == 0 || size > 32 {
+        return None;
+    }
+    let end = start + size;
+    if end.div_ceil(8) as usize > bytes.len() {
+        return None;
+    }
+    let mut value: u64 = 0;
+    for (shift, byte) in bytes[(start / 8) as usize..end.div_ceil(8) as usize]
+        .iter()
+        .enumerate()
+    {
+        value |= u64::from(*byte) << (shift * 8);
+    }
+    value >>= start % 8;
+    Some((value & ((1u64 << size) - 1)) as u32)
+}
+
+fn sign_extend(data: u32, size: usize) -> i32 {
+    match size {
+        1 => data as u8 as i8 as i32,
+        2 => data as u16 as i16 as i32,
+        _ => data as i32,
+    }
+}
+
+fn unsigned(value: i32, size: usize) -> i32 {
+    match size {
+        1 => value as u8 as i32,
+        2 => value as u16 as i32,
+        _ => value,
+    }
+}
+
+/// Unit Exponent is a 4-bit two's complement value.
+fn unit_exponent(data: u32) -> i32 {
+    let nibble = (data & 0x0f) as i32;
+    if nibble >= 8 {
+        nibble - 16
+    } else {
+        nibble
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::{GP2040_DINPUT, HAYBOX_DINPUT, REPORT_IDS};
+    use super::*;
+
+    /// (kind, report ID, bit offset, size, count, constant) of every field.
+    fn layout(descriptor: &ReportDescriptor) -> Vec<(ReportKind, Option<u8>, u32, u32, u32, bool)> {
+        descriptor
+            .fields
+            .iter()
+            .map(|field| {
+                let ReportField {
+                    kind,
+                    report_id,
+                    bit_offset,
+                    size,
+                    count,
+                    constant,
+                    ..
+                } = *field;
+                (kind, report_id, bit_offset, size, count, constant)
+            })
+            .collect()
+    }
+
+    fn error(bytes: &[u8]) -> String {
+        ReportDescriptor::parse(bytes).unwrap_err()
+    }
+
+    #[test]
+    fn gp2040_dinput() {
+        use ReportKind::*;
+        let descriptor = ReportDescriptor::parse(GP2040_DINPUT).unwrap();
+        assert!(descriptor.is_controller());
+        assert!(!descriptor.uses_report_ids);
+        assert_eq!(
+            layout(&descriptor),
+            [
+                (Input, None, 0, 1, 14, false),
+                (Input, None, 14, 1, 2, true),
+                (Input, None, 16, 4, 1, false),
+                (Input, None, 20, 4, 1, true),
+                (Input, None, 24, 8, 4, false),
+                (Input, None, 56, 8, 12, false),
+                (Feature, None, 0, 8, 8, false),
+            ]
+        );
+
+        let buttons = &descriptor.fields[0];
+        assert_eq!(buttons.usages.len(), 14);
+        assert_eq!(buttons.usages[0], Usage::new(USAGE_PAGE_BUTTON, 1));
+        assert_eq!(buttons.usages[13], Usage::new(USAGE_PAGE_BUTTON, 14));
+
+        let hat = &descriptor.fields[2];
+        assert_eq!(
+            hat.usages,
+            [Usage::new(USAGE_PAGE_GENERIC_DESKTOP, USAGE_HAT_SWITCH)]
+        );
+        assert_eq!((hat.logical_min, hat.logical_max), (0, 7));
+        assert_eq!((hat.physical_min, hat.physical_max), (0, 315));
+        assert_eq!(hat.unit, 0x14);
+        assert!(hat.null_state && hat.variable);
+
+        let axes = &descriptor.fields[4];
+        let ids: Vec<u16> = axes.usages.iter().map(|usage| usage.id).collect();
+        assert_eq!(ids, [0x30, 0x31, 0x32, 0x35]);
+        assert_eq!((axes.logical_min, axes.logical_max), (0, 255));
+        assert_eq!(axes.unit, 0);
+
+        assert_eq!(descriptor.fields[6].usages, [Usage::new(0xff00, 0x2621)]);
+
+        let collection = &descriptor.collections[0];
+        assert_eq!(collection.kind, CollectionKind::Application);
+        assert_eq!(collection.fields, [0, 1, 2, 3, 4, 5, 6]);
+
+        let reports: Vec<_> = descriptor
+            .reports
+            .iter()
+            .map(|report| (report.kind, report.report_id, report.bits, report.bytes))
+            .collect();
+        assert_eq!(reports, [(Input, None, 152, 19), (Feature, None, 64, 8)]);
+    }
+
+    #[test]
+    fn haybox_dinput() {
+        use ReportKind::*;
+        let descriptor = ReportDescriptor::parse(HAYBOX_DINPUT).unwrap();
+        assert!(descriptor.is_controller());
+        assert_eq!(
+            layout(&descriptor),
+            [
+                (Input, None, 0, 8, 6, false),
+                (Input, None, 48, 8, 1, false),
+                (Input, None, 56, 1, 32, false),
+            ]
+        );
+
+        let axes = &descriptor.fields[0];
+        let ids: Vec<u16> = axes.usages.iter().map(|usage| usage.id).collect();
+        assert_eq!(ids, [0x30, 0x31, 0x32, 0x35, 0x33, 0x34]);
+        assert_eq!((axes.logical_min, axes.logical_max), (-127, 127));
+        // Signed elements are sign-extended
+        let payload = [0x81, 0x7f, 0x00, 0xff, 0x00, 0x00];
+        assert_eq!(axes.value(&payload, 0), Some(-127));
+        assert_eq!(axes.value(&payload, 1), Some(127));
+        assert_eq!(axes.value(&payload, 3), Some(-1));
+        assert_eq!(axes.value(&payload, 6), None);
+
+        let hat = &descriptor.fields[1];
+        assert_eq!((hat.logical_min, hat.logical_max), (1, 8));
+        assert!(!hat.null_state);
+        assert!(!hat.in_range(0));
+
+        assert_eq!(descriptor.reports[0].bytes, 11);
+    }
+
+    #[test]
+    fn report_ids() {
+        use ReportKind::*;
+        let descriptor = ReportDescriptor::parse(REPORT_IDS).unwrap();
+        assert!(descriptor.uses_report_ids);
+        assert_eq!(
+            layout(&descriptor),
+            [
+                (Input, Some(1), 0, 1, 8, false),
+                (Input, Some(1), 8, 8, 2, false),
+                (Input, Some(1), 24, 1, 8, false),
+                (Input, Some(2), 0, 12, 1, false),
+                (Input, Some(2), 12, 4, 1, true),
+                (Feature, Some(3), 0, 8, 4, false),
+            ]
+        );
+
+        // One-byte 0xff Logical Maximum is read unsigned
+        let sticks = &descriptor.fields[1];
+        assert_eq!((sticks.logical_min, sticks.logical_max), (0, 255));
+        assert_eq!(sticks.value(&[0x00, 0x80, 0xff], 1), Some(255));
+
+        // Pop restores the Button page, range and size from before Push
+        let buttons = &descriptor.fields[2];
+        assert_eq!(
+            buttons.usages.first(),
+            Some(&Usage::new(USAGE_PAGE_BUTTON, 9))
+        );
+        assert_eq!(
+            buttons.usages.last(),
+            Some(&Usage::new(USAGE_PAGE_BUTTON, 16))
+        );
+        assert_eq!((buttons.logical_min, buttons.logical_max), (0, 1));
+
+        // A 4-byte usage carries its own page
+        let z = &descriptor.fields[3];
+        assert_eq!(z.usages, [Usage::new(USAGE_PAGE_GENERIC_DESKTOP, 0x32)]);
+        assert_eq!((z.logical_min, z.logical_max), (-2047, 2047));
+        assert_eq!(z.value(&[0x01, 0x08], 0), Some(-2047));
+        assert_eq!(z.value(&[0xff, 0x07], 0), Some(2047));
+
+        let application = &descriptor.collections[0];
+        assert_eq!(application.fields, [0, 2, 3, 4, 5]);
+        assert_eq!(application.collections[0].kind, CollectionKind::Physical);
+        assert_eq!(application.collections[0].fields, [1]);
+
+        let reports: Vec<_> = descriptor
+            .reports
+            .iter()
+            .map(|report| (report.kind, report.report_id, report.bytes))
+            .collect();
+        assert_eq!(
+            reports,
+            [
+                (Input, Some(1), 4),
+                (Input, Some(2), 2),
+                (Feature, Some(3), 4)
+            ]
+        );
+
+        assert_eq!(
+            descriptor.split_report(&[2, 0x01, 0x08]),
+            Some((Some(2), &[0x01, 0x08][..]))
+        );
+        assert_eq!(descriptor.split_report(&[]), None);
+        assert_eq!(descriptor.input_fields(Some(2)).count(), 2);
+        assert_eq!(descriptor.input_fields(Some(3)).count(), 0);
+    }
+
+    #[test]
+    fn zero_size_fields_are_skipped() {
+        // Report Size 0 with the largest allowed count
+        let descriptor =
+            ReportDescriptor::parse(&[0x75, 0x00, 0x96, 0x00, 0x30, 0x81, 0x02]).unwrap();
+        assert!(descriptor.fields.is_empty());
+        assert_eq!(descriptor.reports[0].bits, 0);
+    }
+
+    #[test]
+    fn invalid_descriptors() {
+        assert_eq!(
+            error(&[0x05]),
+            "Invalid report descriptor at byte 0: truncated item"
+        );
+        assert_eq!(
+            error(&[0x05, 0x01, 0xfe]),
+            "Invalid report descriptor at byte 2: truncated long item"
+        );
+        assert_eq!(
+            error(&[0xa1, 0x01]),
+            "Invalid report descriptor: unclosed collection"
+        );
+        assert_eq!(
+            error(&[0xc0]),
+            "Invalid report descriptor at byte 0: End Collection without Collection"
+        );
+        assert_eq!(
+            error(&[0xb4]),
+            "Invalid report descriptor at byte 0: Pop without Push"
+        );
+        assert_eq!(
+            error(&[0x85, 0x00]),
+            "Invalid report descriptor at byte 0: report ID out of range"
+        );
+        assert_eq!(
+            error(&[0x75, 0x00, 0x96, 0x01, 0x30, 0x81, 0x02]),
+            "Invalid report descriptor at byte 5: report count too large"
+        );
+        assert_eq!(
+            error(&[0x75, 0x20, 0x96, 0x01, 0x04, 0x81, 0x02]),
+            "Invalid report descriptor at byte 5: report too long"
+        );
+        assert_eq!(
+            error(&[0x0c]),
+            "Invalid report descriptor at byte 0: reserved item type"
+        );
+    }
+
+    #[test]
+    fn bits_are_read_lsb_first() {
+        let bytes = [0b1010_1100, 0b0000_0011];
+        assert_eq!(read_bits(&bytes, 0, 4), Some(0b1100));
+        assert_eq!(read_bits(&bytes, 4, 6), Some(0b11_1010));
+        assert_eq!(read_bits(&bytes, 8, 8), Some(0b11));
+        assert_eq!(read_bits(&bytes, 9, 8), None);
+        assert_eq!(read_bits(&bytes, 0, 0), None);
+    }
+}
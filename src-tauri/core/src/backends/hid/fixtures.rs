@@ -0,0 +1,152 @@
+//! Report descriptors for the parser and decoder tests, two from real devices.
+
+/// GP2040-CE in DInput mode (PS3 style): 14 buttons and 2 padding bits, a
+/// 4-bit hat with a null state and a padding nibble, 8-bit X, Y, Z and Rz
+/// with Logical Maximum written as `26 ff 00`, 12 vendor pressure bytes and
+/// a vendor feature report.
+pub const GP2040_DINPUT: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x05, // Usage (Game Pad)
+    0xa1, 0x01, // Collection (Application)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x35, 0x00, //   Physical Minimum (0)
+    0x45, 0x01, //   Physical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x0e, //   Report Count (14)
+    0x05, 0x09, //   Usage Page (Button)
+    0x19, 0x01, //   Usage Minimum (1)
+    0x29, 0x0e, //   Usage Maximum (14)
+    0x81, 0x02, //   Input (Data, Var, Abs)
+    0x95, 0x02, //   Report Count (2)
+    0x81, 0x01, //   Input (Const)
+    0x05, 0x01, //   Usage Page (Generic Desktop)
+    0x25, 0x07, //   Logical Maximum (7)
+    0x46, 0x3b, 0x01, // Physical Maximum (315)
+    0x75, 0x04, //   Report Size (4)
+    0x95, 0x01, //   Report Count (1)
+    0x65, 0x14, //   Unit (English Rotation, Degrees)
+    0x09, 0x39, //   Usage (Hat Switch)
+    0x81, 0x42, //   Input (Data, Var, Abs, Null State)
+    0x65, 0x00, //   Unit (None)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x01, //   Input (Const)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x46, 0xff, 0x00, // Physical Maximum (255)
+    0x09, 0x30, //   Usage (X)
+    0x09, 0x31, //   Usage (Y)
+    0x09, 0x32, //   Usage (Z)
+    0x09, 0x35, //   Usage (Rz)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x04, //   Report Count (4)
+    0x81, 0x02, //   Input (Data, Var, Abs)
+    0x06, 0x00, 0xff, // Usage Page (Vendor 0xFF00)
+    0x09, 0x20, //   Usage (0x20)
+    0x09, 0x21, //   Usage (0x21)
+    0x09, 0x22, //   Usage (0x22)
+    0x09, 0x23, //   Usage (0x23)
+    0x09, 0x24, //   Usage (0x24)
+    0x09, 0x25, //   Usage (0x25)
+    0x09, 0x26, //   Usage (0x26)
+    0x09, 0x27, //   Usage (0x27)
+    0x09, 0x28, //   Usage (0x28)
+    0x09, 0x29, //   Usage (0x29)
+    0x09, 0x2a, //   Usage (0x2A)
+    0x09, 0x2b, //   Usage (0x2B)
+    0x95, 0x0c, //   Report Count (12)
+    0x81, 0x02, //   Input (Data, Var, Abs)
+    0x0a, 0x21, 0x26, // Usage (0x2621)
+    0x95, 0x08, //   Report Count (8)
+    0xb1, 0x02, //   Feature (Data, Var, Abs)
+    0xc0, // End Collection
+];
+
+/// TinyUSB's `TUD_HID_REPORT_DESC_GAMEPAD`, which HayBox's DInput mode
+/// reports: signed 8-bit X, Y, Z, Rz, Rx and Ry, an 8-bit hat counting 1 to
+/// 8 without a null state flag (0 is centered), and 32 buttons.
+pub const HAYBOX_DINPUT: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x05, // Usage (Game Pad)
+    0xa1, 0x01, // Collection (Application)
+    0x05, 0x01, //   Usage Page (Generic Desktop)
+    0x09, 0x30, //   Usage (X)
+    0x09, 0x31, //   Usage (Y)
+    0x09, 0x32, //   Usage (Z)
+    0x09, 0x35, //   Usage (Rz)
+    0x09, 0x33, //   Usage (Rx)
+    0x09, 0x34, //   Usage (Ry)
+    0x15, 0x81, //   Logical Minimum (-127)
+    0x25, 0x7f, //   Logical Maximum (127)
+    0x95, 0x06, //   Report Count (6)
+    0x75, 0x08, //   Report Size (8)
+    0x81, 0x02, //   Input (Data, Var, Abs)
+    0x05, 0x01, //   Usage Page (Generic Desktop)
+    0x09, 0x39, //   Usage (Hat Switch)
+    0x15, 0x01, //   Logical Minimum (1)
+    0x25, 0x08, //   Logical Maximum (8)
+    0x35, 0x00, //   Physical Minimum (0)
+    0x46, 0x3b, 0x01, // Physical Maximum (315)
+    0x95, 0x01, //   Report Count (1)
+    0x75, 0x08, //   Report Size (8)
+    0x81, 0x02, //   Input (Data, Var, Abs)
+    0x05, 0x09, //   Usage Page (Button)
+    0x19, 0x01, //   Usage Minimum (1)
+    0x29, 0x20, //   Usage Maximum (32)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x95, 0x20, //   Report Count (32)
+    0x75, 0x01, //   Report Size (1)
+    0x81, 0x02, //   Input (Data, Var, Abs)
+    0xc0, // End Collection
+];
+
+/// A gamepad with numbered reports: report 1 has 8 buttons, X and Y with
+/// Logical Maximum written as a one-byte `ff` between Push and Pop, and 8
+/// more buttons using the popped state; report 2 a signed 12-bit Z with a
+/// 4-byte usage; report 3 is a vendor feature report.
+pub const REPORT_IDS: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x05, // Usage (Game Pad)
+    0xa1, 0x01, // Collection (Application)
+    0x85, 0x01, //   Report ID (1)
+    0x05, 0x09, //   Usage Page (Button)
+    0x19, 0x01, //   Usage Minimum (1)
+    0x29, 0x08, //   Usage Maximum (8)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x08, //   Report Count (8)
+    0x81, 0x02, //   Input (Data, Var, Abs)
+    0xa4, //         Push
+    0x05, 0x01, //   Usage Page (Generic Desktop)
+    0xa1, 0x00, //   Collection (Physical)
+    0x09, 0x30, //     Usage (X)
+    0x09, 0x31, //     Usage (Y)
+    0x25, 0xff, //     Logical Maximum (255, written as -1)
+    0x75, 0x08, //     Report Size (8)
+    0x95, 0x02, //     Report Count (2)
+    0x81, 0x02, //     Input (Data, Var, Abs)
+    0xc0, //         End Collection
+    0xb4, //         Pop
+    0x19, 0x09, //   Usage Minimum (9)
+    0x29, 0x10, //   Usage Maximum (16)
+    0x81, 0x02, //   Input (Data, Var, Abs)
+    0x85, 0x02, //   Report ID (2)
+    0x0b, 0x32, 0x00, 0x01, 0x00, // Usage (Generic Desktop Z)
+    0x16, 0x01, 0xf8, // Logical Minimum (-2047)
+    0x26, 0xff, 0x07, // Logical Maximum (2047)
+    0x75, 0x0c, //   Report Size (12)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x02, //   Input (Data, Var, Abs)
+    0x75, 0x04, //   Report Size (4)
+    0x81, 0x01, //   Input (Const)
+    0x85, 0x03, //   Report ID (3)
+    0x06, 0x00, 0xff, // Usage Page (Vendor 0xFF00)
+    0x09, 0x01, //   Usage (0x01)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x26, 0xff, 0x00, // Logical Maximum (255)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x04, //   Report Count (4)
+    0xb1, 0x02, //   Feature (Data, Var, Abs)
+    0xc0, // End Collection
+];
@@ -0,0 +1,303 @@
+//! Linux hidraw nodes (`/dev/hidraw*`), read with plain `read` calls and the
+//! hidraw ioctls for the descriptor and device info.
+
+use super::{HidDecoder, ReportDescriptor};
+use crate::backends::{BackendCapabilities, HotplugEvent, InputBackend, InputReport};
+use crate::models::{GamepadInfo, GamepadState, RawReport};
+use nix::libc;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+const DEV_DIR: &str = "/dev";
+
+/// How often `/dev` is checked for new hidraw nodes.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `HID_MAX_DESCRIPTOR_SIZE` in `linux/hid.h`.
+const MAX_DESCRIPTOR_SIZE: usize = 4096;
+/// Longest report hidraw hands out.
+const MAX_REPORT_SIZE: usize = 4096;
+
+/// `struct hidraw_report_descriptor`
+#[repr(C)]
+struct DescriptorBuffer {
+    size: u32,
+    value: [u8; MAX_DESCRIPTOR_SIZE],
+}
+
+/// `struct hidraw_devinfo`
+#[repr(C)]
+#[derive(Default)]
+struct DevInfo {
+    bustype: u32,
+    vendor: i16,
+    product: i16,
+}
+
+mod ioctl {
+    use super::{libc, DescriptorBuffer, DevInfo};
+
+    nix::ioctl_read!(descriptor_size, b'H', 0x01, libc::c_int);
+    nix::ioctl_read!(descriptor, b'H', 0x02, DescriptorBuffer);
+    nix::ioctl_read!(device_info, b'H', 0x03, DevInfo);
+    nix::ioctl_read_buf!(device_name, b'H', 0x04, u8);
+}
+
+struct HidDevice {
+    file: File,
+    path: PathBuf,
+    name: String,
+    vendor_id: u16,
+    product_id: u16,
+    decoder: HidDecoder,
+}
+
+impl HidDevice {
+    /// Opens `path` for non-blocking reads, or `None` if it isn't a
+    /// controller.
+    fn open(path: &Path) -> io::Result<Option<Self>> {
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)?;
+        let fd = file.as_raw_fd();
+
+        let mut size: libc::c_int = 0;
+        unsafe { ioctl::descriptor_size(fd, &mut size) }?;
+        let mut buffer = Box::new(DescriptorBuffer {
+            size: size.clamp(0, MAX_DESCRIPTOR_SIZE as libc::c_int) as u32,
+            value: [0; MAX_DESCRIPTOR_SIZE],
+        });
+        unsafe { ioctl::descriptor(fd, &mut *buffer) }?;
+        let bytes = &buffer.value[..buffer.size as usize];
+        let descriptor = match ReportDescriptor::parse(bytes) {
+            Ok(descriptor) if descriptor.is_controller() => descriptor,
+            Ok(_) => return Ok(None),
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Skipping HID device");
+                return Ok(None);
+            }
+        };
+
+        let mut info = DevInfo::default();
+        unsafe { ioctl::device_info(fd, &mut info) }?;
+        let mut name = [0u8; 256];
+        let name = match unsafe { ioctl::device_name(fd, &mut name) } {
+            Ok(length) => {
+                let name = &name[..(length.max(0) as usize).min(name.len())];
+                String::from_utf8_lossy(name)
+                    .trim_end_matches('\0')
+                    .to_string()
+            }
+            Err(_) => "Unknown HID device".to_string(),
+        };
+
+        Ok(Some(Self {
+            file,
+            path: path.to_path_buf(),
+            name,
+            vendor_id: info.vendor as u16,
+            product_id: info.product as u16,
+            decoder: HidDecoder::new(descriptor),
+        }))
+    }
+
+    /// Reads every queued report, returning how many the descriptor
+    /// describes. An error means the device is gone.
+    fn read_reports(&mut self) -> io::Result<usize> {
+        let mut buffer = [0u8; MAX_REPORT_SIZE];
+        let mut count = 0;
+        loop {
+            match self.file.read(&mut buffer) {
+                Ok(0) => return Ok(count),
+                Ok(length) => {
+                    if self.decoder.process(&buffer[..length]) {
+                        count += 1;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(count),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+pub struct HidBackend {
+    devices: BTreeMap<usize, HidDevice>,
+    next_id: usize,
+    /// Nodes found not to be controllers.
+    ignored: HashSet<PathBuf>,
+    last_scan: Option<Instant>,
+    pending_reports: Vec<InputReport>,
+}
+
+impl HidBackend {
+    /// Opens every controller among the hidraw nodes and watches for new
+    /// ones. Returns `None` if `/dev` can't be read.
+    pub fn new() -> Option<Self> {
+        if let Err(e) = std::fs::read_dir(DEV_DIR) {
+            warn!(error = %e, "Can't read {}, HID backend disabled", DEV_DIR);
+            return None;
+        }
+        let mut backend = Self {
+            devices: BTreeMap::new(),
+            next_id: 0,
+            ignored: HashSet::new(),
+            last_scan: None,
+            pending_reports: Vec::new(),
+        };
+        backend.scan();
+        info!(count = backend.devices.len(), "HID controllers found");
+        Some(backend)
+    }
+
+    /// Opens controllers among the hidraw nodes that aren't open yet,
+    /// returning their info.
+    fn scan(&mut self) -> Vec<GamepadInfo> {
+        self.last_scan = Some(Instant::now());
+        let Ok(entries) = std::fs::read_dir(DEV_DIR) else {
+            return Vec::new();
+        };
+        let present: BTreeSet<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("hidraw"))
+            })
+            .collect();
+        // Nodes that went away may come back as a different device
+        self.ignored.retain(|path| present.contains(path));
+        let open: HashSet<&PathBuf> = self.devices.values().map(|device| &device.path).collect();
+        let new_paths: Vec<PathBuf> = present
+            .into_iter()
+            .filter(|path| !open.contains(path) && !self.ignored.contains(path))
+            .collect();
+
+        let mut opened = Vec::new();
+        for path in new_paths {
+            match HidDevice::open(&path) {
+                Ok(Some(device)) => {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    debug!(
+                        id,
+                        path = %path.display(),
+                        name = %device.name,
+                        fields = device.decoder.descriptor().fields.len(),
+                        "Opened HID device"
+                    );
+                    opened.push(self.info_for(id, &device));
+                    self.devices.insert(id, device);
+                }
+                Ok(None) => {
+                    self.ignored.insert(path);
+                }
+                // Usually missing permissions, hidraw nodes are root-only
+                // without a udev rule. Retried on the next scan.
+                Err(e) => debug!(path = %path.display(), error = %e, "Can't open HID device"),
+            }
+        }
+        opened
+    }
+
+    fn info_for(&self, id: usize, device: &HidDevice) -> GamepadInfo {
+        GamepadInfo {
+            id,
+            name: device.name.clone(),
+            controller_type: self.kind().to_string(),
+            vendor_id: Some(device.vendor_id),
+            product_id: Some(device.product_id),
+            joystick: None,
+            layout: device.decoder.layout().clone(),
+        }
+    }
+}
+
+impl InputBackend for HidBackend {
+    fn kind(&self) -> &'static str {
+        "hid"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            hotplug_events: false,
+            vendor_product_ids: true,
+            max_devices: None,
+            report_timestamps: true,
+        }
+    }
+
+    fn poll(&mut self) -> Vec<HotplugEvent> {
+        let mut hotplug = Vec::new();
+        if self
+            .last_scan
+            .is_none_or(|at| at.elapsed() >= SCAN_INTERVAL)
+        {
+            hotplug.extend(self.scan().into_iter().map(HotplugEvent::Connected));
+        }
+
+        let mut removed = Vec::new();
+        for (id, device) in self.devices.iter_mut() {
+            match device.read_reports() {
+                Ok(count) => {
+                    let received_at = Instant::now();
+                    self.pending_reports.extend((0..count).map(|_| InputReport {
+                        id: *id,
+                        received_at,
+                        device_time_us: None,
+                    }));
+                }
+                Err(e) => {
+                    debug!(id, error = %e, "HID device closed");
+                    removed.push(*id);
+                }
+            }
+        }
+        for id in removed {
+            self.devices.remove(&id);
+            hotplug.push(HotplugEvent::Disconnected {
+                id,
+                controller_type: self.kind().to_string(),
+            });
+        }
+
+        hotplug
+    }
+
+    fn enumerate(&self) -> Vec<GamepadInfo> {
+        self.devices
+            .iter()
+            .map(|(id, device)| self.info_for(*id, device))
+            .collect()
+    }
+
+    fn read_state(&mut self, id: usize) -> Option<GamepadState> {
+        self.devices.get(&id).map(|device| device.decoder.state())
+    }
+
+    fn drain_reports(&mut self) -> Vec<InputReport> {
+        std::mem::take(&mut self.pending_reports)
+    }
+
+    fn raw_reports(&self, id: usize) -> Vec<RawReport> {
+        self.devices
+            .get(&id)
+            .map(|device| device.decoder.raw_reports())
+            .unwrap_or_default()
+    }
+
+    fn report_descriptor(&self, id: usize) -> Option<ReportDescriptor> {
+        self.devices
+            .get(&id)
+            .map(|device| device.decoder.descriptor().clone())
+    }
+}
@@ -0,0 +1,462 @@
+//! Raw HID controllers, decoded with their own report descriptor.
+//!
+//! Nothing here depends on a mapping database: the descriptor says where
+//! every control sits in the input reports, so any joystick or gamepad shows
+//! its real inputs, and the latest report of each ID is kept byte for byte.
+//! Devices are read through Linux hidraw nodes.
+
+use crate::models::{
+    AxisKind, AxisNormalization, ButtonKind, DeviceLayout, GamepadState, RawReport,
+};
+use std::collections::BTreeMap;
+
+pub mod descriptor;
+#[cfg(test)]
+mod fixtures;
+#[cfg(target_os = "linux")]
+mod hidraw;
+
+pub use descriptor::{ReportDescriptor, ReportField, ReportSize, Usage};
+#[cfg(target_os = "linux")]
+pub use hidraw::HidBackend;
+
+use descriptor::{
+    USAGE_HAT_SWITCH, USAGE_PAGE_BUTTON, USAGE_PAGE_GENERIC_DESKTOP, USAGE_PAGE_SIMULATION,
+};
+
+/// Generic Desktop X, Y, Z, Rx, Ry, Rz, Slider, Dial and Wheel.
+const AXIS_USAGES: std::ops::RangeInclusive<u16> = 0x30..=0x38;
+const AXIS_NAMES: [&str; 9] = ["x", "y", "z", "rx", "ry", "rz", "slider", "dial", "wheel"];
+
+/// One element of a report field.
+#[derive(Debug, Clone, Copy)]
+struct Element {
+    field: usize,
+    index: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ButtonSource {
+    /// A one-bit (or wider) variable element, pressed when non-zero.
+    Variable(Element),
+    /// Pressed while any element of an array field holds its usage.
+    Array { field: usize, usage: Usage },
+}
+
+/// Turns input reports into `GamepadState`s using a parsed descriptor.
+///
+/// Button page usages become buttons in usage order, followed by four
+/// buttons per hat switch. Generic Desktop X to Wheel and every Simulation
+/// Controls usage become axes in usage order. Sticks are normalized to
+/// -1..1 around the middle of their logical range, so a 0..255 stick rests
+/// at 0 like a signed one. Other axes are divided by the larger end of their
+/// range, so signed axes land in -1..1 and unsigned ones in 0..1.
+pub struct HidDecoder {
+    descriptor: ReportDescriptor,
+    buttons: Vec<ButtonSource>,
+    hats: Vec<Element>,
+    axes: Vec<Element>,
+    layout: DeviceLayout,
+    state: GamepadState,
+    /// Latest input report per report ID, as read.
+    reports: BTreeMap<Option<u8>, Vec<u8>>,
+}
+
+impl HidDecoder {
+    pub fn new(descriptor: ReportDescriptor) -> Self {
+        let mut buttons: Vec<(Usage, ButtonSource)> = Vec::new();
+        let mut hats: Vec<Element> = Vec::new();
+        let mut axes: Vec<(Usage, Element)> = Vec::new();
+
+        for (field_index, field) in descriptor.fields.iter().enumerate() {
+            if field.kind != descriptor::ReportKind::Input || field.constant {
+                continue;
+            }
+            if !field.variable {
+                buttons.extend(
+                    field
+                        .usages
+                        .iter()
+                        .filter(|usage| usage.page == USAGE_PAGE_BUTTON)
+                        .map(|usage| {
+                            let source = ButtonSource::Array {
+                                field: field_index,
+                                usage: *usage,
+                            };
+                            (*usage, source)
+                        }),
+                );
+                continue;
+            }
+            for index in 0..field.count {
+                let Some(usage) = field.usage(index) else {
+                    continue;
+                };
+                let element = Element {
+                    field: field_index,
+                    index,
+                };
+                match (usage.page, usage.id) {
+                    (USAGE_PAGE_BUTTON, _) => {
+                        buttons.push((usage, ButtonSource::Variable(element)))
+                    }
+                    (USAGE_PAGE_GENERIC_DESKTOP, USAGE_HAT_SWITCH) => hats.push(element),
+                    (USAGE_PAGE_GENERIC_DESKTOP, id) if AXIS_USAGES.contains(&id) => {
+                        axes.push((usage, element))
+                    }
+                    (USAGE_PAGE_SIMULATION, _) => axes.push((usage, element)),
+                    _ => {}
+                }
+            }
+        }
+
+        // Stable sorts, so a usage that appears twice keeps its first source
+        buttons.sort_by_key(|(usage, _)| *usage);
+        buttons.dedup_by_key(|(usage, _)| *usage);
+        axes.sort_by_key(|(usage, _)| *usage);
+        axes.dedup_by_key(|(usage, _)| *usage);
+
+        let normalization: Vec<AxisNormalization> = axes
+            .iter()
+            .map(|(usage, element)| {
+                let field = &descriptor.fields[element.field];
+                if axis_layout(*usage).1 == AxisKind::Stick && field.logical_min >= 0 {
+                    return AxisNormalization::centered(field.logical_min, field.logical_max);
+                }
+                let divisor = field
+                    .logical_min
+                    .unsigned_abs()
+                    .max(field.logical_max.unsigned_abs())
+                    .max(1);
+                AxisNormalization::new(field.logical_min, field.logical_max, divisor as f32)
+            })
+            .collect();
+        let mut layout = DeviceLayout::default();
+        for (usage, _) in &buttons {
+            layout.push_button(format!("button_{}", usage.id), ButtonKind::Other);
+        }
+        for index in 0..hats.len() {
+            layout.push_hat_buttons(index);
+        }
+        for (usage, _) in &axes {
+            let (name, kind) = axis_layout(*usage);
+            layout.push_axis(name, kind);
+        }
+        let button_count = layout.buttons.len();
+
+        Self {
+            descriptor,
+            buttons: buttons.into_iter().map(|(_, source)| source).collect(),
+            hats,
+            layout,
+            state: GamepadState {
+                buttons: vec![false; button_count],
+                axes: vec![0.0; axes.len()],
+                raw_axes: vec![0; axes.len()],
+                axis_normalization: normalization,
+            },
+            axes: axes.into_iter().map(|(_, element)| element).collect(),
+            reports: BTreeMap::new(),
+        }
+    }
+
+    pub fn descriptor(&self) -> &ReportDescriptor {
+        &self.descriptor
+    }
+
+    pub fn layout(&self) -> &DeviceLayout {
+        &self.layout
+    }
+
+    /// Applies an input report as read from the device, including its report
+    /// ID byte if the device uses them. Returns `false` for reports the
+    /// descriptor doesn't describe.
+    pub fn process(&mut self, report: &[u8]) -> bool {
+        let Some((report_id, payload)) = self.descriptor.split_report(report) else {
+            return false;
+        };
+        if self.descriptor.input_fields(report_id).next().is_none() {
+            return false;
+        }
+        self.reports.insert(report_id, report.to_vec());
+
+        let fields = &self.descriptor.fields;
+        let in_report = |field: usize| fields[field].report_id == report_id;
+        let value = |element: &Element| fields[element.field].value(payload, element.index);
+
+        for (button, source) in self.buttons.iter().enumerate() {
+            let pressed = match source {
+                ButtonSource::Variable(element) if in_report(element.field) => {
+                    value(element).map(|value| value != 0)
+                }
+                ButtonSource::Array { field, usage } if in_report(*field) => {
+                    let field = &fields[*field];
+                    Some((0..field.count).any(|index| {
+                        field
+                            .value(payload, index)
+                            .and_then(|value| value.checked_sub(field.logical_min))
+                            .and_then(|offset| field.usages.get(usize::try_from(offset).ok()?))
+                            == Some(usage)
+                    }))
+                }
+                _ => None,
+            };
+            if let Some(pressed) = pressed {
+                self.state.buttons[button] = pressed;
+            }
+        }
+
+        let first_hat_button = self.buttons.len();
+        for (hat, element) in self.hats.iter().enumerate() {
+            if !in_report(element.field) {
+                continue;
+            }
+            let field = &fields[element.field];
+            let directions = value(element)
+                .filter(|value| field.in_range(*value))
+                .map(|value| hat_directions(value - field.logical_min, field))
+                .unwrap_or_default();
+            let start = first_hat_button + hat * DeviceLayout::HAT_BUTTONS;
+            self.state.buttons[start..start + DeviceLayout::HAT_BUTTONS]
+                .copy_from_slice(&directions);
+        }
+
+        for (axis, element) in self.axes.iter().enumerate() {
+            if !in_report(element.field) {
+                continue;
+            }
+            if let Some(raw) = value(element) {
+                self.state.raw_axes[axis] = raw;
+                self.state.axes[axis] = self.state.axis_normalization[axis].apply(raw);
+            }
+        }
+
+        true
+    }
+
+    pub fn state(&self) -> GamepadState {
+        self.state.clone()
+    }
+
+    /// The latest report of each report ID, in ID order.
+    pub fn raw_reports(&self) -> Vec<RawReport> {
+        self.reports
+            .iter()
+            .map(|(report_id, bytes)| RawReport {
+                report_id: *report_id,
+                bytes: bytes.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Up, right, down and left for hat position `position`, counted clockwise
+/// from up in equal steps over the logical range.
+fn hat_directions(position: i32, field: &ReportField) -> [bool; DeviceLayout::HAT_BUTTONS] {
+    let positions = (field.logical_max - field.logical_min + 1).max(1);
+    let angle = position as f32 * 360.0 / positions as f32;
+    [
+        !(90.0..=270.0).contains(&angle),
+        angle > 0.0 && angle < 180.0,
+        angle > 90.0 && angle < 270.0,
+        angle > 180.0,
+    ]
+}
+
+/// Name and kind of an axis usage. Only X and Y are reliably a stick, the
+/// other axes mean different things on different gamepads.
+fn axis_layout(usage: Usage) -> (String, AxisKind) {
+    match (usage.page, usage.id) {
+        (USAGE_PAGE_GENERIC_DESKTOP, 0x30 | 0x31) => (
+            AXIS_NAMES[usize::from(usage.id - 0x30)].to_string(),
+            AxisKind::Stick,
+        ),
+        (USAGE_PAGE_GENERIC_DESKTOP, id) if AXIS_USAGES.contains(&id) => (
+            AXIS_NAMES[usize::from(id - 0x30)].to_string(),
+            AxisKind::Other,
+        ),
+        (USAGE_PAGE_SIMULATION, 0xc4) => ("accelerator".to_string(), AxisKind::Trigger),
+        (USAGE_PAGE_SIMULATION, 0xc5) => ("brake".to_string(), AxisKind::Trigger),
+        (USAGE_PAGE_SIMULATION, 0xba) => ("rudder".to_string(), AxisKind::Other),
+        (USAGE_PAGE_SIMULATION, 0xbb) => ("throttle".to_string(), AxisKind::Other),
+        (USAGE_PAGE_SIMULATION, 0xc8) => ("steering".to_string(), AxisKind::Other),
+        (page, id) => (format!("usage_{:04x}_{:04x}", page, id), AxisKind::Other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::{GP2040_DINPUT, HAYBOX_DINPUT, REPORT_IDS};
+    use super::*;
+
+    fn decoder(bytes: &[u8]) -> HidDecoder {
+        HidDecoder::new(ReportDescriptor::parse(bytes).unwrap())
+    }
+
+    #[test]
+    fn gp2040_layout() {
+        let decoder = decoder(GP2040_DINPUT);
+        let layout = decoder.layout();
+        let buttons: Vec<_> = layout.buttons.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(buttons.len(), 14 + DeviceLayout::HAT_BUTTONS);
+        assert_eq!(buttons[0], "button_1");
+        assert_eq!(buttons[13], "button_14");
+        assert_eq!(
+            &buttons[14..],
+            ["hat_0_up", "hat_0_right", "hat_0_down", "hat_0_left"]
+        );
+        let axes: Vec<_> = layout.axes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(axes, ["x", "y", "z", "rz"]);
+        assert_eq!(layout.axes[0].kind, AxisKind::Stick);
+        assert_eq!(layout.axes[2].kind, AxisKind::Other);
+    }
+
+    #[test]
+    fn gp2040_report() {
+        let mut decoder = decoder(GP2040_DINPUT);
+        // Buttons 1 and 3, hat right, X left, Y centered, Z right, Rz
+        // centered, no pressure
+        let mut report = vec![0x05, 0x00, 0x02, 0x00, 0x80, 0xff, 0x80];
+        report.extend([0; 12]);
+        assert!(decoder.process(&report));
+
+        let state = decoder.state();
+        let pressed: Vec<usize> = (0..14).filter(|&b| state.buttons[b]).collect();
+        assert_eq!(pressed, [0, 2]);
+        assert_eq!(state.buttons[14..], [false, true, false, false]);
+        assert_eq!(state.raw_axes, [0, 128, 255, 128]);
+        assert_eq!(state.axes[0], -1.0);
+        assert!((state.axes[1] - 0.5 / 127.5).abs() < 1e-6);
+        // Z isn't known to be a stick, so it keeps its 0..1 range
+        assert_eq!(state.axes[2], 1.0);
+
+        // Down-left, then the null state
+        report[2] = 5;
+        assert!(decoder.process(&report));
+        assert_eq!(decoder.state().buttons[14..], [false, false, true, true]);
+        report[2] = 0x0f;
+        assert!(decoder.process(&report));
+        assert_eq!(decoder.state().buttons[14..], [false; 4]);
+
+        let reports = decoder.raw_reports();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].report_id, None);
+        assert_eq!(reports[0].bytes, report);
+    }
+
+    #[test]
+    fn unsigned_sticks_rest_at_center() {
+        let mut decoder = decoder(GP2040_DINPUT);
+        let normalization = decoder.state().axis_normalization[0];
+        assert_eq!(
+            (normalization.center, normalization.divisor),
+            (127.5, 127.5)
+        );
+
+        // Both sticks at rest, hat centered
+        let mut report = vec![0x00, 0x00, 0x0f, 0x80, 0x80, 0x80, 0x80];
+        report.extend([0; 12]);
+        assert!(decoder.process(&report));
+        let state = decoder.state();
+        assert!(state.axes[..2].iter().all(|value| value.abs() < 0.01));
+
+        // Full travel reaches both ends
+        report[3] = 0xff;
+        report[4] = 0x00;
+        assert!(decoder.process(&report));
+        assert_eq!(decoder.state().axes[..2], [1.0, -1.0]);
+    }
+
+    #[test]
+    fn haybox_report() {
+        let mut decoder = decoder(HAYBOX_DINPUT);
+        assert_eq!(
+            decoder.layout().buttons.len(),
+            32 + DeviceLayout::HAT_BUTTONS
+        );
+        let axes: Vec<_> = decoder
+            .layout()
+            .axes
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect();
+        // Sorted by usage, not report order
+        assert_eq!(axes, ["x", "y", "z", "rx", "ry", "rz"]);
+
+        // X, Y, Z, Rz, Rx, Ry in the report, hat position 3 (right),
+        // buttons 1 and 32
+        let mut report = [
+            0x81, 0x7f, 0x00, 0x40, 0x00, 0xc0, 3, 0x01, 0x00, 0x00, 0x80,
+        ];
+        assert!(decoder.process(&report));
+        let state = decoder.state();
+        assert_eq!(state.raw_axes, [-127, 127, 0, 0, -64, 64]);
+        assert_eq!(state.axes[0], -1.0);
+        assert_eq!(state.axes[1], 1.0);
+        assert!((state.axes[5] - 64.0 / 127.0).abs() < 1e-6);
+        assert!(state.buttons[0] && state.buttons[31]);
+        assert_eq!(state.buttons[1..31], [false; 30]);
+        assert_eq!(state.buttons[32..], [false, true, false, false]);
+
+        // Up-right, then 0, below the logical minimum, is centered
+        report[6] = 2;
+        assert!(decoder.process(&report));
+        assert_eq!(decoder.state().buttons[32..], [true, true, false, false]);
+        report[6] = 0;
+        assert!(decoder.process(&report));
+        assert_eq!(decoder.state().buttons[32..], [false; 4]);
+    }
+
+    #[test]
+    fn report_ids_are_decoded_separately() {
+        let mut decoder = decoder(REPORT_IDS);
+        assert_eq!(decoder.layout().buttons.len(), 16);
+        let axes: Vec<_> = decoder
+            .layout()
+            .axes
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect();
+        assert_eq!(axes, ["x", "y", "z"]);
+
+        // Buttons 1, 3 and 16, X at 0 and Y at 255
+        assert!(decoder.process(&[1, 0x05, 0x00, 0xff, 0x80]));
+        // Z at -2047, 0x801 in 12 bits
+        assert!(decoder.process(&[2, 0x01, 0x08]));
+
+        let state = decoder.state();
+        let pressed: Vec<usize> = (0..16).filter(|&b| state.buttons[b]).collect();
+        assert_eq!(pressed, [0, 2, 15]);
+        assert_eq!(state.raw_axes, [0, 255, -2047]);
+        assert_eq!(state.axes, [-1.0, 1.0, -1.0]);
+
+        // Feature reports, unknown IDs and empty reads change nothing
+        assert!(!decoder.process(&[3, 1, 2, 3, 4]));
+        assert!(!decoder.process(&[9, 0xff, 0xff]));
+        assert!(!decoder.process(&[]));
+        assert_eq!(decoder.state().raw_axes, [0, 255, -2047]);
+
+        let reports: Vec<_> = decoder
+            .raw_reports()
+            .into_iter()
+            .map(|r| r.report_id)
+            .collect();
+        assert_eq!(reports, [Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn array_buttons() {
+        // Report ID 1, two 8-bit slots holding button usages 1 to 8
+        let mut decoder = decoder(&[
+            0x05, 0x01, 0x09, 0x04, 0xa1, 0x01, 0x85, 0x01, 0x05, 0x09, 0x19, 0x01, 0x29, 0x08,
+            0x15, 0x01, 0x25, 0x08, 0x75, 0x08, 0x95, 0x02, 0x81, 0x00, 0xc0,
+        ]);
+        assert_eq!(decoder.layout().buttons.len(), 8);
+        assert!(decoder.process(&[1, 3, 8]));
+        let state = decoder.state();
+        let pressed: Vec<usize> = (0..8).filter(|&b| state.buttons[b]).collect();
+        assert_eq!(pressed, [2, 7]);
+        assert!(decoder.process(&[1, 0, 0]));
+        assert_eq!(decoder.state().buttons, [false; 8]);
+    }
+}
@@ -0,0 +1,161 @@
+use super::models::{GamepadInfo, GamepadState, PacketStats, RawInputLayout, RawReport};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+pub mod evdev;
+pub mod hid;
+pub mod replay;
+pub mod sdl;
+pub mod sim;
+#[cfg(windows)]
+pub mod xinput;
+
+pub use evdev::{EvdevBackend, EvemuRecording};
+#[cfg(target_os = "linux")]
+pub use hid::HidBackend;
+pub use hid::ReportDescriptor;
+pub use replay::{PlaybackControl, PlaybackStatus, ReplayBackend};
+pub use sdl::SdlBackend;
+pub use sim::{SimBackend, SimScript};
+#[cfg(windows)]
+pub use xinput::XInputBackend;
+
+/// Every `InputBackend::kind` there is, whether or not its backend runs on
+/// this platform.
+pub const CONTROLLER_TYPES: [&str; 6] = ["xinput", "sdl", "evdev", "hid", "sim", "replay"];
+
+/// Where backends that play back scripted or recorded input read the time
+/// from.
+pub(crate) enum ScriptClock {
+    Realtime(Instant),
+    /// Time only moves when `advance` is called, for deterministic tests.
+    Manual(Duration),
+}
+
+impl ScriptClock {
+    pub(crate) fn realtime() -> Self {
+        Self::Realtime(Instant::now())
+    }
+
+    pub(crate) fn manual() -> Self {
+        Self::Manual(Duration::ZERO)
+    }
+
+    /// Time since the clock was created.
+    pub(crate) fn elapsed(&self) -> Duration {
+        match self {
+            Self::Realtime(started) => started.elapsed(),
+            Self::Manual(now) => *now,
+        }
+    }
+
+    /// Moves a manual clock forward. Has no effect on a realtime clock.
+    pub(crate) fn advance(&mut self, by: Duration) {
+        if let Self::Manual(now) = self {
+            *now += by;
+        }
+    }
+}
+
+/// Device arrival/removal reported by a backend during `poll`.
+#[derive(Debug, Clone)]
+pub enum HotplugEvent {
+    Connected(GamepadInfo),
+    Disconnected { id: usize, controller_type: String },
+}
+
+/// A distinct input report received from a device, e.g. a new XInput packet
+/// or a batch of SDL input events.
+#[derive(Debug, Clone, Copy)]
+pub struct InputReport {
+    pub id: usize,
+    pub received_at: Instant,
+    /// When the OS stamped the report, in microseconds on a clock of the
+    /// backend's choosing (evdev's kernel event time). Only the difference
+    /// between two reports from one device is meaningful.
+    pub device_time_us: Option<u64>,
+}
+
+/// What a backend can tell us about the devices it drives.
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct BackendCapabilities {
+    /// Hotplug comes from OS events rather than from probing slots.
+    pub hotplug_events: bool,
+    /// `GamepadInfo` carries USB vendor/product IDs.
+    pub vendor_product_ids: bool,
+    /// Upper bound on simultaneously connected devices, if the API has one.
+    pub max_devices: Option<usize>,
+    /// `drain_reports` reports individual input reports.
+    pub report_timestamps: bool,
+}
+
+/// A source of gamepad input (XInput, SDL, ...).
+///
+/// Backends are owned by `ControllerManager`, which polls them and routes
+/// `get_gamepads`/`get_gamepad_state` by `kind()`, so the Tauri commands
+/// never need to know which API a device came from.
+pub trait InputBackend: Send {
+    /// Identifier exposed to the frontend as `GamepadInfo::controller_type`.
+    fn kind(&self) -> &'static str;
+
+    fn capabilities(&self) -> BackendCapabilities;
+
+    /// Pumps pending OS events and returns the hotplug changes seen since the
+    /// previous call.
+    fn poll(&mut self) -> Vec<HotplugEvent>;
+
+    /// Lists the devices currently connected through this backend.
+    fn enumerate(&self) -> Vec<GamepadInfo>;
+
+    /// Reads the current state of device `id`, or `None` if it is not connected.
+    fn read_state(&mut self, id: usize) -> Option<GamepadState>;
+
+    /// Returns the input reports observed during `poll` since the previous
+    /// call, oldest first. Used for report rate measurement.
+    fn drain_reports(&mut self) -> Vec<InputReport> {
+        Vec::new()
+    }
+
+    /// Packet sequence counters for device `id`, if the backend's reports
+    /// are numbered.
+    fn packet_stats(&self, _id: usize) -> Option<PacketStats> {
+        None
+    }
+
+    fn reset_packet_stats(&mut self, _id: usize) {}
+
+    /// The event codes and kernel ranges behind device `id`'s buttons and
+    /// axes, if the backend reads raw kernel events.
+    fn raw_layout(&self, _id: usize) -> Option<RawInputLayout> {
+        None
+    }
+
+    /// The latest input report of each report ID from device `id`, byte for
+    /// byte, if the backend reads raw reports.
+    fn raw_reports(&self, _id: usize) -> Vec<RawReport> {
+        Vec::new()
+    }
+
+    /// Device `id`'s parsed HID report descriptor, if the backend reads raw
+    /// reports.
+    fn report_descriptor(&self, _id: usize) -> Option<ReportDescriptor> {
+        None
+    }
+
+    /// Pauses, seeks or retimes a backend that plays back recorded input and
+    /// returns its playback status. `None` for live backends.
+    fn control_playback(&mut self, _control: PlaybackControl) -> Option<PlaybackStatus> {
+        None
+    }
+}
+
+/// Ids of the devices in the reports `backend` queued since the last drain,
+/// oldest first.
+#[cfg(test)]
+pub(crate) fn drained_report_ids(backend: &mut dyn InputBackend) -> Vec<usize> {
+    backend
+        .drain_reports()
+        .iter()
+        .map(|report| report.id)
+        .collect()
+}
@@ -0,0 +1,502 @@
+//! Plays a session recording back as virtual controllers.
+//!
+//! Every device in the recording shows up as its own `replay` device, with
+//! ids assigned in order of first appearance, so recordings that mix SDL and
+//! XInput devices with the same id don't collide. Playback position is
+//! measured from the start of the recording and can be paused, sought and
+//! sped up or slowed down while running.
+
+use super::{BackendCapabilities, HotplugEvent, InputBackend, InputReport, ScriptClock};
+use crate::models::{DeviceKey, DeviceLayout, GamepadInfo, GamepadState};
+use crate::recording::{RecordedEvent, Recording};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+pub const MIN_PLAYBACK_SPEED: f64 = 0.01;
+pub const MAX_PLAYBACK_SPEED: f64 = 100.0;
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlaybackControl {
+    Pause,
+    /// Resumes playback, starting over if the end was reached.
+    Resume,
+    Seek {
+        position_us: u64,
+    },
+    SetSpeed {
+        speed: f64,
+    },
+    /// Changes nothing, only reports the status.
+    Status,
+}
+
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct PlaybackStatus {
+    pub position_us: u64,
+    pub duration_us: u64,
+    pub speed: f64,
+    pub paused: bool,
+}
+
+enum ReplayAction {
+    Connect,
+    Disconnect,
+    State(GamepadState),
+}
+
+struct ReplayEvent {
+    /// Microseconds from the start of the recording.
+    position_us: u64,
+    device: usize,
+    action: ReplayAction,
+}
+
+pub struct ReplayBackend {
+    /// Device info by replay id.
+    infos: Vec<GamepadInfo>,
+    /// Devices connected when the recording started.
+    initial_devices: Vec<usize>,
+    events: Vec<ReplayEvent>,
+    duration_us: u64,
+    clock: ScriptClock,
+    /// Clock reading and playback position at the last pause, seek or speed
+    /// change.
+    anchor: (Duration, u64),
+    speed: f64,
+    paused: bool,
+    /// Index of the next event to apply.
+    cursor: usize,
+    /// Position up to which events have been applied, `None` before the
+    /// first poll.
+    applied_us: Option<u64>,
+    /// Connected devices and their latest state.
+    devices: BTreeMap<usize, Option<GamepadState>>,
+    pending_reports: Vec<InputReport>,
+}
+
+impl ReplayBackend {
+    pub fn new(recording: Recording) -> Self {
+        Self::with_clock(recording, ScriptClock::realtime())
+    }
+
+    /// Creates a backend whose clock only moves through
+    /// [`ReplayBackend::advance`].
+    pub fn manual(recording: Recording) -> Self {
+        Self::with_clock(recording, ScriptClock::manual())
+    }
+
+    fn with_clock(recording: Recording, clock: ScriptClock) -> Self {
+        let started_us = recording.header.started_us;
+        let mut ids: Vec<DeviceKey> = Vec::new();
+        let mut infos: Vec<GamepadInfo> = Vec::new();
+
+        let mut register = |key: DeviceKey, info: Option<&GamepadInfo>| -> usize {
+            if let Some(id) = ids.iter().position(|known| *known == key) {
+                return id;
+            }
+            let id = ids.len();
+            infos.push(GamepadInfo {
+                id,
+                name: info
+                    .map(|info| info.name.clone())
+                    .unwrap_or_else(|| format!("{} device {}", key.controller_type, key.id)),
+                controller_type: "replay".to_string(),
+                vendor_id: info.and_then(|info| info.vendor_id),
+                product_id: info.and_then(|info| info.product_id),
+                joystick: info.and_then(|info| info.joystick),
+                layout: info.map(|info| info.layout.clone()).unwrap_or_default(),
+            });
+            ids.push(key);
+            id
+        };
+
+        let initial_devices = recording
+            .header
+            .devices
+            .iter()
+            .map(|info| register(DeviceKey::new(&info.controller_type, info.id), Some(info)))
+            .collect();
+
+        let mut events: Vec<ReplayEvent> = recording
+            .events
+            .into_iter()
+            .map(|event| {
+                let position_us = event.timestamp_us().saturating_sub(started_us);
+                let (device, action) = match event {
+                    RecordedEvent::Connected { info, .. } => (
+                        register(DeviceKey::new(&info.controller_type, info.id), Some(&info)),
+                        ReplayAction::Connect,
+                    ),
+                    RecordedEvent::Disconnected { device, .. } => {
+                        (register(device, None), ReplayAction::Disconnect)
+                    }
+                    RecordedEvent::Sample { device, state, .. } => {
+                        (register(device, None), ReplayAction::State(state))
+                    }
+                };
+                ReplayEvent {
+                    position_us,
+                    device,
+                    action,
+                }
+            })
+            .collect();
+        events.sort_by_key(|event| event.position_us);
+
+        // Recordings from before layouts existed, and devices only seen in
+        // samples, get a layout guessed from their first state
+        for info in infos.iter_mut().filter(|info| info.layout.is_empty()) {
+            let first_state = events.iter().find_map(|event| match &event.action {
+                ReplayAction::State(state) if event.device == info.id => Some(state),
+                _ => None,
+            });
+            if let Some(state) = first_state {
+                info.layout = DeviceLayout::for_counts(state.buttons.len(), state.axes.len());
+            }
+        }
+
+        Self {
+            infos,
+            initial_devices,
+            duration_us: events.last().map_or(0, |event| event.position_us),
+            events,
+            clock,
+            anchor: (Duration::ZERO, 0),
+            speed: 1.0,
+            paused: false,
+            cursor: 0,
+            applied_us: None,
+            devices: BTreeMap::new(),
+            pending_reports: Vec::new(),
+        }
+    }
+
+    /// Moves a manual clock forward. Has no effect on a realtime clock.
+    pub fn advance(&mut self, by: Duration) {
+        self.clock.advance(by);
+    }
+
+    fn position_us(&self) -> u64 {
+        let (anchor_clock, anchor_position) = self.anchor;
+        if self.paused {
+            return anchor_position;
+        }
+        let elapsed = self.clock.elapsed().saturating_sub(anchor_clock);
+        let position = anchor_position + (elapsed.as_micros() as f64 * self.speed) as u64;
+        position.min(self.duration_us)
+    }
+
+    /// Restarts the position count from `position_us` at the current time.
+    fn set_anchor(&mut self, position_us: u64) {
+        self.anchor = (self.clock.elapsed(), position_us.min(self.duration_us));
+    }
+
+    pub fn control(&mut self, control: PlaybackControl) -> PlaybackStatus {
+        match control {
+            PlaybackControl::Pause => {
+                self.set_anchor(self.position_us());
+                self.paused = true;
+            }
+            PlaybackControl::Resume => {
+                let position = self.position_us();
+                let restart = position >= self.duration_us;
+                self.set_anchor(if restart { 0 } else { position });
+                self.paused = false;
+            }
+            PlaybackControl::Seek { position_us } => self.set_anchor(position_us),
+            PlaybackControl::SetSpeed { speed } => {
+                self.set_anchor(self.position_us());
+                self.speed = speed.clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED);
+            }
+            PlaybackControl::Status => {}
+        }
+        self.status()
+    }
+
+    pub fn status(&self) -> PlaybackStatus {
+        PlaybackStatus {
+            position_us: self.position_us(),
+            duration_us: self.duration_us,
+            speed: self.speed,
+            paused: self.paused,
+        }
+    }
+
+    /// Returns to the start of the recording.
+    fn rewind(&mut self) {
+        self.cursor = 0;
+        self.devices = self.initial_devices.iter().map(|id| (*id, None)).collect();
+    }
+}
+
+impl InputBackend for ReplayBackend {
+    fn kind(&self) -> &'static str {
+        "replay"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            hotplug_events: true,
+            vendor_product_ids: true,
+            max_devices: None,
+            report_timestamps: true,
+        }
+    }
+
+    fn poll(&mut self) -> Vec<HotplugEvent> {
+        let target = self.position_us();
+        if target >= self.duration_us && !self.paused {
+            // Hold the last state at the end instead of running on
+            self.set_anchor(self.duration_us);
+            self.paused = true;
+        }
+
+        let before: Vec<usize> = self.devices.keys().copied().collect();
+        let previous = self.applied_us;
+        if previous.is_none_or(|applied| target < applied) {
+            self.rewind();
+        }
+
+        let received_at = Instant::now();
+        let mut reported: Vec<usize> = Vec::new();
+        while let Some(event) = self.events.get(self.cursor) {
+            if event.position_us > target {
+                break;
+            }
+            self.cursor += 1;
+            match &event.action {
+                ReplayAction::Connect => {
+                    self.devices.entry(event.device).or_insert(None);
+                }
+                ReplayAction::Disconnect => {
+                    self.devices.remove(&event.device);
+                }
+                ReplayAction::State(state) => {
+                    self.devices.insert(event.device, Some(state.clone()));
+                    // Only events played in real time count as reports, not
+                    // the ones re-applied after seeking backwards
+                    let is_new = previous.is_none_or(|applied| event.position_us > applied);
+                    if is_new && !reported.contains(&event.device) {
+                        reported.push(event.device);
+                    }
+                }
+            }
+        }
+        self.applied_us = Some(target);
+        self.pending_reports
+            .extend(reported.into_iter().map(|id| InputReport {
+                id,
+                received_at,
+                device_time_us: None,
+            }));
+
+        let mut hotplug: Vec<HotplugEvent> = before
+            .iter()
+            .filter(|id| !self.devices.contains_key(id))
+            .map(|id| HotplugEvent::Disconnected {
+                id: *id,
+                controller_type: self.kind().to_string(),
+            })
+            .collect();
+        hotplug.extend(
+            self.devices
+                .keys()
+                .filter(|id| !before.contains(id))
+                .map(|id| HotplugEvent::Connected(self.infos[*id].clone())),
+        );
+        hotplug
+    }
+
+    fn enumerate(&self) -> Vec<GamepadInfo> {
+        self.devices
+            .keys()
+            .map(|id| self.infos[*id].clone())
+            .collect()
+    }
+
+    fn read_state(&mut self, id: usize) -> Option<GamepadState> {
+        self.devices.get(&id)?.clone()
+    }
+
+    fn drain_reports(&mut self) -> Vec<InputReport> {
+        std::mem::take(&mut self.pending_reports)
+    }
+
+    fn control_playback(&mut self, control: PlaybackControl) -> Option<PlaybackStatus> {
+        Some(self.control(control))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::drained_report_ids;
+    use crate::models::AxisNormalization;
+    use crate::recording::RecordingHeader;
+
+    const STARTED_US: u64 = 5_000_000;
+
+    fn info(controller_type: &str, id: usize, name: &str) -> GamepadInfo {
+        GamepadInfo {
+            id,
+            name: name.to_string(),
+            controller_type: controller_type.to_string(),
+            vendor_id: Some(0x045e),
+            product_id: Some(0x028e),
+            joystick: None,
+            layout: DeviceLayout::standard(),
+        }
+    }
+
+    fn state(pressed: bool) -> GamepadState {
+        GamepadState {
+            buttons: vec![pressed, false],
+            axes: vec![0.0],
+            raw_axes: vec![0],
+            axis_normalization: vec![AxisNormalization::new(-32767, 32767, 32767.0)],
+        }
+    }
+
+    fn sample(at_ms: u64, device: DeviceKey, pressed: bool) -> RecordedEvent {
+        RecordedEvent::Sample {
+            timestamp_us: STARTED_US + at_ms * 1000,
+            device,
+            state: state(pressed),
+        }
+    }
+
+    /// An SDL pad connected from the start, an XInput pad with the same id
+    /// plugged in later, and an SDL device only seen in samples.
+    fn recording() -> Recording {
+        let sdl = DeviceKey::new("sdl", 3);
+        let xinput = DeviceKey::new("xinput", 3);
+        Recording {
+            header: RecordingHeader::new(STARTED_US, 1000, vec![info("sdl", 3, "Pad A")]),
+            events: vec![
+                sample(10, sdl.clone(), true),
+                RecordedEvent::Connected {
+                    timestamp_us: STARTED_US + 20_000,
+                    info: info("xinput", 3, "Pad B"),
+                },
+                sample(30, xinput, false),
+                RecordedEvent::Disconnected {
+                    timestamp_us: STARTED_US + 40_000,
+                    device: sdl,
+                },
+                sample(50, DeviceKey::new("sdl", 7), true),
+            ],
+        }
+    }
+
+    #[test]
+    fn playback() {
+        let mut backend = ReplayBackend::manual(recording());
+        assert_eq!(backend.status().duration_us, 50_000);
+
+        match backend.poll().as_slice() {
+            [HotplugEvent::Connected(info)] => {
+                assert_eq!((info.id, info.name.as_str()), (0, "Pad A"));
+                assert_eq!(info.controller_type, "replay");
+                assert_eq!(info.vendor_id, Some(0x045e));
+            }
+            events => panic!("expected one connection, got {:?}", events),
+        }
+        assert!(backend.read_state(0).is_none());
+        assert!(drained_report_ids(&mut backend).is_empty());
+
+        backend.advance(Duration::from_millis(10));
+        assert!(backend.poll().is_empty());
+        assert_eq!(drained_report_ids(&mut backend), [0]);
+        assert!(backend.read_state(0).unwrap().buttons[0]);
+
+        // Devices from different backends with the same id stay apart
+        backend.advance(Duration::from_millis(10));
+        assert!(matches!(
+            backend.poll().as_slice(),
+            [HotplugEvent::Connected(info)] if info.id == 1 && info.name == "Pad B"
+        ));
+
+        backend.advance(Duration::from_millis(20));
+        assert!(matches!(
+            backend.poll().as_slice(),
+            [HotplugEvent::Disconnected { id: 0, .. }]
+        ));
+        assert_eq!(drained_report_ids(&mut backend), [1]);
+        assert!(backend.read_state(0).is_none());
+
+        // A device only seen in samples gets a name and a guessed layout
+        backend.advance(Duration::from_millis(10));
+        match backend.poll().as_slice() {
+            [HotplugEvent::Connected(info)] => {
+                assert_eq!((info.id, info.name.as_str()), (2, "sdl device 7"));
+                assert_eq!(info.layout.buttons.len(), 2);
+                assert_eq!(info.layout.axes.len(), 1);
+            }
+            events => panic!("expected one connection, got {:?}", events),
+        }
+
+        // Playback holds the last state at the end
+        backend.advance(Duration::from_millis(100));
+        assert!(backend.poll().is_empty());
+        let status = backend.status();
+        assert_eq!(status.position_us, 50_000);
+        assert!(status.paused);
+        let ids: Vec<usize> = backend.enumerate().iter().map(|info| info.id).collect();
+        assert_eq!(ids, [1, 2]);
+    }
+
+    #[test]
+    fn controls() {
+        let mut backend = ReplayBackend::manual(recording());
+        backend.poll();
+
+        let status = backend.control(PlaybackControl::Pause);
+        assert!(status.paused);
+        backend.advance(Duration::from_millis(30));
+        backend.poll();
+        assert_eq!(backend.status().position_us, 0);
+        assert!(backend.read_state(0).is_none());
+
+        backend.control(PlaybackControl::Seek {
+            position_us: 30_000,
+        });
+        assert!(matches!(
+            backend.poll().as_slice(),
+            [HotplugEvent::Connected(info)] if info.id == 1
+        ));
+        backend.drain_reports();
+
+        // Seeking backwards replays the recording from the start, without
+        // counting the replayed samples as reports
+        backend.control(PlaybackControl::Seek {
+            position_us: 15_000,
+        });
+        assert!(matches!(
+            backend.poll().as_slice(),
+            [HotplugEvent::Disconnected { id: 1, .. }]
+        ));
+        assert!(drained_report_ids(&mut backend).is_empty());
+        assert!(backend.read_state(0).unwrap().buttons[0]);
+
+        backend.control(PlaybackControl::SetSpeed { speed: 2.0 });
+        let status = backend.control(PlaybackControl::Resume);
+        assert!(!status.paused);
+        assert_eq!(status.position_us, 15_000);
+        backend.advance(Duration::from_millis(10));
+        assert_eq!(backend.status().position_us, 35_000);
+
+        let status = backend.control(PlaybackControl::SetSpeed { speed: 1000.0 });
+        assert_eq!(status.speed, MAX_PLAYBACK_SPEED);
+
+        // Resuming at the end starts over
+        backend.control(PlaybackControl::Pause);
+        backend.control(PlaybackControl::Seek {
+            position_us: 80_000,
+        });
+        assert_eq!(backend.status().position_us, 50_000);
+        let status = backend.control(PlaybackControl::Resume);
+        assert_eq!(status.position_us, 0);
+    }
+}
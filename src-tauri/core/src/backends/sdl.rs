@@ -0,0 +1,617 @@
+use super::{BackendCapabilities, HotplugEvent, InputBackend, InputReport};
+use crate::models::{
+    AxisKind, AxisNormalization, ButtonKind, ControllerData, DeviceLayout, GamepadInfo,
+    GamepadState, JoystickLayout,
+};
+use crate::utils::{
+    map_sdl_axis, map_sdl_button, normalize_sdl_axis, sdl_axis_normalization, SDL_EXTENDED_BUTTONS,
+};
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::joystick::{Guid, HatState, Joystick};
+use std::collections::HashMap;
+use std::time::Instant;
+use tracing::{debug, error, info, trace, warn};
+
+/// Game controller axes in standard layout order, see `map_sdl_axis`.
+const SDL_AXES: [Axis; 6] = [
+    Axis::LeftX,
+    Axis::LeftY,
+    Axis::RightX,
+    Axis::RightY,
+    Axis::TriggerLeft,
+    Axis::TriggerRight,
+];
+
+/// Native joystick axes and ball motion, in SDL's signed 16-bit range.
+const JOYSTICK_AXIS_NORMALIZATION: AxisNormalization =
+    AxisNormalization::new(-32768, 32767, 32767.0);
+
+pub struct SdlBackend {
+    context: sdl2::Sdl,
+    joystick_subsystem: Option<sdl2::JoystickSubsystem>,
+    game_controller_subsystem: sdl2::GameControllerSubsystem,
+    controllers: Vec<GameController>,
+    /// Joysticks without a game controller mapping, read through the
+    /// joystick API.
+    joysticks: Vec<Joystick>,
+    controller_states: HashMap<u32, ControllerData>,
+    pending_reports: Vec<InputReport>,
+}
+
+// SDL handles are only ever touched while holding the `ControllerManager` lock.
+unsafe impl Send for SdlBackend {}
+
+impl SdlBackend {
+    pub fn new() -> Option<Self> {
+        // Try to get SDL controller mappings from environment first
+        if let Ok(mappings) = std::env::var("SDL_GAMECONTROLLERCONFIG") {
+            debug!("Using mappings from SDL_GAMECONTROLLERCONFIG");
+            std::env::set_var("SDL_GAMECONTROLLERCONFIG", mappings);
+        }
+
+        let context = match sdl2::init() {
+            Ok(context) => {
+                info!("SDL initialized");
+                context
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to initialize SDL");
+                return None;
+            }
+        };
+
+        // First initialize joystick subsystem
+        let joystick_subsystem = match context.joystick() {
+            Ok(js) => {
+                debug!("SDL joystick subsystem initialized");
+                js.set_event_state(true);
+                Some(js)
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to initialize SDL joystick subsystem");
+                None
+            }
+        };
+
+        // Then initialize game controller subsystem
+        let game_controller_subsystem = match context.game_controller() {
+            Ok(game_controller) => {
+                debug!("SDL game controller subsystem initialized");
+                game_controller.set_event_state(true);
+                game_controller
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to initialize SDL game controller subsystem");
+                return None;
+            }
+        };
+
+        let mut backend = Self {
+            context,
+            joystick_subsystem,
+            game_controller_subsystem,
+            controllers: Vec::new(),
+            joysticks: Vec::new(),
+            controller_states: HashMap::new(),
+            pending_reports: Vec::new(),
+        };
+
+        // If we have joystick subsystem, scan for devices
+        if let Some(js) = &backend.joystick_subsystem {
+            let available = js.num_joysticks().unwrap_or(0);
+            info!(count = available, "Found joystick devices");
+
+            for id in 0..available {
+                if backend.game_controller_subsystem.is_game_controller(id) {
+                    backend.open_controller(id);
+                } else {
+                    backend.open_joystick(id);
+                }
+            }
+        }
+
+        Some(backend)
+    }
+
+    /// Opens the joystick at `index` as a game controller and starts tracking
+    /// its state. Returns the device info on success.
+    fn open_controller(&mut self, index: u32) -> Option<GamepadInfo> {
+        let controller = match self.game_controller_subsystem.open(index) {
+            Ok(controller) => controller,
+            Err(e) => {
+                warn!(index, error = %e, "Failed to open game controller");
+                return None;
+            }
+        };
+
+        debug!(
+            id = controller.instance_id(),
+            name = %controller.name(),
+            mapping = %controller.mapping(),
+            "Opened game controller"
+        );
+
+        let guid = self
+            .joystick_subsystem
+            .as_ref()
+            .and_then(|js| js.device_guid(index).ok());
+        let (vendor_id, product_id) = guid.map(parse_guid_ids).unwrap_or((None, None));
+
+        // Extended buttons only exist if the mapping binds them
+        let mapping = controller.mapping();
+        let bound: Vec<&str> = mapping
+            .split(',')
+            .skip(2)
+            .filter_map(|entry| entry.split_once(':').map(|(name, _)| name))
+            .collect();
+        let mut layout = DeviceLayout::standard();
+        for (_, name, kind) in SDL_EXTENDED_BUTTONS {
+            if bound.contains(&name) {
+                layout.push_button(name, kind);
+            }
+        }
+
+        let instance_id = controller.instance_id();
+        let data = ControllerData {
+            name: controller.name(),
+            vendor_id,
+            product_id,
+            buttons: vec![false; layout.buttons.len()],
+            axes: vec![0.0; layout.axes.len()],
+            raw_axes: vec![0; layout.axes.len()],
+            joystick: None,
+            layout,
+        };
+        let info = self.info_for(instance_id, &data);
+        self.controller_states.insert(instance_id, data);
+        self.controllers.push(controller);
+
+        Some(info)
+    }
+
+    /// Opens the joystick at `index`, which has no game controller mapping,
+    /// with its native axes, buttons, hats and balls. Returns the device info
+    /// on success.
+    fn open_joystick(&mut self, index: u32) -> Option<GamepadInfo> {
+        let joystick_subsystem = self.joystick_subsystem.as_ref()?;
+        let joystick = match joystick_subsystem.open(index) {
+            Ok(joystick) => joystick,
+            Err(e) => {
+                warn!(index, error = %e, "Failed to open joystick");
+                return None;
+            }
+        };
+
+        let layout = JoystickLayout {
+            axes: joystick.num_axes(),
+            buttons: joystick.num_buttons(),
+            hats: joystick.num_hats(),
+            balls: joystick.num_balls(),
+        };
+        debug!(
+            id = joystick.instance_id(),
+            name = %joystick.name(),
+            axes = layout.axes,
+            buttons = layout.buttons,
+            hats = layout.hats,
+            balls = layout.balls,
+            "Opened joystick without a game controller mapping"
+        );
+
+        let (vendor_id, product_id) = parse_guid_ids(joystick.guid());
+        let device_layout = joystick_device_layout(layout);
+        let instance_id = joystick.instance_id();
+        let data = ControllerData {
+            name: joystick.name(),
+            vendor_id,
+            product_id,
+            buttons: vec![false; device_layout.buttons.len()],
+            axes: vec![0.0; device_layout.axes.len()],
+            raw_axes: vec![0; device_layout.axes.len()],
+            joystick: Some(layout),
+            layout: device_layout,
+        };
+        let info = self.info_for(instance_id, &data);
+        self.controller_states.insert(instance_id, data);
+        self.joysticks.push(joystick);
+
+        Some(info)
+    }
+
+    fn close_controller(&mut self, instance_id: u32) -> Option<HotplugEvent> {
+        self.controllers.retain(|c| c.instance_id() != instance_id);
+        self.joysticks.retain(|j| j.instance_id() != instance_id);
+        self.controller_states
+            .remove(&instance_id)
+            .map(|_| HotplugEvent::Disconnected {
+                id: instance_id as usize,
+                controller_type: self.kind().to_string(),
+            })
+    }
+
+    fn info_for(&self, instance_id: u32, data: &ControllerData) -> GamepadInfo {
+        GamepadInfo {
+            id: instance_id as usize,
+            name: data.name.clone(),
+            controller_type: self.kind().to_string(),
+            vendor_id: data.vendor_id,
+            product_id: data.product_id,
+            joystick: data.joystick,
+            layout: data.layout.clone(),
+        }
+    }
+
+    /// State of the joystick `which` if it was opened without a game
+    /// controller mapping.
+    fn joystick_state(&mut self, which: u32) -> Option<&mut ControllerData> {
+        self.controller_states
+            .get_mut(&which)
+            .filter(|data| data.joystick.is_some())
+    }
+
+    fn refresh_states(&mut self) {
+        for controller in self.controllers.iter() {
+            if let Some(state) = self.controller_states.get_mut(&controller.instance_id()) {
+                // Update axes
+                for axis in &SDL_AXES {
+                    let value = controller.axis(*axis);
+                    let axis_index = map_sdl_axis(*axis);
+                    if axis_index < state.axes.len() {
+                        state.axes[axis_index] = normalize_sdl_axis(*axis, value);
+                        state.raw_axes[axis_index] = i32::from(value);
+                    }
+                }
+
+                // Update buttons
+                for button in [
+                    Button::A,
+                    Button::B,
+                    Button::X,
+                    Button::Y,
+                    Button::Back,
+                    Button::Guide,
+                    Button::Start,
+                    Button::LeftStick,
+                    Button::RightStick,
+                    Button::LeftShoulder,
+                    Button::RightShoulder,
+                    Button::DPadUp,
+                    Button::DPadDown,
+                    Button::DPadLeft,
+                    Button::DPadRight,
+                ]
+                .into_iter()
+                .chain(SDL_EXTENDED_BUTTONS.map(|(button, _, _)| button))
+                {
+                    if let Some(button_index) = button_index(state, button) {
+                        state.buttons[button_index] = controller.button(button);
+                    }
+                }
+            }
+        }
+
+        // Balls only report motion through events, so only axes, buttons
+        // and hats are read back here
+        for joystick in self.joysticks.iter() {
+            let Some(state) = self.controller_states.get_mut(&joystick.instance_id()) else {
+                continue;
+            };
+            let Some(layout) = state.joystick else {
+                continue;
+            };
+            for axis in 0..layout.axes {
+                if let Ok(value) = joystick.axis(axis) {
+                    set_joystick_axis(state, axis as usize, value);
+                }
+            }
+            for button in 0..layout.buttons {
+                if let Ok(pressed) = joystick.button(button) {
+                    state.buttons[button as usize] = pressed;
+                }
+            }
+            for hat in 0..layout.hats {
+                if let Ok(hat_state) = joystick.hat(hat) {
+                    set_joystick_hat(state, layout, hat as usize, hat_state);
+                }
+            }
+        }
+    }
+}
+
+impl InputBackend for SdlBackend {
+    fn kind(&self) -> &'static str {
+        "sdl"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            hotplug_events: true,
+            vendor_product_ids: true,
+            max_devices: None,
+            report_timestamps: true,
+        }
+    }
+
+    fn poll(&mut self) -> Vec<HotplugEvent> {
+        let mut hotplug = Vec::new();
+
+        let mut event_pump = match self.context.event_pump() {
+            Ok(event_pump) => event_pump,
+            Err(_) => return hotplug,
+        };
+
+        // Devices that sent input events this tick
+        let mut reported: Vec<u32> = Vec::new();
+
+        // Drain everything that queued up since the last tick
+        for event in event_pump.poll_iter() {
+            if let sdl2::event::Event::ControllerAxisMotion { which, .. }
+            | sdl2::event::Event::ControllerButtonDown { which, .. }
+            | sdl2::event::Event::ControllerButtonUp { which, .. } = event
+            {
+                if !reported.contains(&which) {
+                    reported.push(which);
+                }
+            }
+            // Mapped controllers send these too, next to the controller events
+            if let sdl2::event::Event::JoyAxisMotion { which, .. }
+            | sdl2::event::Event::JoyBallMotion { which, .. }
+            | sdl2::event::Event::JoyHatMotion { which, .. }
+            | sdl2::event::Event::JoyButtonDown { which, .. }
+            | sdl2::event::Event::JoyButtonUp { which, .. } = event
+            {
+                if self.joystick_state(which).is_some() && !reported.contains(&which) {
+                    reported.push(which);
+                }
+            }
+
+            match event {
+                sdl2::event::Event::JoyDeviceAdded { which, .. } => {
+                    debug!(index = which, "Joystick added");
+                    let info = if self.game_controller_subsystem.is_game_controller(which) {
+                        self.open_controller(which)
+                    } else {
+                        self.open_joystick(which)
+                    };
+                    hotplug.extend(info.map(HotplugEvent::Connected));
+                }
+                sdl2::event::Event::JoyDeviceRemoved { which, .. } => {
+                    debug!(id = which, "Joystick removed");
+                    hotplug.extend(self.close_controller(which));
+                }
+                sdl2::event::Event::ControllerDeviceAdded { which, .. } => {
+                    debug!(index = which, "Game controller added");
+                }
+                sdl2::event::Event::ControllerDeviceRemoved { which, .. } => {
+                    debug!(id = which, "Game controller removed");
+                    hotplug.extend(self.close_controller(which));
+                }
+                sdl2::event::Event::ControllerAxisMotion {
+                    which, axis, value, ..
+                } => {
+                    trace!(id = which, axis = ?axis, value, "Axis motion");
+                    let axis_index = map_sdl_axis(axis);
+                    if let Some(controller_data) = self.controller_states.get_mut(&which) {
+                        if axis_index < controller_data.axes.len() {
+                            controller_data.axes[axis_index] = normalize_sdl_axis(axis, value);
+                            controller_data.raw_axes[axis_index] = i32::from(value);
+                        }
+                    }
+                }
+                sdl2::event::Event::ControllerButtonDown { which, button, .. } => {
+                    trace!(id = which, button = ?button, "Button down");
+                    if let Some(controller_data) = self.controller_states.get_mut(&which) {
+                        if let Some(button_index) = button_index(controller_data, button) {
+                            controller_data.buttons[button_index] = true;
+                        }
+                    }
+                }
+                sdl2::event::Event::ControllerButtonUp { which, button, .. } => {
+                    trace!(id = which, button = ?button, "Button up");
+                    if let Some(controller_data) = self.controller_states.get_mut(&which) {
+                        if let Some(button_index) = button_index(controller_data, button) {
+                            controller_data.buttons[button_index] = false;
+                        }
+                    }
+                }
+                sdl2::event::Event::JoyAxisMotion {
+                    which,
+                    axis_idx,
+                    value,
+                    ..
+                } => {
+                    if let Some(state) = self.joystick_state(which) {
+                        trace!(id = which, axis = axis_idx, value, "Joystick axis motion");
+                        if usize::from(axis_idx) < state.axes.len() {
+                            set_joystick_axis(state, usize::from(axis_idx), value);
+                        }
+                    }
+                }
+                sdl2::event::Event::JoyBallMotion {
+                    which,
+                    ball_idx,
+                    xrel,
+                    yrel,
+                    ..
+                } => {
+                    if let Some(state) = self.joystick_state(which) {
+                        trace!(
+                            id = which,
+                            ball = ball_idx,
+                            xrel,
+                            yrel,
+                            "Joystick ball motion"
+                        );
+                        add_ball_motion(state, usize::from(ball_idx), xrel, yrel);
+                    }
+                }
+                sdl2::event::Event::JoyHatMotion {
+                    which,
+                    hat_idx,
+                    state: hat_state,
+                    ..
+                } => {
+                    if let Some(state) = self.joystick_state(which) {
+                        trace!(id = which, hat = hat_idx, state = ?hat_state, "Joystick hat motion");
+                        if let Some(layout) = state.joystick {
+                            set_joystick_hat(state, layout, usize::from(hat_idx), hat_state);
+                        }
+                    }
+                }
+                sdl2::event::Event::JoyButtonDown {
+                    which, button_idx, ..
+                }
+                | sdl2::event::Event::JoyButtonUp {
+                    which, button_idx, ..
+                } => {
+                    let pressed = matches!(event, sdl2::event::Event::JoyButtonDown { .. });
+                    if let Some(state) = self.joystick_state(which) {
+                        trace!(id = which, button = button_idx, pressed, "Joystick button");
+                        if let Some(button) = state.buttons.get_mut(usize::from(button_idx)) {
+                            *button = pressed;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Events drained in the same tick are treated as one report per device
+        let received_at = Instant::now();
+        self.pending_reports
+            .extend(reported.into_iter().map(|which| InputReport {
+                id: which as usize,
+                received_at,
+                device_time_us: None,
+            }));
+
+        // Update state for all connected controllers
+        self.refresh_states();
+
+        hotplug
+    }
+
+    fn enumerate(&self) -> Vec<GamepadInfo> {
+        self.controller_states
+            .iter()
+            .map(|(id, data)| self.info_for(*id, data))
+            .collect()
+    }
+
+    fn read_state(&mut self, id: usize) -> Option<GamepadState> {
+        let data = self.controller_states.get_mut(&(id as u32))?;
+        let state = GamepadState {
+            buttons: data.buttons.clone(),
+            axes: data.axes.clone(),
+            raw_axes: data.raw_axes.clone(),
+            axis_normalization: match data.joystick {
+                Some(_) => vec![JOYSTICK_AXIS_NORMALIZATION; data.axes.len()],
+                None => SDL_AXES
+                    .iter()
+                    .map(|axis| sdl_axis_normalization(*axis))
+                    .collect(),
+            },
+        };
+
+        // Ball axes hold the motion since the previous read
+        if let Some(layout) = data.joystick {
+            let first_ball_axis = layout.axes as usize;
+            data.axes[first_ball_axis..].fill(0.0);
+            data.raw_axes[first_ball_axis..].fill(0);
+        }
+        Some(state)
+    }
+}
+
+/// Extracts the USB vendor/product IDs from an SDL joystick GUID.
+fn parse_guid_ids(guid: Guid) -> (Option<u16>, Option<u16>) {
+    // The GUID string format is typically "00000000000000000000000000000000"
+    // where bytes 8-10 contain vendor ID and bytes 10-12 contain product ID
+    let guid_str = guid.string();
+    let vid = guid_str
+        .get(8..12)
+        .and_then(|s| u16::from_str_radix(s, 16).ok());
+    let pid = guid_str
+        .get(12..16)
+        .and_then(|s| u16::from_str_radix(s, 16).ok());
+    (vid, pid)
+}
+
+/// Index of an SDL game controller button in the device's state, `None` if
+/// its layout doesn't have it.
+fn button_index(data: &ControllerData, button: Button) -> Option<usize> {
+    match map_sdl_button(button) {
+        Some(index) => Some(index),
+        None => {
+            let (_, name, _) = SDL_EXTENDED_BUTTONS
+                .iter()
+                .find(|(extended, _, _)| *extended == button)?;
+            data.layout
+                .buttons
+                .iter()
+                .position(|entry| entry.name == *name)
+        }
+    }
+    .filter(|index| *index < data.buttons.len())
+}
+
+/// Native axes, then x and y per ball, and native buttons, then the hat
+/// directions, as `JoystickLayout` describes.
+fn joystick_device_layout(joystick: JoystickLayout) -> DeviceLayout {
+    let mut layout = DeviceLayout::default();
+    for index in 0..joystick.buttons {
+        layout.push_button(format!("button_{}", index), ButtonKind::Other);
+    }
+    for index in 0..joystick.hats as usize {
+        layout.push_hat_buttons(index);
+    }
+    for index in 0..joystick.axes {
+        layout.push_axis(format!("axis_{}", index), AxisKind::Other);
+    }
+    for index in 0..joystick.balls {
+        layout.push_axis(format!("ball_{}_x", index), AxisKind::Relative);
+        layout.push_axis(format!("ball_{}_y", index), AxisKind::Relative);
+    }
+    layout
+}
+
+fn set_joystick_axis(state: &mut ControllerData, axis: usize, value: i16) {
+    state.axes[axis] = JOYSTICK_AXIS_NORMALIZATION.apply(i32::from(value));
+    state.raw_axes[axis] = i32::from(value);
+}
+
+/// Adds ball motion to the ball's x and y axes, which follow the native axes.
+fn add_ball_motion(state: &mut ControllerData, ball: usize, xrel: i16, yrel: i16) {
+    let Some(layout) = state.joystick else {
+        return;
+    };
+    let first = layout.axes as usize + ball * 2;
+    if first + 1 >= state.axes.len() {
+        return;
+    }
+    for (axis, motion) in [(first, xrel), (first + 1, yrel)] {
+        state.raw_axes[axis] = state.raw_axes[axis].saturating_add(i32::from(motion));
+        state.axes[axis] = JOYSTICK_AXIS_NORMALIZATION.apply(state.raw_axes[axis]);
+    }
+}
+
+/// Sets the up, right, down and left buttons of `hat`, which follow the
+/// native buttons.
+fn set_joystick_hat(
+    state: &mut ControllerData,
+    layout: JoystickLayout,
+    hat: usize,
+    hat_state: HatState,
+) {
+    let start = layout.buttons as usize + hat * DeviceLayout::HAT_BUTTONS;
+    let Some(buttons) = state
+        .buttons
+        .get_mut(start..start + DeviceLayout::HAT_BUTTONS)
+    else {
+        return;
+    };
+    let bits = hat_state as u8;
+    for (direction, button) in buttons.iter_mut().enumerate() {
+        *button = bits & (1 << direction) != 0;
+    }
+}
@@ -0,0 +1,475 @@
+//! Scripted virtual controllers for running without hardware.
+//!
+//! A [`SimScript`] is a timeline of hotplug, button and axis events. The
+//! backend replays it against a clock and exposes the resulting devices
+//! through the same `GamepadInfo`/`GamepadState` models as the real backends.
+//! Scripts can be loaded from JSON, e.g.
+//!
+//! ```json
+//! {
+//!   "repeat_ms": 4000,
+//!   "events": [
+//!     { "at_ms": 0, "device": 0, "action": "connect", "name": "Virtual Pad" },
+//!     { "at_ms": 500, "device": 0, "action": "button", "index": 0, "pressed": true },
+//!     { "at_ms": 600, "device": 0, "action": "axis", "index": 0, "value": -1.0 },
+//!     { "at_ms": 3000, "device": 0, "action": "disconnect" }
+//!   ]
+//! }
+//! ```
+
+use super::{BackendCapabilities, HotplugEvent, InputBackend, InputReport, ScriptClock};
+use crate::models::{AxisNormalization, DeviceLayout, GamepadInfo, GamepadState};
+use crate::utils::{AXIS_NAMES, BUTTON_NAMES};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Virtual devices have the standard layout.
+const BUTTON_COUNT: usize = BUTTON_NAMES.len();
+const AXIS_COUNT: usize = AXIS_NAMES.len();
+/// Virtual devices report raw axis values in SDL's signed 16-bit range.
+const SIM_AXIS_NORMALIZATION: AxisNormalization = AxisNormalization::new(-32768, 32767, 32767.0);
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SimAction {
+    Connect {
+        name: String,
+        #[serde(default)]
+        vendor_id: Option<u16>,
+        #[serde(default)]
+        product_id: Option<u16>,
+    },
+    Disconnect,
+    Button {
+        index: usize,
+        pressed: bool,
+    },
+    Axis {
+        index: usize,
+        value: f32,
+    },
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct SimEvent {
+    pub at_ms: u64,
+    pub device: usize,
+    #[serde(flatten)]
+    pub action: SimAction,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct SimScript {
+    /// Restart the timeline after this many milliseconds. All virtual devices
+    /// are unplugged at the loop point.
+    #[serde(default)]
+    pub repeat_ms: Option<u64>,
+    pub events: Vec<SimEvent>,
+}
+
+impl SimScript {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid simulation script: {}", e))
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_json(&json)
+    }
+
+    /// Built-in demo: one pad that cycles through every button and sweeps
+    /// both sticks and triggers, plus a second pad that is plugged in and
+    /// unplugged again halfway through each loop.
+    pub fn demo() -> Self {
+        let mut events = vec![SimEvent {
+            at_ms: 0,
+            device: 0,
+            action: SimAction::Connect {
+                name: "Simulated Controller".to_string(),
+                vendor_id: None,
+                product_id: None,
+            },
+        }];
+
+        for index in 0..BUTTON_COUNT {
+            let at_ms = 500 + index as u64 * 250;
+            events.push(SimEvent {
+                at_ms,
+                device: 0,
+                action: SimAction::Button {
+                    index,
+                    pressed: true,
+                },
+            });
+            events.push(SimEvent {
+                at_ms: at_ms + 150,
+                device: 0,
+                action: SimAction::Button {
+                    index,
+                    pressed: false,
+                },
+            });
+        }
+
+        // One full stick rotation and trigger ramp, 10ms per step
+        for step in 0..=200u64 {
+            let at_ms = 5000 + step * 10;
+            let angle = step as f32 / 200.0 * std::f32::consts::TAU;
+            let ramp = 1.0 - (step as f32 / 100.0 - 1.0).abs();
+            let axes = [
+                angle.cos(),
+                angle.sin(),
+                -angle.sin(),
+                angle.cos(),
+                ramp,
+                1.0 - ramp,
+            ];
+            for (index, value) in axes.into_iter().enumerate() {
+                events.push(SimEvent {
+                    at_ms,
+                    device: 0,
+                    action: SimAction::Axis { index, value },
+                });
+            }
+        }
+
+        events.push(SimEvent {
+            at_ms: 4000,
+            device: 1,
+            action: SimAction::Connect {
+                name: "Simulated Controller (hotplug)".to_string(),
+                vendor_id: None,
+                product_id: None,
+            },
+        });
+        events.push(SimEvent {
+            at_ms: 8000,
+            device: 1,
+            action: SimAction::Disconnect,
+        });
+
+        Self {
+            repeat_ms: Some(10_000),
+            events,
+        }
+    }
+}
+
+struct SimDevice {
+    name: String,
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+    buttons: Vec<bool>,
+    axes: Vec<f32>,
+}
+
+pub struct SimBackend {
+    events: Vec<SimEvent>,
+    repeat: Option<Duration>,
+    clock: ScriptClock,
+    /// Script time at which the current loop iteration started.
+    cycle_start: Duration,
+    /// Index of the next event to apply.
+    cursor: usize,
+    devices: BTreeMap<usize, SimDevice>,
+    pending_reports: Vec<InputReport>,
+}
+
+impl SimBackend {
+    pub fn new(script: SimScript) -> Self {
+        Self::with_clock(script, ScriptClock::realtime())
+    }
+
+    /// Creates a backend whose clock only moves through [`SimBackend::advance`].
+    pub fn manual(script: SimScript) -> Self {
+        Self::with_clock(script, ScriptClock::manual())
+    }
+
+    fn with_clock(script: SimScript, clock: ScriptClock) -> Self {
+        let mut events = script.events;
+        events.sort_by_key(|event| event.at_ms);
+
+        Self {
+            events,
+            repeat: script
+                .repeat_ms
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis),
+            clock,
+            cycle_start: Duration::ZERO,
+            cursor: 0,
+            devices: BTreeMap::new(),
+            pending_reports: Vec::new(),
+        }
+    }
+
+    /// Moves a manual clock forward. Has no effect on a realtime clock.
+    pub fn advance(&mut self, by: Duration) {
+        self.clock.advance(by);
+    }
+
+    fn info_for(&self, id: usize, device: &SimDevice) -> GamepadInfo {
+        GamepadInfo {
+            id,
+            name: device.name.clone(),
+            controller_type: self.kind().to_string(),
+            vendor_id: device.vendor_id,
+            product_id: device.product_id,
+            joystick: None,
+            layout: DeviceLayout::standard(),
+        }
+    }
+
+    fn disconnect(&mut self, id: usize) -> Option<HotplugEvent> {
+        self.devices
+            .remove(&id)
+            .map(|_| HotplugEvent::Disconnected {
+                id,
+                controller_type: self.kind().to_string(),
+            })
+    }
+
+    fn apply(&mut self, event: SimEvent) -> Option<HotplugEvent> {
+        match event.action {
+            SimAction::Connect {
+                name,
+                vendor_id,
+                product_id,
+            } => {
+                let device = SimDevice {
+                    name,
+                    vendor_id,
+                    product_id,
+                    buttons: vec![false; BUTTON_COUNT],
+                    axes: vec![0.0; AXIS_COUNT],
+                };
+                let info = self.info_for(event.device, &device);
+                let previous = self.devices.insert(event.device, device);
+                previous.is_none().then_some(HotplugEvent::Connected(info))
+            }
+            SimAction::Disconnect => self.disconnect(event.device),
+            SimAction::Button { index, pressed } => {
+                if let Some(button) = self
+                    .devices
+                    .get_mut(&event.device)
+                    .and_then(|device| device.buttons.get_mut(index))
+                {
+                    *button = pressed;
+                }
+                None
+            }
+            SimAction::Axis { index, value } => {
+                if let Some(axis) = self
+                    .devices
+                    .get_mut(&event.device)
+                    .and_then(|device| device.axes.get_mut(index))
+                {
+                    *axis = value.clamp(-1.0, 1.0);
+                }
+                None
+            }
+        }
+    }
+}
+
+impl InputBackend for SimBackend {
+    fn kind(&self) -> &'static str {
+        "sim"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            hotplug_events: true,
+            vendor_product_ids: true,
+            max_devices: None,
+            report_timestamps: true,
+        }
+    }
+
+    fn poll(&mut self) -> Vec<HotplugEvent> {
+        let now = self.clock.elapsed();
+        let mut hotplug = Vec::new();
+        let mut reported: Vec<usize> = Vec::new();
+
+        loop {
+            let elapsed = now.saturating_sub(self.cycle_start);
+
+            while let Some(event) = self.events.get(self.cursor) {
+                if Duration::from_millis(event.at_ms) > elapsed {
+                    break;
+                }
+                let event = event.clone();
+                self.cursor += 1;
+                let is_input = matches!(
+                    event.action,
+                    SimAction::Button { .. } | SimAction::Axis { .. }
+                );
+                if is_input && !reported.contains(&event.device) {
+                    reported.push(event.device);
+                }
+                hotplug.extend(self.apply(event));
+            }
+
+            match self.repeat {
+                Some(repeat) if elapsed >= repeat => {
+                    let ids: Vec<usize> = self.devices.keys().copied().collect();
+                    for id in ids {
+                        hotplug.extend(self.disconnect(id));
+                    }
+                    self.cycle_start += repeat;
+                    self.cursor = 0;
+                }
+                _ => break,
+            }
+        }
+
+        let received_at = Instant::now();
+        self.pending_reports
+            .extend(reported.into_iter().map(|id| InputReport {
+                id,
+                received_at,
+                device_time_us: None,
+            }));
+
+        hotplug
+    }
+
+    fn enumerate(&self) -> Vec<GamepadInfo> {
+        self.devices
+            .iter()
+            .map(|(id, device)| self.info_for(*id, device))
+            .collect()
+    }
+
+    fn read_state(&mut self, id: usize) -> Option<GamepadState> {
+        self.devices.get(&id).map(|device| GamepadState {
+            buttons: device.buttons.clone(),
+            axes: device.axes.clone(),
+            raw_axes: device
+                .axes
+                .iter()
+                .map(|value| (value * SIM_AXIS_NORMALIZATION.divisor).round() as i32)
+                .collect(),
+            axis_normalization: vec![SIM_AXIS_NORMALIZATION; device.axes.len()],
+        })
+    }
+
+    fn drain_reports(&mut self) -> Vec<InputReport> {
+        std::mem::take(&mut self.pending_reports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::drained_report_ids;
+
+    const SCRIPT: &str = r#"{
+      "events": [
+        { "at_ms": 0, "device": 0, "action": "connect", "name": "Pad", "vendor_id": 1118 },
+        { "at_ms": 10, "device": 0, "action": "button", "index": 3, "pressed": true },
+        { "at_ms": 10, "device": 0, "action": "axis", "index": 1, "value": 0.5 },
+        { "at_ms": 20, "device": 0, "action": "axis", "index": 0, "value": -4.0 },
+        { "at_ms": 20, "device": 0, "action": "button", "index": 99, "pressed": true },
+        { "at_ms": 30, "device": 1, "action": "button", "index": 0, "pressed": true },
+        { "at_ms": 50, "device": 0, "action": "disconnect" }
+      ]
+    }"#;
+
+    #[test]
+    fn scripted_input() {
+        let mut backend = SimBackend::manual(SimScript::from_json(SCRIPT).unwrap());
+        match backend.poll().as_slice() {
+            [HotplugEvent::Connected(info)] => {
+                assert_eq!((info.id, info.name.as_str()), (0, "Pad"));
+                assert_eq!((info.vendor_id, info.product_id), (Some(1118), None));
+                assert_eq!(info.layout.buttons.len(), BUTTON_COUNT);
+                assert_eq!(info.layout.axes.len(), AXIS_COUNT);
+            }
+            events => panic!("expected one connection, got {:?}", events),
+        }
+        assert!(drained_report_ids(&mut backend).is_empty());
+        assert_eq!(
+            backend.read_state(0).unwrap().buttons,
+            [false; BUTTON_COUNT]
+        );
+
+        // Nothing happens until the clock moves
+        assert!(backend.poll().is_empty());
+        backend.advance(Duration::from_millis(9));
+        backend.poll();
+        assert!(drained_report_ids(&mut backend).is_empty());
+
+        // Events due at the same time make a single report
+        backend.advance(Duration::from_millis(1));
+        assert!(backend.poll().is_empty());
+        assert_eq!(drained_report_ids(&mut backend), [0]);
+        let state = backend.read_state(0).unwrap();
+        assert!(state.buttons[3]);
+        assert_eq!(state.axes[1], 0.5);
+        assert_eq!(state.raw_axes[1], 16384);
+        assert_eq!(state.axis_normalization[1], SIM_AXIS_NORMALIZATION);
+
+        // Axes are clamped and out of range buttons ignored
+        backend.advance(Duration::from_millis(10));
+        backend.poll();
+        let state = backend.read_state(0).unwrap();
+        assert_eq!(state.axes[0], -1.0);
+        assert_eq!(state.raw_axes[0], -32767);
+        assert_eq!(state.buttons.len(), BUTTON_COUNT);
+
+        // Input for a device that isn't connected is dropped
+        backend.advance(Duration::from_millis(10));
+        backend.poll();
+        assert_eq!(backend.enumerate().len(), 1);
+        assert!(backend.read_state(1).is_none());
+
+        backend.advance(Duration::from_millis(20));
+        match backend.poll().as_slice() {
+            [HotplugEvent::Disconnected {
+                id: 0,
+                controller_type,
+            }] => assert_eq!(controller_type, "sim"),
+            events => panic!("expected one disconnection, got {:?}", events),
+        }
+        assert!(backend.read_state(0).is_none());
+        assert!(backend.enumerate().is_empty());
+    }
+
+    #[test]
+    fn repeats() {
+        let script = SimScript::from_json(
+            r#"{
+              "repeat_ms": 100,
+              "events": [
+                { "at_ms": 0, "device": 0, "action": "connect", "name": "Pad" },
+                { "at_ms": 10, "device": 0, "action": "button", "index": 0, "pressed": true }
+              ]
+            }"#,
+        )
+        .unwrap();
+        let mut backend = SimBackend::manual(script);
+        backend.poll();
+        backend.advance(Duration::from_millis(10));
+        backend.poll();
+        assert!(backend.read_state(0).unwrap().buttons[0]);
+
+        // The loop point unplugs every device and starts the timeline over
+        backend.advance(Duration::from_millis(90));
+        let events = backend.poll();
+        assert!(matches!(
+            events.as_slice(),
+            [HotplugEvent::Disconnected { id: 0, .. }, HotplugEvent::Connected(info)] if info.id == 0
+        ));
+        assert!(!backend.read_state(0).unwrap().buttons[0]);
+
+        // A clock jump over several loops plays each of them
+        backend.advance(Duration::from_millis(210));
+        assert_eq!(backend.poll().len(), 4);
+        assert!(backend.read_state(0).unwrap().buttons[0]);
+    }
+}
@@ -0,0 +1,271 @@
+use super::{BackendCapabilities, HotplugEvent, InputBackend, InputReport};
+use crate::models::{DeviceLayout, GamepadInfo, GamepadState, PacketStats, XInputState};
+use crate::utils::{
+    normalize_xinput_axis, normalize_xinput_trigger, XINPUT_STICK_NORMALIZATION,
+    XINPUT_TRIGGER_NORMALIZATION,
+};
+use rusty_xinput::XInputHandle;
+use std::time::Instant;
+use tracing::{debug, trace, warn};
+use winapi::um::xinput::*;
+
+/// XInput only exposes four fixed user slots.
+const XINPUT_SLOTS: usize = 4;
+
+pub struct XInputBackend {
+    handle: XInputHandle,
+    previous_states: [XInputState; XINPUT_SLOTS],
+    /// `dwPacketNumber` tracking for each slot, reset on disconnect.
+    packet_stats: [PacketStats; XINPUT_SLOTS],
+    pending_reports: Vec<InputReport>,
+}
+
+impl XInputBackend {
+    pub fn new() -> Option<Self> {
+        match XInputHandle::load_default() {
+            Ok(handle) => Some(Self {
+                handle,
+                previous_states: [XInputState::default(); XINPUT_SLOTS],
+                packet_stats: [PacketStats::default(); XINPUT_SLOTS],
+                pending_reports: Vec::new(),
+            }),
+            Err(e) => {
+                warn!(error = ?e, "Failed to load XInput");
+                None
+            }
+        }
+    }
+
+    fn info_for(&self, slot: usize) -> GamepadInfo {
+        GamepadInfo {
+            id: slot,
+            name: format!("Xbox Controller {}", slot + 1),
+            controller_type: self.kind().to_string(),
+            vendor_id: None,
+            product_id: None,
+            joystick: None,
+            layout: DeviceLayout::standard(),
+        }
+    }
+}
+
+impl InputBackend for XInputBackend {
+    fn kind(&self) -> &'static str {
+        "xinput"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            hotplug_events: false,
+            vendor_product_ids: false,
+            max_devices: Some(XINPUT_SLOTS),
+            report_timestamps: true,
+        }
+    }
+
+    fn poll(&mut self) -> Vec<HotplugEvent> {
+        let mut hotplug = Vec::new();
+
+        for slot in 0..XINPUT_SLOTS {
+            let packet = self
+                .handle
+                .get_state(slot as u32)
+                .ok()
+                .map(|state| state.raw.dwPacketNumber);
+
+            // The packet number only moves when the controller sent new input
+            if let Some(packet) = packet {
+                if self.packet_stats[slot].record(packet) {
+                    self.pending_reports.push(InputReport {
+                        id: slot,
+                        received_at: Instant::now(),
+                        device_time_us: None,
+                    });
+                }
+            }
+
+            let connected = packet.is_some();
+            if connected == self.previous_states[slot].connected {
+                continue;
+            }
+
+            self.previous_states[slot].connected = connected;
+            if connected {
+                debug!(id = slot, "Slot connected");
+                hotplug.push(HotplugEvent::Connected(self.info_for(slot)));
+            } else {
+                debug!(id = slot, "Slot disconnected");
+                self.packet_stats[slot] = PacketStats::default();
+                hotplug.push(HotplugEvent::Disconnected {
+                    id: slot,
+                    controller_type: self.kind().to_string(),
+                });
+            }
+        }
+
+        hotplug
+    }
+
+    fn enumerate(&self) -> Vec<GamepadInfo> {
+        (0..XINPUT_SLOTS)
+            .filter(|slot| self.previous_states[*slot].connected)
+            .map(|slot| self.info_for(slot))
+            .collect()
+    }
+
+    fn read_state(&mut self, id: usize) -> Option<GamepadState> {
+        if id >= XINPUT_SLOTS {
+            return None;
+        }
+
+        let state = self.handle.get_state(id as u32).ok()?;
+        let raw = state.raw;
+        let mut prev_state = self.previous_states[id];
+
+        // Calculate all analog values first
+        let left_x = normalize_xinput_axis(raw.Gamepad.sThumbLX);
+        let left_y = normalize_xinput_axis(raw.Gamepad.sThumbLY);
+        let right_x = normalize_xinput_axis(raw.Gamepad.sThumbRX);
+        let right_y = normalize_xinput_axis(raw.Gamepad.sThumbRY);
+        let left_trigger = normalize_xinput_trigger(raw.Gamepad.bLeftTrigger);
+        let right_trigger = normalize_xinput_trigger(raw.Gamepad.bRightTrigger);
+
+        // Check for button state changes
+        let buttons = [
+            (XINPUT_GAMEPAD_A, "A"),
+            (XINPUT_GAMEPAD_B, "B"),
+            (XINPUT_GAMEPAD_X, "X"),
+            (XINPUT_GAMEPAD_Y, "Y"),
+            (XINPUT_GAMEPAD_LEFT_SHOULDER, "LB"),
+            (XINPUT_GAMEPAD_RIGHT_SHOULDER, "RB"),
+            (XINPUT_GAMEPAD_BACK, "Back"),
+            (XINPUT_GAMEPAD_START, "Start"),
+            (XINPUT_GAMEPAD_LEFT_THUMB, "L3"),
+            (XINPUT_GAMEPAD_RIGHT_THUMB, "R3"),
+            (XINPUT_GAMEPAD_DPAD_UP, "DPad Up"),
+            (XINPUT_GAMEPAD_DPAD_DOWN, "DPad Down"),
+            (XINPUT_GAMEPAD_DPAD_LEFT, "DPad Left"),
+            (XINPUT_GAMEPAD_DPAD_RIGHT, "DPad Right"),
+        ];
+
+        // Only log button changes
+        if raw.Gamepad.wButtons != prev_state.buttons {
+            for (button_mask, button_name) in buttons.iter() {
+                let was_pressed = prev_state.buttons & button_mask != 0;
+                let is_pressed = raw.Gamepad.wButtons & button_mask != 0;
+                if was_pressed != is_pressed {
+                    if is_pressed {
+                        trace!(id, button = button_name, "Button pressed");
+                    } else {
+                        trace!(id, button = button_name, "Button released");
+                    }
+                }
+            }
+        }
+
+        // Only log stick changes if they differ significantly from previous state
+        let stick_threshold: i32 = 8192; // Convert to i32 for safe math
+        let left_x_diff = (raw.Gamepad.sThumbLX as i32) - (prev_state.left_x as i32);
+        let left_y_diff = (raw.Gamepad.sThumbLY as i32) - (prev_state.left_y as i32);
+        let right_x_diff = (raw.Gamepad.sThumbRX as i32) - (prev_state.right_x as i32);
+        let right_y_diff = (raw.Gamepad.sThumbRY as i32) - (prev_state.right_y as i32);
+
+        if left_x_diff.abs() > stick_threshold || left_y_diff.abs() > stick_threshold {
+            trace!(id, x = left_x, y = left_y, "Left stick moved");
+        }
+        if right_x_diff.abs() > stick_threshold || right_y_diff.abs() > stick_threshold {
+            trace!(id, x = right_x, y = right_y, "Right stick moved");
+        }
+
+        // Only log trigger changes if they differ significantly from previous state
+        let trigger_threshold = 20;
+        let left_trigger_diff =
+            (raw.Gamepad.bLeftTrigger as i16) - (prev_state.left_trigger as i16);
+        let right_trigger_diff =
+            (raw.Gamepad.bRightTrigger as i16) - (prev_state.right_trigger as i16);
+
+        if left_trigger_diff.abs() > trigger_threshold {
+            trace!(id, value = left_trigger, "Left trigger moved");
+        }
+        if right_trigger_diff.abs() > trigger_threshold {
+            trace!(id, value = right_trigger, "Right trigger moved");
+        }
+
+        // Update previous state
+        prev_state.buttons = raw.Gamepad.wButtons;
+        prev_state.left_x = raw.Gamepad.sThumbLX;
+        prev_state.left_y = raw.Gamepad.sThumbLY;
+        prev_state.right_x = raw.Gamepad.sThumbRX;
+        prev_state.right_y = raw.Gamepad.sThumbRY;
+        prev_state.left_trigger = raw.Gamepad.bLeftTrigger;
+        prev_state.right_trigger = raw.Gamepad.bRightTrigger;
+        self.previous_states[id] = prev_state;
+
+        let pressed = |mask: u16| raw.Gamepad.wButtons & mask != 0;
+
+        Some(GamepadState {
+            buttons: vec![
+                pressed(XINPUT_GAMEPAD_A),
+                pressed(XINPUT_GAMEPAD_B),
+                pressed(XINPUT_GAMEPAD_X),
+                pressed(XINPUT_GAMEPAD_Y),
+                pressed(XINPUT_GAMEPAD_LEFT_SHOULDER),
+                pressed(XINPUT_GAMEPAD_RIGHT_SHOULDER),
+                pressed(XINPUT_GAMEPAD_BACK),
+                pressed(XINPUT_GAMEPAD_START),
+                false,
+                pressed(XINPUT_GAMEPAD_LEFT_THUMB),
+                pressed(XINPUT_GAMEPAD_RIGHT_THUMB),
+                pressed(XINPUT_GAMEPAD_DPAD_UP),
+                pressed(XINPUT_GAMEPAD_DPAD_DOWN),
+                pressed(XINPUT_GAMEPAD_DPAD_LEFT),
+                pressed(XINPUT_GAMEPAD_DPAD_RIGHT),
+            ],
+            axes: vec![
+                left_x,
+                left_y,
+                right_x,
+                right_y,
+                left_trigger,
+                right_trigger,
+            ],
+            raw_axes: vec![
+                i32::from(raw.Gamepad.sThumbLX),
+                i32::from(raw.Gamepad.sThumbLY),
+                i32::from(raw.Gamepad.sThumbRX),
+                i32::from(raw.Gamepad.sThumbRY),
+                i32::from(raw.Gamepad.bLeftTrigger),
+                i32::from(raw.Gamepad.bRightTrigger),
+            ],
+            axis_normalization: vec![
+                XINPUT_STICK_NORMALIZATION,
+                XINPUT_STICK_NORMALIZATION,
+                XINPUT_STICK_NORMALIZATION,
+                XINPUT_STICK_NORMALIZATION,
+                XINPUT_TRIGGER_NORMALIZATION,
+                XINPUT_TRIGGER_NORMALIZATION,
+            ],
+        })
+    }
+
+    fn drain_reports(&mut self) -> Vec<InputReport> {
+        std::mem::take(&mut self.pending_reports)
+    }
+
+    fn packet_stats(&self, id: usize) -> Option<PacketStats> {
+        self.packet_stats
+            .get(id)
+            .filter(|_| self.previous_states[id].connected)
+            .copied()
+    }
+
+    fn reset_packet_stats(&mut self, id: usize) {
+        if let Some(stats) = self.packet_stats.get_mut(id) {
+            // Keep the last packet so the next read isn't counted as a gap
+            *stats = PacketStats {
+                last_packet: stats.last_packet,
+                ..PacketStats::default()
+            };
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::io;
+use std::path::Path;
+use thiserror::Error;
+
+/// Why a request about a controller couldn't be answered.
+///
+/// Serializes as `{ "kind", "message" }` plus `controller_type` and `id` for
+/// the variants that name a device, or `path` and `io_kind` for file errors,
+/// so the frontend can both branch on the cause and show it.
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum ControllerError {
+    /// A known controller type whose backend isn't running, e.g. XInput off
+    /// Windows or SDL failing to initialize.
+    #[error("The {controller_type} backend is not available")]
+    BackendUnavailable { controller_type: String },
+    #[error("Unknown controller type '{controller_type}'")]
+    InvalidControllerType { controller_type: String },
+    /// The backend has never reported this device.
+    #[error("No {controller_type} controller with id {id}")]
+    DeviceNotFound { controller_type: String, id: usize },
+    /// The device was connected earlier but has since been unplugged.
+    #[error("The {controller_type} controller with id {id} was disconnected")]
+    Disconnected { controller_type: String, id: usize },
+    /// A thread panicked while holding the controller manager.
+    #[error("Controller state is unavailable after an internal error")]
+    LockPoisoned,
+    /// Reading or writing a recording or export failed. `path` is empty
+    /// when there was no file to name yet.
+    #[error("{message}")]
+    File {
+        path: String,
+        kind: io::ErrorKind,
+        message: String,
+    },
+}
+
+impl ControllerError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::BackendUnavailable { .. } => "backend_unavailable",
+            Self::InvalidControllerType { .. } => "invalid_controller_type",
+            Self::DeviceNotFound { .. } => "device_not_found",
+            Self::Disconnected { .. } => "disconnected",
+            Self::LockPoisoned => "lock_poisoned",
+            Self::File { .. } => "file",
+        }
+    }
+
+    /// A [`File`](Self::File) error for `path`, reading
+    /// "`action` `path`: `error`".
+    pub fn file(action: &str, path: impl AsRef<Path>, error: &io::Error) -> Self {
+        let path = path.as_ref().display().to_string();
+        Self::File {
+            message: format!("{} {}: {}", action, path, error),
+            path,
+            kind: error.kind(),
+        }
+    }
+}
+
+impl Serialize for ControllerError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("kind", self.kind())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match self {
+            Self::BackendUnavailable { controller_type }
+            | Self::InvalidControllerType { controller_type } => {
+                map.serialize_entry("controller_type", controller_type)?;
+            }
+            Self::DeviceNotFound {
+                controller_type,
+                id,
+            }
+            | Self::Disconnected {
+                controller_type,
+                id,
+            } => {
+                map.serialize_entry("controller_type", controller_type)?;
+                map.serialize_entry("id", id)?;
+            }
+            Self::File { path, kind, .. } => {
+                map.serialize_entry("path", path)?;
+                map.serialize_entry("io_kind", &format!("{:?}", kind))?;
+            }
+            Self::LockPoisoned => {}
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,283 @@
+//! Exports buffered samples as flat rows for spreadsheets and scripts.
+//!
+//! Each row has `timestamp_us`, `controller_type` and `device_id`, then one
+//! column per button (`button_a`, ...), normalized axis (`axis_left_x`, ...)
+//! and raw axis (`raw_left_x`, ...), named after the device's layout, or the
+//! standard layout if it isn't known.
+
+use crate::error::ControllerError;
+use crate::models::{DeviceKey, DeviceLayout, InputSample};
+use crate::utils::{AXIS_NAMES, BUTTON_NAMES};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ExportSummary {
+    pub path: String,
+    pub format: ExportFormat,
+    pub rows: usize,
+}
+
+/// Button and axis counts of the largest state in `samples`.
+fn dimensions(samples: &[InputSample]) -> (usize, usize) {
+    samples.iter().fold((0, 0), |(buttons, axes), sample| {
+        let state = &sample.state;
+        (
+            buttons.max(state.buttons.len()),
+            axes.max(state.axes.len()).max(state.raw_axes.len()),
+        )
+    })
+}
+
+fn columns(button_count: usize, axis_count: usize, layout: Option<&DeviceLayout>) -> Vec<String> {
+    let button_name = |index: usize| match layout {
+        Some(layout) => layout.buttons.get(index).map(|button| button.name.clone()),
+        None => BUTTON_NAMES.get(index).map(|name| name.to_string()),
+    };
+    let axis_name = |index: usize| match layout {
+        Some(layout) => layout.axes.get(index).map(|axis| axis.name.clone()),
+        None => AXIS_NAMES.get(index).map(|name| name.to_string()),
+    };
+    // Generic layouts already name controls `button_0`, `axis_0`, ...
+    let column = |prefix: &str, name: Option<String>, index: usize| match name {
+        Some(name) if name.starts_with(prefix) => name,
+        Some(name) => format!("{}{}", prefix, name),
+        None => format!("{}{}", prefix, index),
+    };
+
+    let mut columns: Vec<String> = ["timestamp_us", "controller_type", "device_id"]
+        .iter()
+        .map(|column| column.to_string())
+        .collect();
+    columns.extend((0..button_count).map(|i| column("button_", button_name(i), i)));
+    columns.extend((0..axis_count).map(|i| column("axis_", axis_name(i), i)));
+    columns.extend((0..axis_count).map(|i| column("raw_", axis_name(i), i)));
+    columns
+}
+
+/// The row values for one sample, in `columns` order. Entries the sample
+/// doesn't have are `Null`.
+fn row(
+    key: &DeviceKey,
+    sample: &InputSample,
+    button_count: usize,
+    axis_count: usize,
+) -> Vec<Value> {
+    let state = &sample.state;
+    let mut values = vec![
+        Value::from(sample.timestamp_us),
+        Value::from(key.controller_type.as_str()),
+        Value::from(key.id),
+    ];
+    values.extend((0..button_count).map(|i| {
+        state
+            .buttons
+            .get(i)
+            .map_or(Value::Null, |b| Value::from(*b))
+    }));
+    values.extend(
+        (0..axis_count).map(|i| state.axes.get(i).map_or(Value::Null, |v| Value::from(*v))),
+    );
+    values.extend((0..axis_count).map(|i| {
+        state
+            .raw_axes
+            .get(i)
+            .map_or(Value::Null, |v| Value::from(*v))
+    }));
+    values
+}
+
+/// One JSONL row: `columns` paired with their values, in column order and
+/// keeping repeated names.
+struct Row<'a> {
+    columns: &'a [String],
+    values: &'a [Value],
+}
+
+impl Serialize for Row<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (column, value) in self.columns.iter().zip(self.values) {
+            map.serialize_entry(column, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Writes `samples` of device `key` to `path` and returns the row count.
+/// Columns are named after `layout` when given.
+pub fn export_samples(
+    path: impl AsRef<Path>,
+    format: ExportFormat,
+    key: &DeviceKey,
+    layout: Option<&DeviceLayout>,
+    samples: &[InputSample],
+) -> Result<ExportSummary, ControllerError> {
+    let path = path.as_ref();
+    let file =
+        File::create(path).map_err(|e| ControllerError::file("Failed to create", path, &e))?;
+    let mut writer = BufWriter::new(file);
+
+    let (button_count, axis_count) = dimensions(samples);
+    let columns = columns(button_count, axis_count, layout);
+
+    let result = (|| -> std::io::Result<()> {
+        if format == ExportFormat::Csv {
+            writeln!(writer, "{}", columns.join(","))?;
+        }
+        for sample in samples {
+            let values = row(key, sample, button_count, axis_count);
+            match format {
+                ExportFormat::Csv => {
+                    let cells: Vec<String> = values.iter().map(csv_cell).collect();
+                    writeln!(writer, "{}", cells.join(","))?;
+                }
+                ExportFormat::Jsonl => {
+                    let row = Row {
+                        columns: &columns,
+                        values: &values,
+                    };
+                    serde_json::to_writer(&mut writer, &row)?;
+                    writeln!(writer)?;
+                }
+            }
+        }
+        writer.flush()
+    })();
+    result.map_err(|e| ControllerError::file("Failed to write", path, &e))?;
+
+    Ok(ExportSummary {
+        path: path.display().to_string(),
+        format,
+        rows: samples.len(),
+    })
+}
+
+/// Booleans as 0/1 so spreadsheets can sum them, missing values as empty
+/// cells.
+fn csv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(b) => (*b as u8).to_string(),
+        Value::String(s) if s.contains([',', '"', '\n']) => {
+            format!("\"{}\"", s.replace('"', "\"\""))
+        }
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GamepadState;
+    use serde_json::Map;
+
+    fn sample(timestamp_us: u64, buttons: Vec<bool>, raw_axes: Vec<i32>) -> InputSample {
+        InputSample {
+            timestamp_us,
+            state: GamepadState {
+                buttons,
+                axes: raw_axes.iter().map(|raw| *raw as f32 / 4.0).collect(),
+                raw_axes,
+                axis_normalization: Vec::new(),
+            },
+        }
+    }
+
+    /// Exports `samples` and returns the file's lines with the columns the
+    /// export used.
+    fn export(
+        format: ExportFormat,
+        layout: &DeviceLayout,
+        samples: &[InputSample],
+    ) -> (Vec<String>, Vec<String>) {
+        let path = std::env::temp_dir().join(format!(
+            "sloptester-export-{}.{}",
+            std::process::id(),
+            format.extension()
+        ));
+        let key = DeviceKey::new("sdl", 3);
+        let summary = export_samples(&path, format, &key, Some(layout), samples).unwrap();
+        assert_eq!(summary.rows, samples.len());
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let (button_count, axis_count) = dimensions(samples);
+        let columns = columns(button_count, axis_count, Some(layout));
+        (contents.lines().map(str::to_string).collect(), columns)
+    }
+
+    fn samples() -> Vec<InputSample> {
+        vec![
+            sample(1_000, vec![true, false], vec![4, -2]),
+            // A shorter state leaves the missing cells empty
+            sample(2_000, vec![false], vec![8]),
+        ]
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let layout = DeviceLayout::generic(2, 2);
+        let (lines, columns) = export(ExportFormat::Csv, &layout, &samples());
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], columns.join(","));
+        assert_eq!(
+            lines[0],
+            "timestamp_us,controller_type,device_id,button_0,button_1,axis_0,axis_1,raw_axis_0,raw_axis_1"
+        );
+        assert_eq!(lines[1], "1000,sdl,3,1,0,1.0,-0.5,4,-2");
+        assert_eq!(lines[2], "2000,sdl,3,0,,2.0,,8,");
+    }
+
+    #[test]
+    fn jsonl_round_trip() {
+        let mut layout = DeviceLayout::generic(2, 2);
+        // Repeated names still get a key each
+        layout.buttons[1].name = layout.buttons[0].name.clone();
+        let samples = samples();
+        let (lines, columns) = export(ExportFormat::Jsonl, &layout, &samples);
+        assert_eq!(lines.len(), samples.len());
+
+        for (line, sample) in lines.iter().zip(&samples) {
+            // Keys appear in column order
+            let mut position = 0;
+            for column in &columns {
+                let key = format!("\"{}\":", column);
+                position += line[position..].find(&key).unwrap() + key.len();
+            }
+            assert_eq!(line.matches("\"button_0\":").count(), 2);
+
+            let row: Map<String, Value> = serde_json::from_str(line).unwrap();
+            assert_eq!(row["timestamp_us"], sample.timestamp_us);
+            assert_eq!(row["controller_type"], "sdl");
+            assert_eq!(row["device_id"], 3);
+            for (i, raw) in sample.state.raw_axes.iter().enumerate() {
+                assert_eq!(row[&format!("raw_axis_{}", i)], *raw);
+                assert_eq!(row[&format!("axis_{}", i)], sample.state.axes[i]);
+            }
+        }
+        let row: Map<String, Value> = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(row["axis_1"], Value::Null);
+    }
+}
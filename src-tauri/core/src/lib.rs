@@ -0,0 +1,13 @@
+//! Controller testing logic without any UI: input backends, the sampling
+//! `ControllerManager`, normalization, measurements, recordings and exports.
+//!
+//! The Tauri app and `sloptester-cli` are thin layers over this crate.
+
+pub mod analysis;
+pub mod backends;
+pub mod error;
+pub mod export;
+pub mod managers;
+pub mod models;
+pub mod recording;
+pub mod utils;
@@ -0,0 +1,43 @@
+use crate::models::InputSample;
+use std::collections::VecDeque;
+
+/// Fixed-capacity ring buffer of timestamped samples for one device.
+pub struct SampleHistory {
+    samples: VecDeque<InputSample>,
+    capacity: usize,
+}
+
+impl SampleHistory {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, sample: InputSample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn latest(&self) -> Option<&InputSample> {
+        self.samples.back()
+    }
+
+    /// Samples with `timestamp_us >= since_us`, oldest first.
+    pub fn since(&self, since_us: u64) -> impl Iterator<Item = &InputSample> {
+        let start = self.samples.partition_point(|s| s.timestamp_us < since_us);
+        self.samples.range(start..)
+    }
+
+    /// Changes the capacity, dropping the oldest samples if it shrinks.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+    }
+}
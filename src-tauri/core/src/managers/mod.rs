@@ -0,0 +1,656 @@
+use super::analysis::{ReportRateStats, SocdTest, SocdTestStatus, SOCD_SAMPLE_WINDOW_US};
+#[cfg(target_os = "linux")]
+use super::backends::HidBackend;
+#[cfg(windows)]
+use super::backends::XInputBackend;
+use super::backends::{
+    EvdevBackend, EvemuRecording, HotplugEvent, InputBackend, PlaybackControl, PlaybackStatus,
+    ReplayBackend, ReportDescriptor, SdlBackend, SimBackend, SimScript, CONTROLLER_TYPES,
+};
+use super::error::ControllerError;
+use super::models::*;
+use super::recording::{
+    RecordedEvent, Recording, RecordingFormat, RecordingHeader, RecordingSummary, SessionRecorder,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Instant;
+use tracing::{error, info, warn};
+
+mod history;
+mod poller;
+
+pub use history::SampleHistory;
+pub use poller::{spawn_poller, PollerConfig, POLL_RATE_ENV};
+
+/// Environment variable choosing the input backends: `hardware` (default),
+/// `evdev`, `hid`, `sim`, `replay` or `evemu`.
+pub const BACKEND_ENV: &str = "SLOPTESTER_BACKEND";
+/// Path to a JSON script for the simulated backend. The built-in demo is used if unset.
+pub const SIM_SCRIPT_ENV: &str = "SLOPTESTER_SIM_SCRIPT";
+/// Path to the session recording played by the `replay` backend.
+pub const REPLAY_ENV: &str = "SLOPTESTER_REPLAY";
+/// Path to the `evemu-record` capture played by the `evemu` backend.
+pub const EVEMU_ENV: &str = "SLOPTESTER_EVEMU";
+
+/// Which set of backends a `ControllerManager` drives.
+pub enum BackendSelection {
+    /// XInput (on Windows) and SDL.
+    Hardware,
+    /// Linux `/dev/input` event devices only. Not combined with SDL, which
+    /// would list the same pads again.
+    #[cfg(target_os = "linux")]
+    Evdev,
+    /// Linux `/dev/hidraw*` devices only, decoded from their report
+    /// descriptors. Not combined with SDL for the same reason.
+    #[cfg(target_os = "linux")]
+    Hid,
+    /// Scripted virtual controllers only, for headless runs.
+    Simulated(SimScript),
+    /// A recorded session played back as virtual controllers.
+    Replay(Recording),
+    /// An `evemu-record` capture played back through the evdev backend.
+    Evemu(EvemuRecording),
+}
+
+impl BackendSelection {
+    /// Reads the selection from `SLOPTESTER_BACKEND`, `SLOPTESTER_SIM_SCRIPT`,
+    /// `SLOPTESTER_REPLAY` and `SLOPTESTER_EVEMU`.
+    pub fn from_env() -> Self {
+        match std::env::var(BACKEND_ENV).as_deref() {
+            Ok("sim") => {
+                let script = match std::env::var(SIM_SCRIPT_ENV) {
+                    Ok(path) => SimScript::from_file(&path).unwrap_or_else(|e| {
+                        warn!("{}, falling back to the demo script", e);
+                        SimScript::demo()
+                    }),
+                    Err(_) => SimScript::demo(),
+                };
+                Self::Simulated(script)
+            }
+            Ok("replay") => {
+                let recording = std::env::var(REPLAY_ENV)
+                    .map_err(|_| format!("{} is not set", REPLAY_ENV))
+                    .and_then(|path| Recording::from_file(path).map_err(|e| e.to_string()));
+                match recording {
+                    Ok(recording) => Self::Replay(recording),
+                    Err(e) => {
+                        warn!("{}, using hardware backends", e);
+                        Self::Hardware
+                    }
+                }
+            }
+            Ok("evemu") => {
+                let recording = std::env::var(EVEMU_ENV)
+                    .map_err(|_| format!("{} is not set", EVEMU_ENV))
+                    .and_then(EvemuRecording::from_file);
+                match recording {
+                    Ok(recording) => Self::Evemu(recording),
+                    Err(e) => {
+                        warn!("{}, using hardware backends", e);
+                        Self::Hardware
+                    }
+                }
+            }
+            #[cfg(target_os = "linux")]
+            Ok("evdev") => Self::Evdev,
+            #[cfg(target_os = "linux")]
+            Ok("hid") => Self::Hid,
+            Ok("hardware") | Err(_) => Self::Hardware,
+            Ok(other) => {
+                warn!(
+                    "Unknown {} value '{}', using hardware backends",
+                    BACKEND_ENV, other
+                );
+                Self::Hardware
+            }
+        }
+    }
+}
+
+/// Something the frontend should hear about after a poll.
+#[derive(Debug, Clone)]
+pub enum ManagerEvent {
+    Hotplug(HotplugEvent),
+    State(GamepadStateDelta),
+}
+
+/// Caps how many report timestamps one measurement keeps.
+const MAX_REPORT_TIMESTAMPS: usize = 100_000;
+
+/// An in-progress report rate measurement for one device.
+struct ReportRateMeasurement {
+    key: DeviceKey,
+    timestamps: Vec<u64>,
+}
+
+pub struct ControllerManager {
+    backends: Vec<Box<dyn InputBackend>>,
+    /// Zero point for sample timestamps.
+    epoch: Instant,
+    config: PollerConfig,
+    history: HashMap<DeviceKey, SampleHistory>,
+    /// Devices that were unplugged and haven't come back, to tell them apart
+    /// from ids that never existed.
+    disconnected: HashSet<DeviceKey>,
+    last_sample_at: Option<Instant>,
+    report_rate: Option<ReportRateMeasurement>,
+    socd_test: Option<(DeviceKey, SocdTest)>,
+    recorder: Option<SessionRecorder>,
+    /// Hotplug events caused outside of `poll`, e.g. by closing a replay.
+    pending_hotplug: Vec<HotplugEvent>,
+}
+
+impl ControllerManager {
+    pub fn new() -> Self {
+        Self::with_selection(BackendSelection::from_env())
+    }
+
+    pub fn with_selection(selection: BackendSelection) -> Self {
+        match selection {
+            BackendSelection::Hardware => Self::with_backends(Self::hardware_backends()),
+            #[cfg(target_os = "linux")]
+            BackendSelection::Evdev => {
+                info!("Using evdev backend");
+                let evdev =
+                    EvdevBackend::new().map(|evdev| Box::new(evdev) as Box<dyn InputBackend>);
+                Self::with_backends(evdev.into_iter().collect())
+            }
+            #[cfg(target_os = "linux")]
+            BackendSelection::Hid => {
+                info!("Using hidraw backend");
+                let hid = HidBackend::new().map(|hid| Box::new(hid) as Box<dyn InputBackend>);
+                Self::with_backends(hid.into_iter().collect())
+            }
+            BackendSelection::Simulated(script) => {
+                info!("Using simulated controller backend");
+                Self::with_backends(vec![Box::new(SimBackend::new(script))])
+            }
+            BackendSelection::Replay(recording) => {
+                info!("Replaying recorded session");
+                Self::with_backends(vec![Box::new(ReplayBackend::new(recording))])
+            }
+            BackendSelection::Evemu(recording) => {
+                info!(name = %recording.description.name, "Playing back evemu recording");
+                Self::with_backends(vec![Box::new(EvdevBackend::from_recordings(vec![
+                    recording,
+                ]))])
+            }
+        }
+    }
+
+    fn hardware_backends() -> Vec<Box<dyn InputBackend>> {
+        let mut backends: Vec<Box<dyn InputBackend>> = Vec::new();
+
+        #[cfg(windows)]
+        if let Some(xinput) = XInputBackend::new() {
+            backends.push(Box::new(xinput));
+        }
+
+        if let Some(sdl) = SdlBackend::new() {
+            backends.push(Box::new(sdl));
+        }
+
+        backends
+    }
+
+    /// Builds a manager around an explicit set of backends instead of the
+    /// platform defaults.
+    pub fn with_backends(backends: Vec<Box<dyn InputBackend>>) -> Self {
+        Self {
+            backends,
+            epoch: Instant::now(),
+            config: PollerConfig::from_env(),
+            history: HashMap::new(),
+            disconnected: HashSet::new(),
+            last_sample_at: None,
+            report_rate: None,
+            socd_test: None,
+            recorder: None,
+            pending_hotplug: Vec::new(),
+        }
+    }
+
+    /// Pumps every backend, appends a timestamped sample for each connected
+    /// device to its ring buffer and returns the hotplug events reported plus
+    /// a delta for every device whose state changed.
+    pub fn poll(&mut self) -> Vec<ManagerEvent> {
+        let mut hotplug = std::mem::take(&mut self.pending_hotplug);
+        hotplug.extend(self.backends.iter_mut().flat_map(|backend| backend.poll()));
+
+        let epoch = self.epoch;
+        for event in &hotplug {
+            let timestamp_us = epoch.elapsed().as_micros() as u64;
+            let recorded = match event {
+                HotplugEvent::Connected(info) => {
+                    info!(
+                        controller_type = %info.controller_type,
+                        id = info.id,
+                        name = %info.name,
+                        "Controller connected"
+                    );
+                    self.disconnected
+                        .remove(&DeviceKey::new(&info.controller_type, info.id));
+                    RecordedEvent::Connected {
+                        timestamp_us,
+                        info: info.clone(),
+                    }
+                }
+                HotplugEvent::Disconnected {
+                    id,
+                    controller_type,
+                } => {
+                    info!(controller_type = %controller_type, id, "Controller disconnected");
+                    let device = DeviceKey::new(controller_type, *id);
+                    self.history.remove(&device);
+                    self.disconnected.insert(device.clone());
+                    RecordedEvent::Disconnected {
+                        timestamp_us,
+                        device,
+                    }
+                }
+            };
+            record(&mut self.recorder, &recorded);
+        }
+
+        let mut events: Vec<ManagerEvent> =
+            hotplug.into_iter().map(ManagerEvent::Hotplug).collect();
+
+        for backend in self.backends.iter_mut() {
+            let reports = backend.drain_reports();
+            let Some(measurement) = self.report_rate.as_mut() else {
+                continue;
+            };
+            if measurement.key.controller_type != backend.kind() {
+                continue;
+            }
+            for report in reports.iter().filter(|r| r.id == measurement.key.id) {
+                if measurement.timestamps.len() < MAX_REPORT_TIMESTAMPS {
+                    // Prefer the OS timestamp, it isn't skewed by poll timing
+                    let timestamp_us = report.device_time_us.unwrap_or_else(|| {
+                        let since_epoch = report.received_at.saturating_duration_since(epoch);
+                        since_epoch.as_micros() as u64
+                    });
+                    measurement.timestamps.push(timestamp_us);
+                }
+            }
+        }
+
+        // While measuring, the poller spins as fast as it can to timestamp
+        // reports precisely, but samples are still only taken at the
+        // configured rate.
+        let sample_due = self
+            .last_sample_at
+            .is_none_or(|at| at.elapsed() >= self.config.interval());
+        if self.report_rate.is_some() && !sample_due {
+            return events;
+        }
+        self.last_sample_at = Some(Instant::now());
+
+        let capacity = self.config.history_capacity();
+        for backend in self.backends.iter_mut() {
+            for info in backend.enumerate() {
+                if let Some(state) = backend.read_state(info.id) {
+                    let timestamp_us = epoch.elapsed().as_micros() as u64;
+                    let key = DeviceKey::new(backend.kind(), info.id);
+                    let history = self
+                        .history
+                        .entry(key.clone())
+                        .or_insert_with(|| SampleHistory::new(capacity));
+
+                    let previous = history.latest().map(|sample| &sample.state);
+                    if let Some(delta) =
+                        GamepadStateDelta::between(&key, timestamp_us, previous, &state)
+                    {
+                        events.push(ManagerEvent::State(delta));
+                    }
+
+                    if self.recorder.is_some() {
+                        let recorded = RecordedEvent::Sample {
+                            timestamp_us,
+                            device: key,
+                            state: state.clone(),
+                        };
+                        record(&mut self.recorder, &recorded);
+                    }
+
+                    history.push(InputSample {
+                        timestamp_us,
+                        state,
+                    });
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Starts writing every sample and hotplug event to `path`, replacing any
+    /// recording in progress.
+    pub fn start_recording(
+        &mut self,
+        path: impl AsRef<Path>,
+        format: RecordingFormat,
+    ) -> Result<(), ControllerError> {
+        self.stop_recording();
+        let header =
+            RecordingHeader::new(self.timestamp_us(), self.config.rate_hz, self.gamepads());
+        let recorder = SessionRecorder::create(path, format, &header)?;
+        info!(path = recorder.path(), format = ?format, "Recording started");
+        self.recorder = Some(recorder);
+        Ok(())
+    }
+
+    /// Ends the active recording and flushes it to disk.
+    pub fn stop_recording(&mut self) -> Option<Result<RecordingSummary, ControllerError>> {
+        let summary = self.recorder.take().map(SessionRecorder::finish);
+        if let Some(Ok(summary)) = &summary {
+            info!(
+                path = %summary.path,
+                events = summary.events,
+                duration_us = summary.duration_us,
+                "Recording stopped"
+            );
+        }
+        summary
+    }
+
+    /// Path of the active recording, if any.
+    pub fn recording_path(&self) -> Option<&str> {
+        self.recorder.as_ref().map(SessionRecorder::path)
+    }
+
+    /// Microseconds since the manager started, on the same clock as samples.
+    pub fn timestamp_us(&self) -> u64 {
+        self.epoch.elapsed().as_micros() as u64
+    }
+
+    pub fn poller_config(&self) -> PollerConfig {
+        self.config
+    }
+
+    pub fn set_poll_rate(&mut self, rate_hz: u32) {
+        self.config.rate_hz = rate_hz.clamp(PollerConfig::MIN_RATE_HZ, PollerConfig::MAX_RATE_HZ);
+        let capacity = self.config.history_capacity();
+        for history in self.history.values_mut() {
+            history.set_capacity(capacity);
+        }
+    }
+
+    /// Starts timestamping every input report from a device, replacing any
+    /// measurement already running.
+    pub fn start_report_rate(
+        &mut self,
+        id: usize,
+        controller_type: &str,
+    ) -> Result<(), ControllerError> {
+        self.device_history(id, controller_type)?;
+        self.report_rate = Some(ReportRateMeasurement {
+            key: DeviceKey::new(controller_type, id),
+            timestamps: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Stats for the running measurement so far.
+    pub fn report_rate_stats(&self) -> Option<ReportRateStats> {
+        let measurement = self.report_rate.as_ref()?;
+        ReportRateStats::from_timestamps(&measurement.timestamps)
+    }
+
+    /// Ends the running measurement and returns its final stats.
+    pub fn stop_report_rate(&mut self) -> Option<ReportRateStats> {
+        let measurement = self.report_rate.take()?;
+        ReportRateStats::from_timestamps(&measurement.timestamps)
+    }
+
+    pub fn is_measuring_report_rate(&self) -> bool {
+        self.report_rate.is_some()
+    }
+
+    /// Starts the guided SOCD test on a device, replacing any test in progress.
+    pub fn start_socd_test(
+        &mut self,
+        id: usize,
+        controller_type: &str,
+    ) -> Result<SocdTestStatus, ControllerError> {
+        let test = SocdTest::new(&self.device_layout(id, controller_type)?);
+        let status = test.status();
+        self.socd_test = Some((DeviceKey::new(controller_type, id), test));
+        Ok(status)
+    }
+
+    /// Records what the device is currently outputting for the SOCD test's
+    /// current step and advances to the next one.
+    pub fn record_socd_step(&mut self) -> Option<SocdTestStatus> {
+        let since_us = self.timestamp_us().saturating_sub(SOCD_SAMPLE_WINDOW_US);
+        let (key, test) = self.socd_test.as_mut()?;
+        let samples: Vec<InputSample> = self
+            .history
+            .get(key)
+            .map(|history| history.since(since_us).cloned().collect())
+            .unwrap_or_default();
+        test.record_step(&samples);
+        Some(test.status())
+    }
+
+    pub fn cancel_socd_test(&mut self) {
+        self.socd_test = None;
+    }
+
+    /// Adds a replay of `recording` alongside the other backends, replacing
+    /// any replay already loaded.
+    pub fn load_replay(&mut self, recording: Recording) -> PlaybackStatus {
+        self.close_replay();
+        let replay = ReplayBackend::new(recording);
+        let status = replay.status();
+        info!(duration_us = status.duration_us, "Replay loaded");
+        self.backends.push(Box::new(replay));
+        status
+    }
+
+    /// Removes the replay backend, unplugging its devices.
+    pub fn close_replay(&mut self) {
+        let Some(index) = self
+            .backends
+            .iter()
+            .position(|backend| backend.kind() == "replay")
+        else {
+            return;
+        };
+        let replay = self.backends.remove(index);
+        for info in replay.enumerate() {
+            self.history
+                .remove(&DeviceKey::new(&info.controller_type, info.id));
+            self.pending_hotplug.push(HotplugEvent::Disconnected {
+                id: info.id,
+                controller_type: info.controller_type,
+            });
+        }
+    }
+
+    pub fn control_replay(&mut self, control: PlaybackControl) -> Option<PlaybackStatus> {
+        self.backend_mut("replay")?.control_playback(control)
+    }
+
+    /// Packet counters for a device, `None` if its backend doesn't number
+    /// reports.
+    pub fn packet_stats(
+        &self,
+        id: usize,
+        controller_type: &str,
+    ) -> Result<Option<PacketStats>, ControllerError> {
+        self.device_history(id, controller_type)?;
+        Ok(self
+            .backends
+            .iter()
+            .find(|backend| backend.kind() == controller_type)
+            .and_then(|backend| backend.packet_stats(id)))
+    }
+
+    pub fn reset_packet_stats(
+        &mut self,
+        id: usize,
+        controller_type: &str,
+    ) -> Result<(), ControllerError> {
+        self.device_history(id, controller_type)?;
+        if let Some(backend) = self.backend_mut(controller_type) {
+            backend.reset_packet_stats(id);
+        }
+        Ok(())
+    }
+
+    /// Event codes and kernel ranges behind a device's buttons and axes,
+    /// `None` if its backend doesn't read raw kernel events.
+    pub fn raw_layout(
+        &self,
+        id: usize,
+        controller_type: &str,
+    ) -> Result<Option<RawInputLayout>, ControllerError> {
+        self.device_history(id, controller_type)?;
+        Ok(self
+            .backends
+            .iter()
+            .find(|backend| backend.kind() == controller_type)
+            .and_then(|backend| backend.raw_layout(id)))
+    }
+
+    /// The latest raw input report of each report ID from a device, empty
+    /// if its backend doesn't read raw reports.
+    pub fn raw_reports(
+        &self,
+        id: usize,
+        controller_type: &str,
+    ) -> Result<Vec<RawReport>, ControllerError> {
+        self.device_history(id, controller_type)?;
+        Ok(self
+            .backends
+            .iter()
+            .find(|backend| backend.kind() == controller_type)
+            .map(|backend| backend.raw_reports(id))
+            .unwrap_or_default())
+    }
+
+    /// A device's parsed HID report descriptor, `None` if its backend
+    /// doesn't read raw reports.
+    pub fn report_descriptor(
+        &self,
+        id: usize,
+        controller_type: &str,
+    ) -> Result<Option<ReportDescriptor>, ControllerError> {
+        self.device_history(id, controller_type)?;
+        Ok(self
+            .backends
+            .iter()
+            .find(|backend| backend.kind() == controller_type)
+            .and_then(|backend| backend.report_descriptor(id)))
+    }
+
+    pub fn gamepads(&self) -> Vec<GamepadInfo> {
+        self.backends
+            .iter()
+            .flat_map(|backend| backend.enumerate())
+            .collect()
+    }
+
+    /// What each button and axis of a device is, empty if its backend no
+    /// longer lists it.
+    pub fn device_layout(
+        &self,
+        id: usize,
+        controller_type: &str,
+    ) -> Result<DeviceLayout, ControllerError> {
+        self.device_history(id, controller_type)?;
+        Ok(self
+            .backends
+            .iter()
+            .find(|backend| backend.kind() == controller_type)
+            .and_then(|backend| backend.enumerate().into_iter().find(|info| info.id == id))
+            .map(|info| info.layout)
+            .unwrap_or_default())
+    }
+
+    /// Most recent sample's state for a device.
+    pub fn gamepad_state(
+        &self,
+        id: usize,
+        controller_type: &str,
+    ) -> Result<GamepadState, ControllerError> {
+        self.device_history(id, controller_type)?
+            .latest()
+            .map(|sample| sample.state.clone())
+            .ok_or_else(|| ControllerError::DeviceNotFound {
+                controller_type: controller_type.to_string(),
+                id,
+            })
+    }
+
+    /// Buffered samples for a device taken at or after `since_us`.
+    pub fn samples_since(
+        &self,
+        id: usize,
+        controller_type: &str,
+        since_us: u64,
+    ) -> Result<Vec<InputSample>, ControllerError> {
+        let history = self.device_history(id, controller_type)?;
+        Ok(history.since(since_us).cloned().collect())
+    }
+
+    /// A device's sample history, or why there is none.
+    fn device_history(
+        &self,
+        id: usize,
+        controller_type: &str,
+    ) -> Result<&SampleHistory, ControllerError> {
+        if !self
+            .backends
+            .iter()
+            .any(|backend| backend.kind() == controller_type)
+        {
+            let controller_type = controller_type.to_string();
+            return Err(if CONTROLLER_TYPES.contains(&controller_type.as_str()) {
+                ControllerError::BackendUnavailable { controller_type }
+            } else {
+                ControllerError::InvalidControllerType { controller_type }
+            });
+        }
+
+        let key = DeviceKey::new(controller_type, id);
+        self.history.get(&key).ok_or_else(|| {
+            if self.disconnected.contains(&key) {
+                ControllerError::Disconnected {
+                    controller_type: key.controller_type,
+                    id,
+                }
+            } else {
+                ControllerError::DeviceNotFound {
+                    controller_type: key.controller_type,
+                    id,
+                }
+            }
+        })
+    }
+
+    pub fn backend_mut(&mut self, controller_type: &str) -> Option<&mut Box<dyn InputBackend>> {
+        self.backends
+            .iter_mut()
+            .find(|backend| backend.kind() == controller_type)
+    }
+}
+
+/// Appends an event to the active recording, dropping the recording if the
+/// write fails.
+fn record(recorder: &mut Option<SessionRecorder>, event: &RecordedEvent) {
+    if let Some(active) = recorder.as_mut() {
+        if let Err(e) = active.record(event) {
+            error!(path = active.path(), error = %e, "Recording write failed, stopping recording");
+            *recorder = None;
+        }
+    }
+}
+
+impl Default for ControllerManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,89 @@
+use super::{ControllerManager, ManagerEvent};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+/// Overrides the default input polling rate, in Hz.
+pub const POLL_RATE_ENV: &str = "SLOPTESTER_POLL_HZ";
+
+#[derive(Debug, Clone, Copy)]
+pub struct PollerConfig {
+    /// How often the input thread polls the backends.
+    pub rate_hz: u32,
+    /// How many seconds of samples each device's ring buffer keeps.
+    pub history_secs: u32,
+}
+
+impl PollerConfig {
+    pub const MIN_RATE_HZ: u32 = 1;
+    pub const MAX_RATE_HZ: u32 = 8000;
+
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(value) = std::env::var(POLL_RATE_ENV) {
+            match value.parse() {
+                Ok(rate_hz) => config.rate_hz = rate_hz,
+                Err(_) => warn!(value = %value, "Ignoring invalid {}", POLL_RATE_ENV),
+            }
+        }
+        config.rate_hz = config.rate_hz.clamp(Self::MIN_RATE_HZ, Self::MAX_RATE_HZ);
+        config
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(1) / self.rate_hz.max(1)
+    }
+
+    /// Ring buffer capacity needed to hold `history_secs` at `rate_hz`.
+    pub fn history_capacity(&self) -> usize {
+        self.rate_hz as usize * self.history_secs as usize
+    }
+}
+
+impl Default for PollerConfig {
+    fn default() -> Self {
+        Self {
+            rate_hz: 1000,
+            history_secs: 30,
+        }
+    }
+}
+
+/// Starts the dedicated input thread.
+///
+/// The thread polls the manager at its configured rate, independent of the
+/// frontend's frame rate, and only holds the lock for the duration of a poll.
+/// Events from each poll are handed to `on_events` after the lock is
+/// released. While a report rate measurement runs it polls back to back
+/// instead of sleeping. It exits if the manager's mutex is poisoned.
+pub fn spawn_poller<F>(manager: Arc<Mutex<ControllerManager>>, mut on_events: F) -> JoinHandle<()>
+where
+    F: FnMut(Vec<ManagerEvent>) + Send + 'static,
+{
+    thread::Builder::new()
+        .name("input-poller".to_string())
+        .spawn(move || loop {
+            let started = Instant::now();
+            let (events, interval, busy) = match manager.lock() {
+                Ok(mut manager) => (
+                    manager.poll(),
+                    manager.poller_config().interval(),
+                    manager.is_measuring_report_rate(),
+                ),
+                Err(_) => {
+                    error!("Controller manager lock poisoned, stopping input thread");
+                    break;
+                }
+            };
+            if !events.is_empty() {
+                on_events(events);
+            }
+            if busy {
+                thread::yield_now();
+            } else {
+                thread::sleep(interval.saturating_sub(started.elapsed()));
+            }
+        })
+        .expect("failed to spawn input polling thread")
+}
@@ -0,0 +1,504 @@
+use crate::utils::{AXIS_NAMES, BUTTON_NAMES};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GamepadInfo {
+    pub id: usize,
+    pub name: String,
+    pub controller_type: String,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    /// Native control counts, for joysticks read without a game controller
+    /// mapping.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub joystick: Option<JoystickLayout>,
+    /// Names and kinds of the buttons and axes in the device's state. Empty
+    /// in recordings made before layouts existed.
+    #[serde(default)]
+    pub layout: DeviceLayout,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonKind {
+    /// A, B, X, Y and other action buttons.
+    Face,
+    Shoulder,
+    /// Back, Start, Guide, Share and the like.
+    Menu,
+    /// Stick clicks.
+    Stick,
+    /// D-pad and hat switch directions.
+    Dpad,
+    /// Back paddles.
+    Paddle,
+    Touchpad,
+    Other,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AxisKind {
+    Stick,
+    Trigger,
+    /// A hat switch reported as an axis, as evdev does.
+    Hat,
+    /// Motion since the previous read, like trackball movement.
+    Relative,
+    Other,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ButtonLayout {
+    pub name: String,
+    pub kind: ButtonKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AxisLayout {
+    pub name: String,
+    pub kind: AxisKind,
+}
+
+/// What each button and axis of a device is. `buttons[i]` and `axes[i]`
+/// describe the same index in its `GamepadState`, which is sized to match.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct DeviceLayout {
+    pub buttons: Vec<ButtonLayout>,
+    pub axes: Vec<AxisLayout>,
+}
+
+impl DeviceLayout {
+    /// The 15 buttons and 6 axes of XInput and SDL game controllers, named
+    /// after `BUTTON_NAMES` and `AXIS_NAMES`.
+    pub fn standard() -> Self {
+        use ButtonKind::*;
+        const BUTTON_KINDS: [ButtonKind; 15] = [
+            Face, Face, Face, Face, Shoulder, Shoulder, Menu, Menu, Menu, Stick, Stick, Dpad, Dpad,
+            Dpad, Dpad,
+        ];
+        const AXIS_KINDS: [AxisKind; 6] = [
+            AxisKind::Stick,
+            AxisKind::Stick,
+            AxisKind::Stick,
+            AxisKind::Stick,
+            AxisKind::Trigger,
+            AxisKind::Trigger,
+        ];
+
+        let mut layout = Self::default();
+        for (name, kind) in BUTTON_NAMES.iter().zip(BUTTON_KINDS) {
+            layout.push_button(*name, kind);
+        }
+        for (name, kind) in AXIS_NAMES.iter().zip(AXIS_KINDS) {
+            layout.push_axis(*name, kind);
+        }
+        layout
+    }
+
+    /// Numbered buttons and axes, for devices nothing more is known about.
+    pub fn generic(button_count: usize, axis_count: usize) -> Self {
+        let mut layout = Self::default();
+        for index in 0..button_count {
+            layout.push_button(format!("button_{}", index), ButtonKind::Other);
+        }
+        for index in 0..axis_count {
+            layout.push_axis(format!("axis_{}", index), AxisKind::Other);
+        }
+        layout
+    }
+
+    /// A best guess for a state of this size when the layout wasn't
+    /// recorded: the standard layout if it fits, numbered controls otherwise.
+    pub fn for_counts(button_count: usize, axis_count: usize) -> Self {
+        if (button_count, axis_count) == (BUTTON_NAMES.len(), AXIS_NAMES.len()) {
+            Self::standard()
+        } else {
+            Self::generic(button_count, axis_count)
+        }
+    }
+
+    pub fn push_button(&mut self, name: impl Into<String>, kind: ButtonKind) {
+        self.buttons.push(ButtonLayout {
+            name: name.into(),
+            kind,
+        });
+    }
+
+    pub fn push_axis(&mut self, name: impl Into<String>, kind: AxisKind) {
+        self.axes.push(AxisLayout {
+            name: name.into(),
+            kind,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buttons.is_empty() && self.axes.is_empty()
+    }
+
+    /// Buttons each hat switch adds: up, right, down, left.
+    pub const HAT_BUTTONS: usize = 4;
+
+    /// The up, right, down and left buttons of hat `index`.
+    pub fn push_hat_buttons(&mut self, index: usize) {
+        let directions: [&str; Self::HAT_BUTTONS] = ["up", "right", "down", "left"];
+        for direction in directions {
+            self.push_button(format!("hat_{}_{}", index, direction), ButtonKind::Dpad);
+        }
+    }
+
+    /// Indices of the up, down, left and right buttons of the first d-pad or
+    /// hat, found by their `_up`, `_down`, ... name suffix.
+    pub fn dpad(&self) -> Option<[usize; 4]> {
+        let find = |direction: &str| {
+            let suffix = format!("_{}", direction);
+            self.buttons.iter().position(|button| {
+                button.kind == ButtonKind::Dpad && button.name.ends_with(&suffix)
+            })
+        };
+        Some([find("up")?, find("down")?, find("left")?, find("right")?])
+    }
+
+    /// `(label, x axis, y axis)` for each stick, pairing stick axes in layout
+    /// order. Standard sticks are labeled `left` and `right`, others by
+    /// their axis names.
+    pub fn sticks(&self) -> Vec<(String, usize, usize)> {
+        let axes: Vec<usize> = (0..self.axes.len())
+            .filter(|index| self.axes[*index].kind == AxisKind::Stick)
+            .collect();
+        axes.chunks_exact(2)
+            .map(|pair| {
+                let (x, y) = (&self.axes[pair[0]].name, &self.axes[pair[1]].name);
+                let label = match x.strip_suffix("_x") {
+                    Some(prefix @ ("left" | "right")) if *y == format!("{}_y", prefix) => {
+                        prefix.to_string()
+                    }
+                    _ => format!("{} / {}", x, y),
+                };
+                (label, pair[0], pair[1])
+            })
+            .collect()
+    }
+
+    /// `(label, axis)` for each trigger, labeled `left` and `right` on the
+    /// standard layout and by axis name otherwise.
+    pub fn triggers(&self) -> Vec<(String, usize)> {
+        self.axes
+            .iter()
+            .enumerate()
+            .filter(|(_, axis)| axis.kind == AxisKind::Trigger)
+            .map(|(index, axis)| {
+                let label = axis.name.strip_suffix("_trigger").unwrap_or(&axis.name);
+                (label.to_string(), index)
+            })
+            .collect()
+    }
+}
+
+/// What a joystick opened without a game controller mapping has. Its state
+/// holds the `axes`, then an x and y axis per ball with the motion since
+/// the previous read, and the `buttons`, then up, right, down and left for
+/// each hat.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct JoystickLayout {
+    pub axes: u32,
+    pub buttons: u32,
+    pub hats: u32,
+    pub balls: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GamepadState {
+    pub buttons: Vec<bool>,
+    pub axes: Vec<f32>,
+    /// The integer values `axes` were normalized from, in the backend's
+    /// native units and direction.
+    pub raw_axes: Vec<i32>,
+    /// How each entry of `raw_axes` was turned into `axes`.
+    pub axis_normalization: Vec<AxisNormalization>,
+}
+
+/// The source range of a raw axis and the scaling applied to it:
+/// `normalized = (raw - center) / divisor`, negated if `inverted`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct AxisNormalization {
+    pub min: i32,
+    pub max: i32,
+    /// The raw value that normalizes to 0. Zero except for centered axes
+    /// reported in an unsigned range, like a 0..255 stick.
+    #[serde(default)]
+    pub center: f32,
+    pub divisor: f32,
+    pub inverted: bool,
+}
+
+impl AxisNormalization {
+    pub const fn new(min: i32, max: i32, divisor: f32) -> Self {
+        Self {
+            min,
+            max,
+            center: 0.0,
+            divisor,
+            inverted: false,
+        }
+    }
+
+    /// Maps `min..=max` onto -1..1 around its midpoint.
+    pub fn centered(min: i32, max: i32) -> Self {
+        let half_range = (f64::from(max) - f64::from(min)) / 2.0;
+        Self {
+            min,
+            max,
+            center: (f64::from(min) + half_range) as f32,
+            divisor: half_range.max(1.0) as f32,
+            inverted: false,
+        }
+    }
+
+    pub const fn inverted(self) -> Self {
+        Self {
+            inverted: true,
+            ..self
+        }
+    }
+
+    pub fn apply(&self, raw: i32) -> f32 {
+        let value = (raw as f32 - self.center) / self.divisor;
+        if self.inverted {
+            -value
+        } else {
+            value
+        }
+    }
+}
+
+/// Where a device's buttons and axes come from, for backends that read raw
+/// kernel event codes (evdev). `buttons[i]` and `axes[i]` describe the same
+/// index in `GamepadState`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RawInputLayout {
+    pub buttons: Vec<RawButton>,
+    pub axes: Vec<RawAxis>,
+}
+
+/// An `EV_KEY` code.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RawButton {
+    pub code: u16,
+    /// Kernel name of the code, e.g. `BTN_SOUTH`.
+    pub name: String,
+}
+
+/// An `EV_ABS` code and the range the kernel reports for it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RawAxis {
+    pub code: u16,
+    /// Kernel name of the code, e.g. `ABS_HAT0X`.
+    pub name: String,
+    pub min: i32,
+    pub max: i32,
+    /// Changes smaller than this are filtered out by the kernel as noise.
+    pub fuzz: i32,
+    /// Values within this distance of the center are reported as centered
+    /// by consumers such as joydev.
+    pub flat: i32,
+    /// Units per millimeter, or per radian for rotational axes. 0 if unknown.
+    pub resolution: i32,
+}
+
+/// An input report exactly as a device sent it, for backends that read
+/// reports directly (HID).
+#[derive(Debug, Serialize, Clone)]
+pub struct RawReport {
+    /// `None` if the device doesn't number its reports.
+    pub report_id: Option<u8>,
+    /// The report bytes, starting with the report ID if there is one.
+    pub bytes: Vec<u8>,
+}
+
+/// A `GamepadState` stamped with microseconds since the manager started,
+/// taken from a monotonic clock.
+#[derive(Debug, Serialize, Clone)]
+pub struct InputSample {
+    pub timestamp_us: u64,
+    pub state: GamepadState,
+}
+
+/// The buttons and axes that changed between two samples of one device.
+/// Entries are `(index, new value)`.
+#[derive(Debug, Serialize, Clone)]
+pub struct GamepadStateDelta {
+    pub id: usize,
+    pub controller_type: String,
+    pub timestamp_us: u64,
+    pub buttons: Vec<(usize, bool)>,
+    pub axes: Vec<(usize, f32)>,
+    pub raw_axes: Vec<(usize, i32)>,
+}
+
+impl GamepadStateDelta {
+    /// Diffs `current` against `previous`, treating every entry as changed if
+    /// there is no previous state. Returns `None` when nothing changed.
+    pub fn between(
+        key: &DeviceKey,
+        timestamp_us: u64,
+        previous: Option<&GamepadState>,
+        current: &GamepadState,
+    ) -> Option<Self> {
+        let buttons: Vec<(usize, bool)> = current
+            .buttons
+            .iter()
+            .enumerate()
+            .filter(|(i, pressed)| previous.and_then(|p| p.buttons.get(*i)) != Some(*pressed))
+            .map(|(i, pressed)| (i, *pressed))
+            .collect();
+        let axes: Vec<(usize, f32)> = current
+            .axes
+            .iter()
+            .enumerate()
+            .filter(|(i, value)| previous.and_then(|p| p.axes.get(*i)) != Some(*value))
+            .map(|(i, value)| (i, *value))
+            .collect();
+        let raw_axes: Vec<(usize, i32)> = current
+            .raw_axes
+            .iter()
+            .enumerate()
+            .filter(|(i, value)| previous.and_then(|p| p.raw_axes.get(*i)) != Some(*value))
+            .map(|(i, value)| (i, *value))
+            .collect();
+
+        if buttons.is_empty() && axes.is_empty() && raw_axes.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            id: key.id,
+            controller_type: key.controller_type.clone(),
+            timestamp_us,
+            buttons,
+            axes,
+            raw_axes,
+        })
+    }
+}
+
+/// Identifies a device across backends.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct DeviceKey {
+    pub controller_type: String,
+    pub id: usize,
+}
+
+impl DeviceKey {
+    pub fn new(controller_type: &str, id: usize) -> Self {
+        Self {
+            controller_type: controller_type.to_string(),
+            id,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ControllerData {
+    pub name: String,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    pub buttons: Vec<bool>,
+    pub axes: Vec<f32>,
+    pub raw_axes: Vec<i32>,
+    /// Set for joysticks read without a game controller mapping.
+    pub joystick: Option<JoystickLayout>,
+    pub layout: DeviceLayout,
+}
+
+/// Packet sequence counters for backends whose reports carry a packet number
+/// (XInput's `dwPacketNumber`).
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+pub struct PacketStats {
+    /// Times the device state was read.
+    pub reads: u64,
+    /// Reads that returned a packet number not seen before.
+    pub new_packets: u64,
+    /// Reads that returned the same packet number as the previous read.
+    pub duplicate_reads: u64,
+    /// Packet numbers that were skipped between two consecutive reads, i.e.
+    /// controller updates that were never sampled.
+    pub skipped_packets: u64,
+    /// Largest jump in packet number between two reads.
+    pub max_gap: u32,
+    pub last_packet: Option<u32>,
+}
+
+impl PacketStats {
+    /// Records a read and returns whether it carried a new packet.
+    pub fn record(&mut self, packet: u32) -> bool {
+        self.reads += 1;
+        let is_new = match self.last_packet {
+            Some(last) if last == packet => {
+                self.duplicate_reads += 1;
+                false
+            }
+            Some(last) => {
+                let gap = packet.wrapping_sub(last);
+                self.skipped_packets += u64::from(gap - 1);
+                self.max_gap = self.max_gap.max(gap);
+                true
+            }
+            None => true,
+        };
+        if is_new {
+            self.new_packets += 1;
+        }
+        self.last_packet = Some(packet);
+        is_new
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct XInputState {
+    pub connected: bool,
+    pub buttons: u16,
+    pub left_x: i16,
+    pub left_y: i16,
+    pub right_x: i16,
+    pub right_y: i16,
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_controls() {
+        let layout = DeviceLayout::standard();
+        assert_eq!(layout.dpad(), Some([11, 12, 13, 14]));
+        assert_eq!(
+            layout.sticks(),
+            [("left".to_string(), 0, 1), ("right".to_string(), 2, 3)]
+        );
+        assert_eq!(
+            layout.triggers(),
+            [("left".to_string(), 4), ("right".to_string(), 5)]
+        );
+    }
+
+    #[test]
+    fn native_controls() {
+        // Like a HID joystick: buttons, a hat, then X, Y and a throttle
+        let mut layout = DeviceLayout::generic(2, 0);
+        layout.push_hat_buttons(0);
+        layout.push_axis("x", AxisKind::Stick);
+        layout.push_axis("y", AxisKind::Stick);
+        layout.push_axis("throttle", AxisKind::Other);
+        assert_eq!(layout.dpad(), Some([2, 4, 5, 3]));
+        assert_eq!(layout.sticks(), [("x / y".to_string(), 0, 1)]);
+        assert!(layout.triggers().is_empty());
+
+        let layout = DeviceLayout::generic(15, 6);
+        assert_eq!(layout.dpad(), None);
+        assert!(layout.sticks().is_empty());
+        assert!(layout.triggers().is_empty());
+    }
+}
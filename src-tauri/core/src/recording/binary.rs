@@ -0,0 +1,476 @@
+//! Binary recording layout. All integers are little endian.
+//!
+//! ```text
+//! magic        8 bytes  "SLOPREC\0"
+//! header_len   u32
+//! header       header_len bytes of RecordingHeader JSON
+//! events       until end of file
+//! ```
+//!
+//! Each event starts with a `u8` tag and a `u64` timestamp in microseconds:
+//!
+//! ```text
+//! 0 connected     u32 length + GamepadInfo JSON
+//! 1 disconnected  device
+//! 2 sample        device
+//!                 u16 button count, then the buttons packed 8 per byte, LSB first
+//!                 u16 axis count, then that many f32 normalized values
+//!                 u16 raw axis count, then that many i32 raw values
+//!                 u8 0 if the normalization is the same as this device's
+//!                 previous sample, or 1 followed by
+//!                 u16 normalization count, then per axis:
+//!                     i32 min, i32 max, f32 divisor, u8 inverted, f32 center
+//!
+//! device = u8 length + controller_type UTF-8, u32 id
+//! ```
+//!
+//! Version 1 files have no flag byte, a normalization count of 0 stands for
+//! "same as the previous sample" there. Versions 1 and 2 have no center, it
+//! is 0.
+
+use super::{with_context, RecordedEvent, Recording, RecordingHeader};
+use crate::models::{AxisNormalization, DeviceKey, GamepadInfo, GamepadState};
+use std::collections::HashMap;
+use std::io::{self, ErrorKind, Read, Write};
+
+pub const MAGIC: &[u8; 8] = b"SLOPREC\0";
+
+const TAG_CONNECTED: u8 = 0;
+const TAG_DISCONNECTED: u8 = 1;
+const TAG_SAMPLE: u8 = 2;
+
+/// Upper bounds for the JSON blocks, so a corrupt length fails the read
+/// instead of allocating up to 4 GiB.
+const MAX_HEADER_LEN: usize = 1 << 20;
+const MAX_INFO_LEN: usize = 64 << 10;
+
+pub fn write_header(writer: &mut impl Write, header: &RecordingHeader) -> io::Result<()> {
+    let json = serde_json::to_vec(header)?;
+    writer.write_all(MAGIC)?;
+    writer.write_all(&(json.len() as u32).to_le_bytes())?;
+    writer.write_all(&json)
+}
+
+/// Writes events, remembering each device's last normalization so it is
+/// only stored when it changes.
+#[derive(Default)]
+pub struct Encoder {
+    normalization: HashMap<DeviceKey, Vec<AxisNormalization>>,
+}
+
+impl Encoder {
+    pub fn write_event(
+        &mut self,
+        writer: &mut impl Write,
+        event: &RecordedEvent,
+    ) -> io::Result<()> {
+        match event {
+            RecordedEvent::Connected { timestamp_us, info } => {
+                writer.write_all(&[TAG_CONNECTED])?;
+                writer.write_all(&timestamp_us.to_le_bytes())?;
+                let json = serde_json::to_vec(info)?;
+                writer.write_all(&(json.len() as u32).to_le_bytes())?;
+                writer.write_all(&json)
+            }
+            RecordedEvent::Disconnected {
+                timestamp_us,
+                device,
+            } => {
+                self.normalization.remove(device);
+                writer.write_all(&[TAG_DISCONNECTED])?;
+                writer.write_all(&timestamp_us.to_le_bytes())?;
+                write_device(writer, device)
+            }
+            RecordedEvent::Sample {
+                timestamp_us,
+                device,
+                state,
+            } => {
+                writer.write_all(&[TAG_SAMPLE])?;
+                writer.write_all(&timestamp_us.to_le_bytes())?;
+                write_device(writer, device)?;
+
+                writer.write_all(&(state.buttons.len() as u16).to_le_bytes())?;
+                let mut packed = vec![0u8; state.buttons.len().div_ceil(8)];
+                for (index, pressed) in state.buttons.iter().enumerate() {
+                    if *pressed {
+                        packed[index / 8] |= 1 << (index % 8);
+                    }
+                }
+                writer.write_all(&packed)?;
+
+                writer.write_all(&(state.axes.len() as u16).to_le_bytes())?;
+                for value in &state.axes {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+                writer.write_all(&(state.raw_axes.len() as u16).to_le_bytes())?;
+                for value in &state.raw_axes {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+
+                if self.normalization.get(device) == Some(&state.axis_normalization) {
+                    return writer.write_all(&[0]);
+                }
+                writer.write_all(&[1])?;
+                writer.write_all(&(state.axis_normalization.len() as u16).to_le_bytes())?;
+                for normalization in &state.axis_normalization {
+                    writer.write_all(&normalization.min.to_le_bytes())?;
+                    writer.write_all(&normalization.max.to_le_bytes())?;
+                    writer.write_all(&normalization.divisor.to_le_bytes())?;
+                    writer.write_all(&[normalization.inverted as u8])?;
+                    writer.write_all(&normalization.center.to_le_bytes())?;
+                }
+                self.normalization
+                    .insert(device.clone(), state.axis_normalization.clone());
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_device(writer: &mut impl Write, device: &DeviceKey) -> io::Result<()> {
+    let name = device.controller_type.as_bytes();
+    writer.write_all(&[name.len() as u8])?;
+    writer.write_all(name)?;
+    writer.write_all(&(device.id as u32).to_le_bytes())
+}
+
+pub fn read_recording(reader: &mut impl Read) -> io::Result<Recording> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Missing binary recording magic",
+        ));
+    }
+    let header_len = read_len(reader, MAX_HEADER_LEN).map_err(|e| with_context("Bad header", e))?;
+    let header_json = read_bytes(reader, header_len)?;
+    let header: RecordingHeader =
+        serde_json::from_slice(&header_json).map_err(|e| with_context("Bad header", e.into()))?;
+    // Events of a newer layout can't be decoded
+    header.validate()?;
+
+    let mut decoder = Decoder::new(header.version);
+    let mut events = Vec::new();
+    loop {
+        let mut tag = [0u8; 1];
+        match reader.read_exact(&mut tag) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+        let event = decoder
+            .read_event(reader, tag[0])
+            .map_err(|e| with_context(format_args!("Bad event {}", events.len()), e))?;
+        events.push(event);
+    }
+
+    Ok(Recording { header, events })
+}
+
+struct Decoder {
+    /// `RecordingHeader::version` of the file being read.
+    version: u32,
+    normalization: HashMap<DeviceKey, Vec<AxisNormalization>>,
+}
+
+impl Decoder {
+    fn new(version: u32) -> Self {
+        Self {
+            version,
+            normalization: HashMap::new(),
+        }
+    }
+
+    fn read_event(&mut self, reader: &mut impl Read, tag: u8) -> io::Result<RecordedEvent> {
+        let timestamp_us = read_u64(reader)?;
+        match tag {
+            TAG_CONNECTED => {
+                let len = read_len(reader, MAX_INFO_LEN)?;
+                let info: GamepadInfo = serde_json::from_slice(&read_bytes(reader, len)?)?;
+                Ok(RecordedEvent::Connected { timestamp_us, info })
+            }
+            TAG_DISCONNECTED => {
+                let device = read_device(reader)?;
+                self.normalization.remove(&device);
+                Ok(RecordedEvent::Disconnected {
+                    timestamp_us,
+                    device,
+                })
+            }
+            TAG_SAMPLE => {
+                let device = read_device(reader)?;
+
+                let button_count = read_u16(reader)? as usize;
+                let packed = read_bytes(reader, button_count.div_ceil(8))?;
+                let buttons = (0..button_count)
+                    .map(|index| packed[index / 8] & (1 << (index % 8)) != 0)
+                    .collect();
+
+                let axis_count = read_u16(reader)?;
+                let axes = (0..axis_count)
+                    .map(|_| read_u32(reader).map(f32::from_bits))
+                    .collect::<io::Result<Vec<f32>>>()?;
+                let raw_count = read_u16(reader)?;
+                let raw_axes = (0..raw_count)
+                    .map(|_| read_u32(reader).map(|value| value as i32))
+                    .collect::<io::Result<Vec<i32>>>()?;
+
+                let normalization_count = if self.version < 2 {
+                    Some(read_u16(reader)?).filter(|count| *count > 0)
+                } else if read_bytes(reader, 1)?[0] != 0 {
+                    Some(read_u16(reader)?)
+                } else {
+                    None
+                };
+                let axis_normalization = if let Some(normalization_count) = normalization_count {
+                    let normalization = (0..normalization_count)
+                        .map(|_| {
+                            Ok(AxisNormalization {
+                                min: read_u32(reader)? as i32,
+                                max: read_u32(reader)? as i32,
+                                divisor: f32::from_bits(read_u32(reader)?),
+                                inverted: read_bytes(reader, 1)?[0] != 0,
+                                // Fields are read in the order written here
+                                center: if self.version < 3 {
+                                    0.0
+                                } else {
+                                    f32::from_bits(read_u32(reader)?)
+                                },
+                            })
+                        })
+                        .collect::<io::Result<Vec<AxisNormalization>>>()?;
+                    self.normalization
+                        .insert(device.clone(), normalization.clone());
+                    normalization
+                } else {
+                    self.normalization.get(&device).cloned().unwrap_or_default()
+                };
+
+                Ok(RecordedEvent::Sample {
+                    timestamp_us,
+                    device,
+                    state: GamepadState {
+                        buttons,
+                        axes,
+                        raw_axes,
+                        axis_normalization,
+                    },
+                })
+            }
+            other => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown event tag {}", other),
+            )),
+        }
+    }
+}
+
+fn read_device(reader: &mut impl Read) -> io::Result<DeviceKey> {
+    let len = read_bytes(reader, 1)?[0] as usize;
+    let controller_type = String::from_utf8(read_bytes(reader, len)?)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    let id = read_u32(reader)? as usize;
+    Ok(DeviceKey {
+        controller_type,
+        id,
+    })
+}
+
+/// Reads a `u32` length, rejecting ones over `max`.
+fn read_len(reader: &mut impl Read, max: usize) -> io::Result<usize> {
+    let len = read_u32(reader)? as usize;
+    if len > max {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("length {} exceeds {} bytes", len, max),
+        ));
+    }
+    Ok(len)
+}
+
+fn read_bytes(reader: &mut impl Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut buffer = vec![0u8; len];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn read_u16(reader: &mut impl Read) -> io::Result<u16> {
+    let mut buffer = [0u8; 2];
+    reader.read_exact(&mut buffer)?;
+    Ok(u16::from_le_bytes(buffer))
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buffer = [0u8; 8];
+    reader.read_exact(&mut buffer)?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::RECORDING_VERSION;
+
+    fn header() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_header(&mut bytes, &RecordingHeader::new(0, 1000, Vec::new())).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn oversized_header() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        let error = read_recording(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "Bad header: length 4294967295 exceeds 1048576 bytes"
+        );
+    }
+
+    #[test]
+    fn oversized_gamepad_info() {
+        let mut bytes = header();
+        bytes.push(TAG_CONNECTED);
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&(MAX_INFO_LEN as u32 + 1).to_le_bytes());
+        let error = read_recording(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Bad event 0: length 65537 exceeds 65536 bytes"
+        );
+    }
+
+    fn sample(timestamp_us: u64, axis_normalization: Vec<AxisNormalization>) -> RecordedEvent {
+        RecordedEvent::Sample {
+            timestamp_us,
+            device: DeviceKey::new("sdl", 2),
+            state: GamepadState {
+                buttons: (0..10).map(|index| index % 3 == 0).collect(),
+                axes: vec![-0.5; axis_normalization.len()],
+                raw_axes: vec![-16384; axis_normalization.len()],
+                axis_normalization,
+            },
+        }
+    }
+
+    fn normalizations(recording: &Recording) -> Vec<Vec<AxisNormalization>> {
+        recording
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                RecordedEvent::Sample { state, .. } => Some(state.axis_normalization.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let info = GamepadInfo {
+            id: 2,
+            name: "Pad".to_string(),
+            controller_type: "sdl".to_string(),
+            vendor_id: None,
+            product_id: None,
+            joystick: None,
+            layout: Default::default(),
+        };
+        let normalization = AxisNormalization::centered(0, 255);
+        let events = [
+            RecordedEvent::Connected {
+                timestamp_us: 1,
+                info,
+            },
+            sample(2, vec![normalization]),
+            sample(3, vec![normalization]),
+            // Losing every axis is a change, not "same as before"
+            sample(4, Vec::new()),
+            RecordedEvent::Disconnected {
+                timestamp_us: 5,
+                device: DeviceKey::new("sdl", 2),
+            },
+        ];
+
+        let mut bytes = header();
+        let mut encoder = Encoder::default();
+        for event in &events {
+            encoder.write_event(&mut bytes, event).unwrap();
+        }
+        let recording = read_recording(&mut bytes.as_slice()).unwrap();
+        assert_eq!(recording.header.poll_rate_hz, 1000);
+        let [RecordedEvent::Connected { info, .. }, RecordedEvent::Sample { state, .. }, _, _, RecordedEvent::Disconnected {
+            timestamp_us: 5, ..
+        }] = recording.events.as_slice()
+        else {
+            panic!("unexpected events {:?}", recording.events);
+        };
+        assert_eq!((info.id, info.name.as_str()), (2, "Pad"));
+        assert_eq!(
+            state.buttons,
+            [true, false, false, true, false, false, true, false, false, true]
+        );
+        assert_eq!(state.raw_axes, [-16384]);
+        assert_eq!(
+            normalizations(&recording),
+            [vec![normalization], vec![normalization], Vec::new()]
+        );
+    }
+
+    #[test]
+    fn version_1() {
+        let mut header_v1 = RecordingHeader::new(0, 1000, Vec::new());
+        header_v1.version = 1;
+        let mut bytes = Vec::new();
+        write_header(&mut bytes, &header_v1).unwrap();
+
+        // Version 1 samples have no flag, a count of 0 repeats the last list
+        let normalization = AxisNormalization::new(0, 255, 255.0);
+        for count in [1u16, 0] {
+            bytes.push(TAG_SAMPLE);
+            bytes.extend_from_slice(&0u64.to_le_bytes());
+            write_device(&mut bytes, &DeviceKey::new("sdl", 2)).unwrap();
+            bytes.extend_from_slice(&0u16.to_le_bytes());
+            bytes.extend_from_slice(&1u16.to_le_bytes());
+            bytes.extend_from_slice(&1.0f32.to_le_bytes());
+            bytes.extend_from_slice(&1u16.to_le_bytes());
+            bytes.extend_from_slice(&255i32.to_le_bytes());
+            bytes.extend_from_slice(&count.to_le_bytes());
+            if count > 0 {
+                bytes.extend_from_slice(&0i32.to_le_bytes());
+                bytes.extend_from_slice(&255i32.to_le_bytes());
+                bytes.extend_from_slice(&255.0f32.to_le_bytes());
+                bytes.push(0);
+            }
+        }
+
+        let recording = read_recording(&mut bytes.as_slice()).unwrap();
+        assert_eq!(
+            normalizations(&recording),
+            [vec![normalization], vec![normalization]]
+        );
+    }
+
+    #[test]
+    fn newer_version() {
+        let mut header_next = RecordingHeader::new(0, 1000, Vec::new());
+        header_next.version = RECORDING_VERSION + 1;
+        let mut bytes = Vec::new();
+        write_header(&mut bytes, &header_next).unwrap();
+        // Garbage in a layout this reader doesn't know
+        bytes.extend_from_slice(&[0xff; 16]);
+        let error = read_recording(&mut bytes.as_slice()).unwrap_err();
+        let expected = format!("Recording version {} is newer", RECORDING_VERSION + 1);
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        assert!(error.to_string().starts_with(&expected), "{}", error);
+    }
+}
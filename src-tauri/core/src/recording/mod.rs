@@ -0,0 +1,407 @@
+//! Session recordings: a timestamped trace of every sample and hotplug event
+//! the `ControllerManager` saw, for attaching to bug reports.
+//!
+//! Both formats start with the same [`RecordingHeader`] and then list
+//! [`RecordedEvent`]s in timestamp order:
+//!
+//! - **NDJSON** (`.ndjson`): the header as a JSON object on the first line,
+//!   then one JSON event per line, tagged by `"type"`.
+//! - **Binary** (`.slop`): see [`binary`] for the byte layout. Around a
+//!   tenth of the size, for long captures at high poll rates.
+//!
+//! `RecordingHeader::version` is bumped whenever either layout changes.
+//! Readers reject files with a newer version than they understand.
+
+mod binary;
+
+use crate::error::ControllerError;
+use crate::models::{DeviceKey, GamepadInfo, GamepadState};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+/// Identifies a recording, stored in the header of both formats.
+pub const RECORDING_FORMAT: &str = "sloptester-recording";
+/// 2: binary samples flag whether a normalization list follows.
+/// 3: normalizations carry the axis center.
+pub const RECORDING_VERSION: u32 = 3;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingFormat {
+    Ndjson,
+    Binary,
+}
+
+impl RecordingFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Ndjson => "ndjson",
+            Self::Binary => "slop",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordingHeader {
+    /// Always [`RECORDING_FORMAT`].
+    pub format: String,
+    pub version: u32,
+    /// Wall clock time the recording started, in milliseconds since the Unix
+    /// epoch. Event timestamps are relative to the manager's monotonic clock.
+    pub started_unix_ms: u64,
+    /// Sample clock reading when the recording started.
+    pub started_us: u64,
+    pub poll_rate_hz: u32,
+    /// Devices that were already connected when the recording started.
+    pub devices: Vec<GamepadInfo>,
+}
+
+impl RecordingHeader {
+    pub fn new(started_us: u64, poll_rate_hz: u32, devices: Vec<GamepadInfo>) -> Self {
+        let started_unix_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+
+        Self {
+            format: RECORDING_FORMAT.to_string(),
+            version: RECORDING_VERSION,
+            started_unix_ms,
+            started_us,
+            poll_rate_hz,
+            devices,
+        }
+    }
+
+    fn validate(&self) -> io::Result<()> {
+        if self.format != RECORDING_FORMAT {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Not a sloptester recording: '{}'", self.format),
+            ));
+        }
+        if self.version > RECORDING_VERSION {
+            return Err(io::Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "Recording version {} is newer than the supported version {}",
+                    self.version, RECORDING_VERSION
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One entry in a recording. Timestamps are microseconds on the manager's
+/// sample clock.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    Connected {
+        timestamp_us: u64,
+        info: GamepadInfo,
+    },
+    Disconnected {
+        timestamp_us: u64,
+        device: DeviceKey,
+    },
+    Sample {
+        timestamp_us: u64,
+        device: DeviceKey,
+        state: GamepadState,
+    },
+}
+
+impl RecordedEvent {
+    pub fn timestamp_us(&self) -> u64 {
+        match self {
+            Self::Connected { timestamp_us, .. }
+            | Self::Disconnected { timestamp_us, .. }
+            | Self::Sample { timestamp_us, .. } => *timestamp_us,
+        }
+    }
+}
+
+/// What was written once a recording is stopped.
+#[derive(Debug, Serialize, Clone)]
+pub struct RecordingSummary {
+    pub path: String,
+    pub format: RecordingFormat,
+    pub events: u64,
+    pub duration_us: u64,
+}
+
+/// Streams events to a recording file as they happen.
+pub struct SessionRecorder {
+    path: String,
+    format: RecordingFormat,
+    writer: BufWriter<File>,
+    started_us: u64,
+    last_timestamp_us: u64,
+    events: u64,
+    encoder: binary::Encoder,
+}
+
+impl SessionRecorder {
+    pub fn create(
+        path: impl AsRef<Path>,
+        format: RecordingFormat,
+        header: &RecordingHeader,
+    ) -> Result<Self, ControllerError> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).map_err(|e| ControllerError::file("Failed to create", path, &e))?;
+        let mut recorder = Self {
+            path: path.display().to_string(),
+            format,
+            writer: BufWriter::new(file),
+            started_us: header.started_us,
+            last_timestamp_us: header.started_us,
+            events: 0,
+            encoder: binary::Encoder::default(),
+        };
+
+        let result = match format {
+            RecordingFormat::Ndjson => serde_json::to_writer(&mut recorder.writer, header)
+                .map_err(std::io::Error::from)
+                .and_then(|_| recorder.writer.write_all(b"\n")),
+            RecordingFormat::Binary => binary::write_header(&mut recorder.writer, header),
+        };
+        result.map_err(|e| ControllerError::file("Failed to write", path, &e))?;
+
+        Ok(recorder)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn record(&mut self, event: &RecordedEvent) -> std::io::Result<()> {
+        match self.format {
+            RecordingFormat::Ndjson => {
+                serde_json::to_writer(&mut self.writer, event)?;
+                self.writer.write_all(b"\n")?;
+            }
+            RecordingFormat::Binary => self.encoder.write_event(&mut self.writer, event)?,
+        }
+        self.events += 1;
+        self.last_timestamp_us = event.timestamp_us();
+        Ok(())
+    }
+
+    /// Flushes everything to disk.
+    pub fn finish(mut self) -> Result<RecordingSummary, ControllerError> {
+        self.writer
+            .flush()
+            .map_err(|e| ControllerError::file("Failed to write", &self.path, &e))?;
+
+        Ok(RecordingSummary {
+            path: self.path,
+            format: self.format,
+            events: self.events,
+            duration_us: self.last_timestamp_us.saturating_sub(self.started_us),
+        })
+    }
+}
+
+/// A recording read back into memory.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub header: RecordingHeader,
+    pub events: Vec<RecordedEvent>,
+}
+
+impl Recording {
+    /// Loads a recording in either format, telling them apart by the binary
+    /// magic bytes.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ControllerError> {
+        let path = path.as_ref();
+        let file =
+            File::open(path).map_err(|e| ControllerError::file("Failed to open", path, &e))?;
+        let mut reader = BufReader::new(file);
+
+        let is_binary = reader
+            .fill_buf()
+            .map(|buffer| buffer.starts_with(binary::MAGIC))
+            .map_err(|e| ControllerError::file("Failed to read", path, &e))?;
+        let recording = if is_binary {
+            binary::read_recording(&mut reader)
+        } else {
+            Self::read_ndjson(reader)
+        }
+        .and_then(|recording| recording.header.validate().map(|_| recording))
+        .map_err(|e| ControllerError::file("Invalid recording", path, &e))?;
+
+        Ok(recording)
+    }
+
+    fn read_ndjson(reader: impl Read) -> io::Result<Self> {
+        let mut lines = BufReader::new(reader).lines();
+        let header_line = lines
+            .next()
+            .ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "Empty file"))??;
+        let header: RecordingHeader =
+            serde_json::from_str(&header_line).map_err(|e| with_context("Bad header", e.into()))?;
+
+        let mut events = Vec::new();
+        for (number, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = serde_json::from_str(&line).map_err(|e| {
+                with_context(format_args!("Bad event on line {}", number + 2), e.into())
+            })?;
+            events.push(event);
+        }
+
+        Ok(Self { header, events })
+    }
+}
+
+/// `error` with `context` prepended to its message, keeping its kind.
+fn with_context(context: impl Display, error: io::Error) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {}", context, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AxisNormalization, DeviceLayout};
+
+    fn info(id: usize) -> GamepadInfo {
+        GamepadInfo {
+            id,
+            name: "Pad".to_string(),
+            controller_type: "sdl".to_string(),
+            vendor_id: Some(0x045e),
+            product_id: Some(0x028e),
+            joystick: None,
+            layout: DeviceLayout::standard(),
+        }
+    }
+
+    fn state(pressed: bool) -> GamepadState {
+        GamepadState {
+            buttons: vec![pressed, false],
+            axes: vec![0.5],
+            raw_axes: vec![16384],
+            axis_normalization: vec![AxisNormalization::new(-32768, 32767, 32767.0)],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for format in [RecordingFormat::Ndjson, RecordingFormat::Binary] {
+            let path = std::env::temp_dir().join(format!(
+                "sloptester-recording-{}.{}",
+                std::process::id(),
+                format.extension()
+            ));
+            let header = RecordingHeader::new(1_000, 500, vec![info(0)]);
+            let device = DeviceKey::new("sdl", 1);
+            let events = [
+                RecordedEvent::Connected {
+                    timestamp_us: 2_000,
+                    info: info(1),
+                },
+                RecordedEvent::Sample {
+                    timestamp_us: 3_000,
+                    device: device.clone(),
+                    state: state(true),
+                },
+                RecordedEvent::Disconnected {
+                    timestamp_us: 4_000,
+                    device,
+                },
+            ];
+
+            let mut recorder = SessionRecorder::create(&path, format, &header).unwrap();
+            for event in &events {
+                recorder.record(event).unwrap();
+            }
+            let summary = recorder.finish().unwrap();
+            assert_eq!((summary.events, summary.duration_us), (3, 3_000));
+
+            let recording = Recording::from_file(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(recording.header.version, RECORDING_VERSION);
+            assert_eq!(recording.header.poll_rate_hz, 500);
+            assert_eq!(recording.header.devices[0].layout, DeviceLayout::standard());
+            let timestamps: Vec<u64> = recording.events.iter().map(|e| e.timestamp_us()).collect();
+            assert_eq!(timestamps, [2_000, 3_000, 4_000], "{:?}", format);
+            let RecordedEvent::Sample { device, state, .. } = &recording.events[1] else {
+                panic!("expected a sample, got {:?}", recording.events[1]);
+            };
+            assert_eq!(*device, DeviceKey::new("sdl", 1));
+            assert_eq!(state.buttons, [true, false]);
+            assert_eq!(state.raw_axes, [16384]);
+            assert_eq!(state.axis_normalization[0].divisor, 32767.0);
+        }
+    }
+
+    /// Version 1 files predate `GamepadInfo::joystick` and `layout`.
+    #[test]
+    fn version_1_ndjson() {
+        let device =
+            r#"{"id":0,"name":"Pad","controller_type":"sdl","vendor_id":null,"product_id":null}"#;
+        let file = format!(
+            "{{\"format\":\"sloptester-recording\",\"version\":1,\"started_unix_ms\":0,\"started_us\":0,\"poll_rate_hz\":1000,\"devices\":[{device}]}}\n\
+             {{\"type\":\"connected\",\"timestamp_us\":5,\"info\":{device}}}\n\
+             \n\
+             {{\"type\":\"disconnected\",\"timestamp_us\":6,\"device\":{{\"controller_type\":\"sdl\",\"id\":0}}}}\n"
+        );
+        let recording = Recording::read_ndjson(file.as_bytes()).unwrap();
+        recording.header.validate().unwrap();
+        assert_eq!(recording.header.version, 1);
+        let info = &recording.header.devices[0];
+        assert!(info.joystick.is_none());
+        assert!(info.layout.is_empty());
+        assert_eq!(recording.events.len(), 2);
+    }
+
+    #[test]
+    fn file_errors() {
+        let missing = std::env::temp_dir().join("sloptester-missing/recording.ndjson");
+        let Err(ControllerError::File { path, kind, .. }) = Recording::from_file(&missing) else {
+            panic!("missing file loaded");
+        };
+        assert_eq!(path, missing.display().to_string());
+        assert_eq!(kind, ErrorKind::NotFound);
+
+        let garbage =
+            std::env::temp_dir().join(format!("sloptester-garbage-{}.ndjson", std::process::id()));
+        std::fs::write(&garbage, "not json\n").unwrap();
+        let result = Recording::from_file(&garbage);
+        std::fs::remove_file(&garbage).unwrap();
+        let Err(ControllerError::File { kind, message, .. }) = result else {
+            panic!("garbage loaded");
+        };
+        assert_eq!(kind, ErrorKind::InvalidData);
+        assert!(message.starts_with("Invalid recording "), "{}", message);
+        assert!(message.contains("Bad header: "), "{}", message);
+    }
+
+    #[test]
+    fn finish_before_start() {
+        let path =
+            std::env::temp_dir().join(format!("sloptester-finish-{}.ndjson", std::process::id()));
+        let header = RecordingHeader::new(1_000, 500, Vec::new());
+        let mut recorder =
+            SessionRecorder::create(&path, RecordingFormat::Ndjson, &header).unwrap();
+        // An event stamped before the header is clamped, not an underflow
+        recorder
+            .record(&RecordedEvent::Disconnected {
+                timestamp_us: 10,
+                device: DeviceKey::new("sdl", 0),
+            })
+            .unwrap();
+        let summary = recorder.finish().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(summary.duration_us, 0);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::models::{AxisNormalization, ButtonKind};
+use sdl2::controller::{Axis, Button};
+
+/// Names of the standard layout's buttons, by index.
+pub const BUTTON_NAMES: [&str; 15] = [
+    "a",
+    "b",
+    "x",
+    "y",
+    "left_shoulder",
+    "right_shoulder",
+    "back",
+    "start",
+    "guide",
+    "left_stick",
+    "right_stick",
+    "dpad_up",
+    "dpad_down",
+    "dpad_left",
+    "dpad_right",
+];
+
+/// Names of the standard layout's axes, by index.
+pub const AXIS_NAMES: [&str; 6] = [
+    "left_x",
+    "left_y",
+    "right_x",
+    "right_y",
+    "left_trigger",
+    "right_trigger",
+];
+
+/// SDL buttons beyond the standard layout. A controller whose mapping binds
+/// some of them gets those after the standard buttons, in this order.
+/// Names match SDL's mapping string keys.
+pub const SDL_EXTENDED_BUTTONS: [(Button, &str, ButtonKind); 6] = [
+    // Share, capture or microphone, depending on the controller
+    (Button::Misc1, "misc1", ButtonKind::Menu),
+    (Button::Paddle1, "paddle1", ButtonKind::Paddle),
+    (Button::Paddle2, "paddle2", ButtonKind::Paddle),
+    (Button::Paddle3, "paddle3", ButtonKind::Paddle),
+    (Button::Paddle4, "paddle4", ButtonKind::Paddle),
+    (Button::Touchpad, "touchpad", ButtonKind::Touchpad),
+];
+
+/// Index of `button` in the standard layout, `None` for extended buttons.
+pub fn map_sdl_button(button: Button) -> Option<usize> {
+    use sdl2::controller::Button::*;
+    match button {
+        A => Some(0),
+        B => Some(1),
+        X => Some(2),
+        Y => Some(3),
+        LeftShoulder => Some(4),
+        RightShoulder => Some(5),
+        Back => Some(6),
+        Start => Some(7),
+        Guide => Some(8),
+        LeftStick => Some(9),
+        RightStick => Some(10),
+        DPadUp => Some(11),
+        DPadDown => Some(12),
+        DPadLeft => Some(13),
+        DPadRight => Some(14),
+        Misc1 | Paddle1 | Paddle2 | Paddle3 | Paddle4 | Touchpad => None,
+    }
+}
+
+pub fn map_sdl_axis(axis: Axis) -> usize {
+    use sdl2::controller::Axis::*;
+    match axis {
+        LeftX => 0,
+        LeftY => 1,
+        RightX => 2,
+        RightY => 3,
+        TriggerLeft => 4,
+        TriggerRight => 5,
+    }
+}
+
+pub fn sdl_axis_normalization(axis: Axis) -> AxisNormalization {
+    use sdl2::controller::Axis::*;
+    match axis {
+        // SDL triggers are 0 when released, 32767 when fully pressed
+        TriggerLeft | TriggerRight => AxisNormalization::new(0, 32767, 32767.0),
+        // SDL Y axes point down for positive values, we want up for positive
+        LeftY | RightY => AxisNormalization::new(-32768, 32767, 32767.0).inverted(),
+        // Other axes use regular normalization
+        _ => AxisNormalization::new(-32768, 32767, 32767.0),
+    }
+}
+
+pub fn normalize_sdl_axis(axis: Axis, value: i16) -> f32 {
+    sdl_axis_normalization(axis).apply(i32::from(value))
+}
+
+pub const XINPUT_STICK_NORMALIZATION: AxisNormalization =
+    AxisNormalization::new(-32768, 32767, 32768.0);
+
+// XInput triggers are already in range 0-255, just normalize to 0-1
+pub const XINPUT_TRIGGER_NORMALIZATION: AxisNormalization = AxisNormalization::new(0, 255, 255.0);
+
+pub fn normalize_xinput_axis(value: i16) -> f32 {
+    XINPUT_STICK_NORMALIZATION.apply(i32::from(value))
+}
+
+pub fn normalize_xinput_trigger(value: u8) -> f32 {
+    XINPUT_TRIGGER_NORMALIZATION.apply(i32::from(value))
+}
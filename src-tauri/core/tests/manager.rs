@@ -0,0 +1,198 @@
+//! `ControllerManager` driven by a scripted backend on a manual clock.
+
+use sloptester_core::backends::{
+    BackendCapabilities, HotplugEvent, InputBackend, InputReport, SimBackend, SimScript,
+};
+use sloptester_core::error::ControllerError;
+use sloptester_core::managers::{ControllerManager, ManagerEvent};
+use sloptester_core::models::{GamepadInfo, GamepadState};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const SCRIPT: &str = r#"{
+  "events": [
+    { "at_ms": 0, "device": 0, "action": "connect", "name": "Pad A", "vendor_id": 1118, "product_id": 654 },
+    { "at_ms": 10, "device": 0, "action": "button", "index": 0, "pressed": true },
+    { "at_ms": 20, "device": 0, "action": "axis", "index": 0, "value": -1.0 },
+    { "at_ms": 30, "device": 1, "action": "connect", "name": "Pad B" },
+    { "at_ms": 40, "device": 0, "action": "disconnect" }
+  ]
+}"#;
+
+/// Hands the manager a backend the test can still reach to move its clock.
+struct Shared(Arc<Mutex<SimBackend>>);
+
+impl InputBackend for Shared {
+    fn kind(&self) -> &'static str {
+        self.0.lock().unwrap().kind()
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        self.0.lock().unwrap().capabilities()
+    }
+
+    fn poll(&mut self) -> Vec<HotplugEvent> {
+        self.0.lock().unwrap().poll()
+    }
+
+    fn enumerate(&self) -> Vec<GamepadInfo> {
+        self.0.lock().unwrap().enumerate()
+    }
+
+    fn read_state(&mut self, id: usize) -> Option<GamepadState> {
+        self.0.lock().unwrap().read_state(id)
+    }
+
+    fn drain_reports(&mut self) -> Vec<InputReport> {
+        self.0.lock().unwrap().drain_reports()
+    }
+}
+
+fn manager() -> (ControllerManager, Arc<Mutex<SimBackend>>) {
+    let script = SimScript::from_json(SCRIPT).unwrap();
+    let sim = Arc::new(Mutex::new(SimBackend::manual(script)));
+    let manager = ControllerManager::with_backends(vec![Box::new(Shared(sim.clone()))]);
+    (manager, sim)
+}
+
+fn advance_and_poll(
+    manager: &mut ControllerManager,
+    sim: &Mutex<SimBackend>,
+    ms: u64,
+) -> Vec<ManagerEvent> {
+    sim.lock().unwrap().advance(Duration::from_millis(ms));
+    manager.poll()
+}
+
+#[test]
+fn hotplug() {
+    let (mut manager, sim) = manager();
+
+    let events = manager.poll();
+    let [ManagerEvent::Hotplug(HotplugEvent::Connected(info)), ManagerEvent::State(_)] =
+        events.as_slice()
+    else {
+        panic!(
+            "expected a connection and its first state, got {:?}",
+            events
+        );
+    };
+    assert_eq!((info.id, info.name.as_str()), (0, "Pad A"));
+    assert_eq!(info.controller_type, "sim");
+    assert_eq!(
+        (info.vendor_id, info.product_id),
+        (Some(0x045e), Some(0x028e))
+    );
+    assert_eq!(manager.gamepads().len(), 1);
+
+    advance_and_poll(&mut manager, &sim, 20);
+    let events = advance_and_poll(&mut manager, &sim, 10);
+    assert!(matches!(
+        events.first(),
+        Some(ManagerEvent::Hotplug(HotplugEvent::Connected(info))) if info.id == 1
+    ));
+    let names: Vec<String> = manager
+        .gamepads()
+        .into_iter()
+        .map(|info| info.name)
+        .collect();
+    assert_eq!(names, ["Pad A", "Pad B"]);
+
+    let events = advance_and_poll(&mut manager, &sim, 10);
+    assert!(matches!(
+        events.first(),
+        Some(ManagerEvent::Hotplug(HotplugEvent::Disconnected {
+            id: 0,
+            ..
+        }))
+    ));
+    assert_eq!(manager.gamepads().len(), 1);
+
+    // Unplugged devices are told apart from ones that never existed
+    assert!(matches!(
+        manager.gamepad_state(0, "sim"),
+        Err(ControllerError::Disconnected { id: 0, .. })
+    ));
+    assert!(matches!(
+        manager.gamepad_state(7, "sim"),
+        Err(ControllerError::DeviceNotFound { id: 7, .. })
+    ));
+    assert!(matches!(
+        manager.gamepad_state(0, "xinput"),
+        Err(ControllerError::BackendUnavailable { .. })
+    ));
+    assert!(matches!(
+        manager.gamepad_state(0, "nope"),
+        Err(ControllerError::InvalidControllerType { .. })
+    ));
+    assert!(manager.gamepad_state(1, "sim").is_ok());
+}
+
+#[test]
+fn state_deltas() {
+    let (mut manager, sim) = manager();
+
+    // The first sample of a device reports every entry
+    let events = manager.poll();
+    let Some(ManagerEvent::State(first)) = events.last() else {
+        panic!("expected a state delta, got {:?}", events);
+    };
+    assert_eq!(first.buttons.len(), 15);
+    assert_eq!(first.axes.len(), 6);
+
+    let events = advance_and_poll(&mut manager, &sim, 10);
+    let [ManagerEvent::State(delta)] = events.as_slice() else {
+        panic!("expected one state delta, got {:?}", events);
+    };
+    assert_eq!((delta.controller_type.as_str(), delta.id), ("sim", 0));
+    assert_eq!(delta.buttons, [(0, true)]);
+    assert!(delta.axes.is_empty());
+
+    // Nothing changed, nothing to report
+    assert!(manager.poll().is_empty());
+
+    let events = advance_and_poll(&mut manager, &sim, 10);
+    let [ManagerEvent::State(delta)] = events.as_slice() else {
+        panic!("expected one state delta, got {:?}", events);
+    };
+    assert!(delta.buttons.is_empty());
+    assert_eq!(delta.axes, [(0, -1.0)]);
+    assert_eq!(delta.raw_axes, [(0, -32767)]);
+}
+
+#[test]
+fn sample_history() {
+    let (mut manager, sim) = manager();
+
+    manager.poll();
+    advance_and_poll(&mut manager, &sim, 10);
+    // Sample timestamps are in microseconds, keep the earlier ones out
+    std::thread::sleep(Duration::from_millis(1));
+    let since_us = manager.timestamp_us();
+    advance_and_poll(&mut manager, &sim, 10);
+    manager.poll();
+
+    let samples = manager.samples_since(0, "sim", 0).unwrap();
+    assert_eq!(samples.len(), 4);
+    assert!(samples
+        .windows(2)
+        .all(|pair| pair[0].timestamp_us <= pair[1].timestamp_us));
+    let pressed: Vec<bool> = samples
+        .iter()
+        .map(|sample| sample.state.buttons[0])
+        .collect();
+    assert_eq!(pressed, [false, true, true, true]);
+
+    let recent = manager.samples_since(0, "sim", since_us).unwrap();
+    assert_eq!(recent.len(), 2);
+    assert!(recent.iter().all(|sample| sample.state.axes[0] == -1.0));
+
+    let state = manager.gamepad_state(0, "sim").unwrap();
+    assert!(state.buttons[0]);
+    assert_eq!(state.axes[0], -1.0);
+
+    // History goes with the device
+    advance_and_poll(&mut manager, &sim, 20);
+    assert!(manager.samples_since(0, "sim", 0).is_err());
+    assert!(!manager.samples_since(1, "sim", 0).unwrap().is_empty());
+}
@@ -1,210 +1,445 @@
-use rusty_xinput::XInputHandle;
-use tauri::State;
-use winapi::um::xinput::*;
-use super::models::{ControllerState, GamepadInfo, GamepadState};
-use super::utils::{normalize_xinput_axis, normalize_xinput_trigger};
-
-#[tauri::command(rename_all = "snake_case")]
-pub fn get_gamepads(controller_state: State<ControllerState>) -> Vec<GamepadInfo> {
-    let mut controllers = Vec::new();
-    let mut manager = controller_state.0.lock().unwrap();
-    manager.poll();
-
-    // Add XInput controllers (IDs 0-3)
-    if let Ok(xinput) = XInputHandle::load_default() {
-        for i in 0..4 {
-            match xinput.get_state(i) {
-                Ok(_) => {
-                    if !manager.xinput_previous_states[i as usize].connected {
-                        println!("XInput Controller {} connected", i + 1);
-                        manager.xinput_previous_states[i as usize].connected = true;
-                    }
-                    controllers.push(GamepadInfo {
-                        id: i as usize,
-                        name: format!("Xbox Controller {}", i + 1),
-                        controller_type: "xinput".to_string(),
-                        vendor_id: None,
-                        product_id: None,
-                    });
-                },
-                Err(_) => {
-                    if manager.xinput_previous_states[i as usize].connected {
-                        println!("XInput Controller {} disconnected", i + 1);
-                        manager.xinput_previous_states[i as usize].connected = false;
-                    }
-                }
-            }
-        }
-    }
-
-    // Add SDL controllers using their actual instance IDs
-    if let Some(sdl) = manager.get_sdl_context() {
-        if let Ok(js) = sdl.context.joystick() {
-            if let Ok(available) = js.num_joysticks() {
-                let states = manager.get_controller_states();
-                
-                for (id, name, _, _) in states.iter() {
-                    for joy_id in 0..available {
-                        if let Ok(joystick) = js.open(joy_id) {
-                            if joystick.instance_id() == *id {
-                                // Get the GUID string and try to parse vendor/product IDs
-                                let guid_str = format!("{}", joystick.guid());
-                                // The GUID string format is typically "00000000000000000000000000000000"
-                                // where bytes 8-10 contain vendor ID and bytes 10-12 contain product ID
-                                let vid = u16::from_str_radix(&guid_str[8..12], 16).ok();
-                                let pid = u16::from_str_radix(&guid_str[12..16], 16).ok();
-                                
-                                controllers.push(GamepadInfo {
-                                    id: *id as usize,
-                                    name: name.clone(),
-                                    controller_type: "sdl".to_string(),
-                                    vendor_id: vid,
-                                    product_id: pid,
-                                });
-                                break;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    controllers
-}
-
-#[tauri::command(rename_all = "snake_case")]
-pub fn get_gamepad_state(id: usize, controller_type: String, controller_state: State<ControllerState>) -> Option<GamepadState> {
-    let mut manager = controller_state.0.lock().unwrap();
-    manager.poll();
-    
-    match controller_type.as_str() {
-        "xinput" => {
-            // XInput controller handling
-            if let Ok(xinput) = XInputHandle::load_default() {
-                if let Ok(state) = xinput.get_state(id as u32) {
-                    let raw = state.raw;
-                    let mut prev_state = manager.xinput_previous_states[id];
-                    
-                    // Calculate all analog values first
-                    let left_x = normalize_xinput_axis(raw.Gamepad.sThumbLX);
-                    let left_y = normalize_xinput_axis(raw.Gamepad.sThumbLY);
-                    let right_x = normalize_xinput_axis(raw.Gamepad.sThumbRX);
-                    let right_y = normalize_xinput_axis(raw.Gamepad.sThumbRY);
-                    let left_trigger = normalize_xinput_trigger(raw.Gamepad.bLeftTrigger);
-                    let right_trigger = normalize_xinput_trigger(raw.Gamepad.bRightTrigger);
-                    
-                    // Check for button state changes
-                    let buttons = [
-                        (XINPUT_GAMEPAD_A as u16, "A"),
-                        (XINPUT_GAMEPAD_B as u16, "B"),
-                        (XINPUT_GAMEPAD_X as u16, "X"),
-                        (XINPUT_GAMEPAD_Y as u16, "Y"),
-                        (XINPUT_GAMEPAD_LEFT_SHOULDER as u16, "LB"),
-                        (XINPUT_GAMEPAD_RIGHT_SHOULDER as u16, "RB"),
-                        (XINPUT_GAMEPAD_BACK as u16, "Back"),
-                        (XINPUT_GAMEPAD_START as u16, "Start"),
-                        (XINPUT_GAMEPAD_LEFT_THUMB as u16, "L3"),
-                        (XINPUT_GAMEPAD_RIGHT_THUMB as u16, "R3"),
-                        (XINPUT_GAMEPAD_DPAD_UP as u16, "DPad Up"),
-                        (XINPUT_GAMEPAD_DPAD_DOWN as u16, "DPad Down"),
-                        (XINPUT_GAMEPAD_DPAD_LEFT as u16, "DPad Left"),
-                        (XINPUT_GAMEPAD_DPAD_RIGHT as u16, "DPad Right"),
-                    ];
-
-                    // Only log button changes
-                    if raw.Gamepad.wButtons != prev_state.buttons {
-                        for (button_mask, button_name) in buttons.iter() {
-                            let was_pressed = prev_state.buttons & button_mask != 0;
-                            let is_pressed = raw.Gamepad.wButtons & button_mask != 0;
-                            if was_pressed != is_pressed {
-                                if is_pressed {
-                                    println!("XInput {}: {} button pressed", id, button_name);
-                                } else {
-                                    println!("XInput {}: {} button released", id, button_name);
-                                }
-                            }
-                        }
-                    }
-
-                    // Only log stick changes if they differ significantly from previous state
-                    let stick_threshold: i32 = 8192; // Convert to i32 for safe math
-                    let left_x_diff = (raw.Gamepad.sThumbLX as i32) - (prev_state.left_x as i32);
-                    let left_y_diff = (raw.Gamepad.sThumbLY as i32) - (prev_state.left_y as i32);
-                    let right_x_diff = (raw.Gamepad.sThumbRX as i32) - (prev_state.right_x as i32);
-                    let right_y_diff = (raw.Gamepad.sThumbRY as i32) - (prev_state.right_y as i32);
-
-                    if left_x_diff.abs() > stick_threshold || left_y_diff.abs() > stick_threshold {
-                        println!("XInput {}: Left stick at ({:.2}, {:.2})", id, left_x, left_y);
-                    }
-                    if right_x_diff.abs() > stick_threshold || right_y_diff.abs() > stick_threshold {
-                        println!("XInput {}: Right stick at ({:.2}, {:.2})", id, right_x, right_y);
-                    }
-
-                    // Only log trigger changes if they differ significantly from previous state
-                    let trigger_threshold = 20;
-                    let left_trigger_diff = (raw.Gamepad.bLeftTrigger as i16) - (prev_state.left_trigger as i16);
-                    let right_trigger_diff = (raw.Gamepad.bRightTrigger as i16) - (prev_state.right_trigger as i16);
-
-                    if left_trigger_diff.abs() > trigger_threshold {
-                        println!("XInput {}: Left trigger at {:.2}", id, left_trigger);
-                    }
-                    if right_trigger_diff.abs() > trigger_threshold {
-                        println!("XInput {}: Right trigger at {:.2}", id, right_trigger);
-                    }
-
-                    // Update previous state
-                    prev_state.buttons = raw.Gamepad.wButtons;
-                    prev_state.left_x = raw.Gamepad.sThumbLX;
-                    prev_state.left_y = raw.Gamepad.sThumbLY;
-                    prev_state.right_x = raw.Gamepad.sThumbRX;
-                    prev_state.right_y = raw.Gamepad.sThumbRY;
-                    prev_state.left_trigger = raw.Gamepad.bLeftTrigger;
-                    prev_state.right_trigger = raw.Gamepad.bRightTrigger;
-                    manager.xinput_previous_states[id] = prev_state;
-
-                    return Some(GamepadState {
-                        buttons: vec![
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_A as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_B as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_X as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_Y as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_LEFT_SHOULDER as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_RIGHT_SHOULDER as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_BACK as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_START as u16) != 0,
-                            false,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_LEFT_THUMB as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_RIGHT_THUMB as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_DPAD_UP as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_DPAD_DOWN as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_DPAD_LEFT as u16) != 0,
-                            raw.Gamepad.wButtons & (XINPUT_GAMEPAD_DPAD_RIGHT as u16) != 0,
-                        ],
-                        axes: vec![
-                            left_x,
-                            left_y,
-                            right_x,
-                            right_y,
-                            left_trigger,
-                            right_trigger,
-                        ],
-                    });
-                }
-            }
+use super::logging::{LogBuffer, LogEntry, LogLevel};
+use super::ControllerState;
+use sloptester_core::analysis::{
+    analyze_circularity, analyze_debounce, analyze_drift, analyze_quantization, analyze_triggers,
+    CircularityReport, DebounceReport, DriftReport, QuantizationReport, ReportRateStats,
+    SocdTestStatus, TriggerReport, TriggerThresholds, DEFAULT_ANGLE_BINS,
+    DEFAULT_BOUNCE_THRESHOLD_US, DEFAULT_DRIFT_DURATION_US, TRIGGER_TEST_US,
+};
+use sloptester_core::backends::{PlaybackControl, PlaybackStatus, ReportDescriptor};
+use sloptester_core::error::ControllerError;
+use sloptester_core::export::{self, ExportFormat, ExportSummary};
+use sloptester_core::models::{
+    DeviceKey, GamepadInfo, GamepadState, InputSample, PacketStats, RawInputLayout, RawReport,
+};
+use sloptester_core::recording::{Recording, RecordingFormat, RecordingSummary};
+use std::io;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::DialogExt;
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_gamepads(
+    controller_state: State<ControllerState>,
+) -> Result<Vec<GamepadInfo>, ControllerError> {
+    let manager = controller_state.lock()?;
+    Ok(manager.gamepads())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_gamepad_state(
+    id: usize,
+    controller_type: String,
+    controller_state: State<ControllerState>,
+) -> Result<GamepadState, ControllerError> {
+    let manager = controller_state.lock()?;
+    manager.gamepad_state(id, &controller_type)
+}
+
+/// Returns the buffered samples for a device, oldest first. Pass the last
+/// `timestamp_us` seen to only get newer samples.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_gamepad_samples(
+    id: usize,
+    controller_type: String,
+    since_us: Option<u64>,
+    controller_state: State<ControllerState>,
+) -> Result<Vec<InputSample>, ControllerError> {
+    let manager = controller_state.lock()?;
+    manager.samples_since(id, &controller_type, since_us.unwrap_or(0))
+}
+
+/// Current time on the sample clock. Pass it as `since_us` to analysis
+/// commands to only look at samples taken from now on.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_timestamp_us(controller_state: State<ControllerState>) -> Result<u64, ControllerError> {
+    let manager = controller_state.lock()?;
+    Ok(manager.timestamp_us())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn set_poll_rate(
+    rate_hz: u32,
+    controller_state: State<ControllerState>,
+) -> Result<u32, ControllerError> {
+    let mut manager = controller_state.lock()?;
+    manager.set_poll_rate(rate_hz);
+    Ok(manager.poller_config().rate_hz)
+}
+
+/// Starts timestamping every input report from a device. Reports are only
+/// sent when the input changes, so the stick should be kept moving.
+#[tauri::command(rename_all = "snake_case")]
+pub fn start_report_rate_measurement(
+    id: usize,
+    controller_type: String,
+    controller_state: State<ControllerState>,
+) -> Result<(), ControllerError> {
+    let mut manager = controller_state.lock()?;
+    manager.start_report_rate(id, &controller_type)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_report_rate(
+    controller_state: State<ControllerState>,
+) -> Result<Option<ReportRateStats>, ControllerError> {
+    let manager = controller_state.lock()?;
+    Ok(manager.report_rate_stats())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn stop_report_rate_measurement(
+    controller_state: State<ControllerState>,
+) -> Result<Option<ReportRateStats>, ControllerError> {
+    let mut manager = controller_state.lock()?;
+    Ok(manager.stop_report_rate())
+}
+
+/// Packet sequence counters for a device whose backend numbers its reports
+/// (XInput). `skipped_packets` counts controller updates the poller missed.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_packet_stats(
+    id: usize,
+    controller_type: String,
+    controller_state: State<ControllerState>,
+) -> Result<Option<PacketStats>, ControllerError> {
+    let manager = controller_state.lock()?;
+    manager.packet_stats(id, &controller_type)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn reset_packet_stats(
+    id: usize,
+    controller_type: String,
+    controller_state: State<ControllerState>,
+) -> Result<(), ControllerError> {
+    let mut manager = controller_state.lock()?;
+    manager.reset_packet_stats(id, &controller_type)
+}
+
+/// Kernel event codes and absinfo ranges behind each button and axis index,
+/// for devices read through evdev. `None` for other backends.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_raw_layout(
+    id: usize,
+    controller_type: String,
+    controller_state: State<ControllerState>,
+) -> Result<Option<RawInputLayout>, ControllerError> {
+    let manager = controller_state.lock()?;
+    manager.raw_layout(id, &controller_type)
+}
+
+/// The latest input report of each report ID, byte for byte, for devices
+/// read as raw HID. Empty for other backends.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_raw_reports(
+    id: usize,
+    controller_type: String,
+    controller_state: State<ControllerState>,
+) -> Result<Vec<RawReport>, ControllerError> {
+    let manager = controller_state.lock()?;
+    manager.raw_reports(id, &controller_type)
+}
+
+/// The parsed report descriptor of a raw HID device: its collection tree,
+/// every Input, Output and Feature field with its position, usages and
+/// ranges, and the size of each report. `None` for other backends.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_report_descriptor(
+    id: usize,
+    controller_type: String,
+    controller_state: State<ControllerState>,
+) -> Result<Option<ReportDescriptor>, ControllerError> {
+    let manager = controller_state.lock()?;
+    manager.report_descriptor(id, &controller_type)
+}
+
+/// Per-button bounce analysis over the buffered samples taken at or after
+/// `since_us`. Presses or releases shorter than `threshold_ms` count as bounce.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_debounce_report(
+    id: usize,
+    controller_type: String,
+    threshold_ms: Option<f64>,
+    since_us: Option<u64>,
+    controller_state: State<ControllerState>,
+) -> Result<DebounceReport, ControllerError> {
+    let samples = {
+        let manager = controller_state.lock()?;
+        manager.samples_since(id, &controller_type, since_us.unwrap_or(0))?
+    };
+    let threshold_us = threshold_ms
+        .map(|ms| (ms * 1000.0) as u64)
+        .unwrap_or(DEFAULT_BOUNCE_THRESHOLD_US);
+    Ok(analyze_debounce(&samples, threshold_us))
+}
+
+/// Stick circularity and gate shape over the buffered samples taken at or
+/// after `since_us`. The stick should be rotated slowly against the gate.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_circularity_report(
+    id: usize,
+    controller_type: String,
+    since_us: Option<u64>,
+    bins: Option<usize>,
+    controller_state: State<ControllerState>,
+) -> Result<CircularityReport, ControllerError> {
+    let (samples, layout) = {
+        let manager = controller_state.lock()?;
+        (
+            manager.samples_since(id, &controller_type, since_us.unwrap_or(0))?,
+            manager.device_layout(id, &controller_type)?,
+        )
+    };
+    Ok(analyze_circularity(
+        &samples,
+        &layout,
+        bins.unwrap_or(DEFAULT_ANGLE_BINS),
+    ))
+}
+
+/// Resting offset, noise and suggested deadzone per axis over the last
+/// `duration_ms` of samples. The controller must not be touched during that
+/// time.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_drift_report(
+    id: usize,
+    controller_type: String,
+    duration_ms: Option<u64>,
+    controller_state: State<ControllerState>,
+) -> Result<DriftReport, ControllerError> {
+    let samples = {
+        let manager = controller_state.lock()?;
+        let duration_us = duration_ms
+            .map(|ms| ms.saturating_mul(1000))
+            .unwrap_or(DEFAULT_DRIFT_DURATION_US);
+        let since_us = manager.timestamp_us().saturating_sub(duration_us);
+        manager.samples_since(id, &controller_type, since_us)?
+    };
+    Ok(analyze_drift(&samples))
+}
+
+/// Distinct raw values per axis over the buffered samples taken at or after
+/// `since_us`, with the step size and bit depth they imply. Axes should be
+/// swept slowly across their whole range.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_quantization_report(
+    id: usize,
+    controller_type: String,
+    since_us: Option<u64>,
+    controller_state: State<ControllerState>,
+) -> Result<QuantizationReport, ControllerError> {
+    let samples = {
+        let manager = controller_state.lock()?;
+        manager.samples_since(id, &controller_type, since_us.unwrap_or(0))?
+    };
+    Ok(analyze_quantization(&samples))
+}
+
+/// Trigger range and linearity for a guided test started at `started_us`
+/// (from `get_timestamp_us`). Call once the test's time has run out.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_trigger_report(
+    id: usize,
+    controller_type: String,
+    started_us: u64,
+    thresholds: Option<TriggerThresholds>,
+    controller_state: State<ControllerState>,
+) -> Result<TriggerReport, ControllerError> {
+    let (mut samples, layout) = {
+        let manager = controller_state.lock()?;
+        (
+            manager.samples_since(id, &controller_type, started_us)?,
+            manager.device_layout(id, &controller_type)?,
+        )
+    };
+    samples.retain(|sample| sample.timestamp_us < started_us.saturating_add(TRIGGER_TEST_US));
+    Ok(analyze_triggers(
+        &samples,
+        &layout,
+        started_us,
+        thresholds.unwrap_or_default(),
+    ))
+}
+
+/// Starts the guided SOCD test. The returned status carries the first prompt.
+#[tauri::command(rename_all = "snake_case")]
+pub fn start_socd_test(
+    id: usize,
+    controller_type: String,
+    controller_state: State<ControllerState>,
+) -> Result<SocdTestStatus, ControllerError> {
+    let mut manager = controller_state.lock()?;
+    manager.start_socd_test(id, &controller_type)
+}
+
+/// Call while the user performs the current prompt. Records the controller's
+/// output and returns the next prompt, or the result once all steps are done.
+#[tauri::command(rename_all = "snake_case")]
+pub fn record_socd_step(
+    controller_state: State<ControllerState>,
+) -> Result<Option<SocdTestStatus>, ControllerError> {
+    let mut manager = controller_state.lock()?;
+    Ok(manager.record_socd_step())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn cancel_socd_test(controller_state: State<ControllerState>) -> Result<(), ControllerError> {
+    let mut manager = controller_state.lock()?;
+    manager.cancel_socd_test();
+    Ok(())
+}
+
+/// Starts recording every sample and hotplug event. Without a `path` the file
+/// goes to the app data directory's `recordings` folder. Returns the path.
+#[tauri::command(rename_all = "snake_case")]
+pub fn start_recording(
+    format: RecordingFormat,
+    path: Option<String>,
+    app: AppHandle,
+    controller_state: State<ControllerState>,
+) -> Result<String, ControllerError> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let directory = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| ControllerError::File {
+                    path: String::new(),
+                    kind: io::ErrorKind::NotFound,
+                    message: format!("Failed to find app data directory: {}", e),
+                })?
+                .join("recordings");
+            std::fs::create_dir_all(&directory)
+                .map_err(|e| ControllerError::file("Failed to create", &directory, &e))?;
+            let started_unix_ms = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis())
+                .unwrap_or(0);
+            directory.join(format!(
+                "session-{}.{}",
+                started_unix_ms,
+                format.extension()
+            ))
         }
-        "sdl" => {
-            // SDL controller handling
-            let states = manager.get_controller_states();
-            if let Some((_, _, buttons, axes)) = states.iter().find(|(controller_id, _, _, _)| *controller_id == id as u32) {
-                return Some(GamepadState {
-                    buttons: buttons.clone(),
-                    axes: axes.clone(),
-                });
+    };
+
+    let mut manager = controller_state.lock()?;
+    manager.start_recording(&path, format)?;
+    Ok(path.display().to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn stop_recording(
+    controller_state: State<ControllerState>,
+) -> Result<Option<RecordingSummary>, ControllerError> {
+    let mut manager = controller_state.lock()?;
+    manager.stop_recording().transpose()
+}
+
+/// Path of the recording in progress, if any.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_recording_path(
+    controller_state: State<ControllerState>,
+) -> Result<Option<String>, ControllerError> {
+    let manager = controller_state.lock()?;
+    Ok(manager.recording_path().map(str::to_string))
+}
+
+/// Writes the buffered samples taken between `since_us` and `until_us` to a
+/// CSV or JSON Lines file. Without a `path` a save dialog asks for one, and
+/// `None` is returned if it is cancelled.
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_samples(
+    id: usize,
+    controller_type: String,
+    format: ExportFormat,
+    since_us: Option<u64>,
+    until_us: Option<u64>,
+    path: Option<String>,
+    app: AppHandle,
+    controller_state: State<'_, ControllerState>,
+) -> Result<Option<ExportSummary>, ControllerError> {
+    // Snapshot first so the window doesn't move while the dialog is open
+    let (samples, layout) = {
+        let manager = controller_state.lock()?;
+        let samples = manager.samples_since(id, &controller_type, since_us.unwrap_or(0))?;
+        let layout = manager.device_layout(id, &controller_type)?;
+        (samples, layout)
+    };
+    let samples: Vec<InputSample> = samples
+        .into_iter()
+        .filter(|sample| until_us.is_none_or(|until| sample.timestamp_us <= until))
+        .collect();
+
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let file_name = format!("{}-{}-samples.{}", controller_type, id, format.extension());
+            let chosen = app
+                .dialog()
+                .file()
+                .add_filter(format.extension().to_uppercase(), &[format.extension()])
+                .set_file_name(file_name)
+                .blocking_save_file();
+            match chosen {
+                Some(chosen) => chosen.into_path().map_err(|e| ControllerError::File {
+                    path: String::new(),
+                    kind: io::ErrorKind::InvalidInput,
+                    message: format!("Invalid export path: {}", e),
+                })?,
+                None => return Ok(None),
             }
         }
-        _ => {}
-    }
-    
-    None
-}
\ No newline at end of file
+    };
+
+    let key = DeviceKey::new(&controller_type, id);
+    export::export_samples(&path, format, &key, Some(&layout), &samples).map(Some)
+}
+
+/// Plays a session recording back as virtual `replay` controllers next to
+/// the live ones, replacing any replay already loaded.
+#[tauri::command(rename_all = "snake_case")]
+pub fn load_replay(
+    path: String,
+    controller_state: State<ControllerState>,
+) -> Result<PlaybackStatus, ControllerError> {
+    let recording = Recording::from_file(&path)?;
+    let mut manager = controller_state.lock()?;
+    Ok(manager.load_replay(recording))
+}
+
+/// Pauses, resumes, seeks or changes the speed of the loaded replay.
+#[tauri::command(rename_all = "snake_case")]
+pub fn control_replay(
+    control: PlaybackControl,
+    controller_state: State<ControllerState>,
+) -> Result<Option<PlaybackStatus>, ControllerError> {
+    let mut manager = controller_state.lock()?;
+    Ok(manager.control_replay(control))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn close_replay(controller_state: State<ControllerState>) -> Result<(), ControllerError> {
+    let mut manager = controller_state.lock()?;
+    manager.close_replay();
+    Ok(())
+}
+
+/// Entries logged after `after_seq`, at `min_level` (default `info`) or more
+/// severe, oldest first. At most `limit` of the newest are returned.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_log_entries(
+    after_seq: Option<u64>,
+    min_level: Option<LogLevel>,
+    limit: Option<usize>,
+    log_buffer: State<LogBuffer>,
+) -> Vec<LogEntry> {
+    log_buffer.entries(
+        after_seq,
+        min_level.unwrap_or(LogLevel::Info),
+        limit.unwrap_or(500),
+    )
+}
@@ -1,23 +1,105 @@
-pub mod models;
-pub mod managers;
-pub mod utils;
 pub mod commands;
+pub mod logging;
 
-use std::sync::{Arc, Mutex};
-use models::ControllerState;
-use managers::ControllerManager;
+use logging::LogBuffer;
+use sloptester_core::backends::HotplugEvent;
+use sloptester_core::error::ControllerError;
+use sloptester_core::managers::{spawn_poller, ControllerManager, ManagerEvent};
+use sloptester_core::models::DeviceKey;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::warn;
+
+/// The manager shared between the input thread and the commands.
+pub struct ControllerState(pub Arc<Mutex<ControllerManager>>);
+
+impl ControllerState {
+    /// Locks the manager, failing instead of panicking if a thread panicked
+    /// while holding it.
+    pub fn lock(&self) -> Result<MutexGuard<'_, ControllerManager>, ControllerError> {
+        self.0.lock().map_err(|_| ControllerError::LockPoisoned)
+    }
+}
+
+/// Forwards manager events to the webview as `gamepad-connected`,
+/// `gamepad-disconnected` and `gamepad-state` events.
+fn emit_events(app: &AppHandle, events: Vec<ManagerEvent>) {
+    for event in events {
+        let result = match event {
+            ManagerEvent::Hotplug(HotplugEvent::Connected(info)) => {
+                app.emit("gamepad-connected", info)
+            }
+            ManagerEvent::Hotplug(HotplugEvent::Disconnected {
+                id,
+                controller_type,
+            }) => app.emit(
+                "gamepad-disconnected",
+                DeviceKey {
+                    controller_type,
+                    id,
+                },
+            ),
+            ManagerEvent::State(delta) => app.emit("gamepad-state", delta),
+        };
+        if let Err(e) = result {
+            warn!(error = %e, "Failed to emit gamepad event");
+        }
+    }
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let controller_manager = Arc::new(Mutex::new(ControllerManager::new()));
+    let log_buffer = LogBuffer::default();
 
     tauri::Builder::default()
-        .manage(ControllerState(controller_manager.clone()))
+        .plugin(tauri_plugin_dialog::init())
+        .manage(log_buffer.clone())
         .invoke_handler(tauri::generate_handler![
             commands::get_gamepads,
-            commands::get_gamepad_state
+            commands::get_gamepad_state,
+            commands::get_gamepad_samples,
+            commands::get_timestamp_us,
+            commands::set_poll_rate,
+            commands::start_report_rate_measurement,
+            commands::get_report_rate,
+            commands::stop_report_rate_measurement,
+            commands::get_packet_stats,
+            commands::reset_packet_stats,
+            commands::get_raw_layout,
+            commands::get_raw_reports,
+            commands::get_report_descriptor,
+            commands::get_debounce_report,
+            commands::get_circularity_report,
+            commands::get_drift_report,
+            commands::get_quantization_report,
+            commands::get_trigger_report,
+            commands::start_socd_test,
+            commands::record_socd_step,
+            commands::cancel_socd_test,
+            commands::start_recording,
+            commands::stop_recording,
+            commands::get_recording_path,
+            commands::export_samples,
+            commands::load_replay,
+            commands::control_replay,
+            commands::close_replay,
+            commands::get_log_entries
         ])
-        .setup(|_app| Ok(()))
+        .setup(move |app| {
+            // Logging needs the app data directory, so backends are only
+            // started once it is set up
+            let log_dir = app.path().app_data_dir().ok().map(|dir| dir.join("logs"));
+            logging::init(log_dir.as_deref(), log_buffer);
+
+            let controller_manager = Arc::new(Mutex::new(ControllerManager::new()));
+            app.manage(ControllerState(controller_manager.clone()));
+
+            let handle = app.handle().clone();
+            spawn_poller(controller_manager, move |events| {
+                emit_events(&handle, events)
+            });
+            Ok(())
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,296 @@
+//! Log output for the app. Every entry goes to stderr, to a daily rotating
+//! file in the app data directory's `logs` folder, and to an in-memory
+//! buffer that `get_log_entries` serves to the log panel.
+//!
+//! The filter is read from `SLOPTESTER_LOG` in `RUST_LOG` syntax, e.g.
+//! `debug` or `info,sloptester_core::backends::sdl=trace`. Each backend logs
+//! under its own module path, and device events carry `controller_type` and
+//! `id` fields.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt as log_fmt, EnvFilter, Layer};
+
+/// Environment variable holding the log filter.
+pub const LOG_ENV: &str = "SLOPTESTER_LOG";
+const DEFAULT_FILTER: &str = "info";
+
+/// Entries kept in memory for the log panel.
+const MAX_ENTRIES: usize = 2000;
+/// Rotated log files kept on disk.
+const MAX_LOG_FILES: usize = 7;
+
+/// Severity, ordered from most to least severe.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<&Level> for LogLevel {
+    fn from(level: &Level) -> Self {
+        match *level {
+            Level::ERROR => Self::Error,
+            Level::WARN => Self::Warn,
+            Level::INFO => Self::Info,
+            Level::DEBUG => Self::Debug,
+            Level::TRACE => Self::Trace,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LogEntry {
+    /// Increases by one per entry. Pass the last one seen to
+    /// `get_log_entries` to only get newer entries.
+    pub seq: u64,
+    pub timestamp_unix_ms: u64,
+    pub level: LogLevel,
+    /// Module the entry was logged from, e.g.
+    /// `sloptester_core::backends::sdl`.
+    pub target: String,
+    pub message: String,
+    /// Structured fields such as `controller_type` and `id`.
+    pub fields: Map<String, Value>,
+}
+
+#[derive(Default)]
+struct Entries {
+    entries: VecDeque<LogEntry>,
+    next_seq: u64,
+}
+
+/// The most recent log entries, filled by a tracing layer.
+#[derive(Clone, Default)]
+pub struct LogBuffer(Arc<Mutex<Entries>>);
+
+impl LogBuffer {
+    /// Up to `limit` of the newest entries logged after `after_seq` at
+    /// `min_level` or more severe, oldest first.
+    pub fn entries(
+        &self,
+        after_seq: Option<u64>,
+        min_level: LogLevel,
+        limit: usize,
+    ) -> Vec<LogEntry> {
+        let Ok(buffer) = self.0.lock() else {
+            return Vec::new();
+        };
+        let mut entries: Vec<LogEntry> = buffer
+            .entries
+            .iter()
+            .rev()
+            .take_while(|entry| after_seq.is_none_or(|after| entry.seq > after))
+            .filter(|entry| entry.level <= min_level)
+            .take(limit)
+            .cloned()
+            .collect();
+        entries.reverse();
+        entries
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogBuffer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        let timestamp_unix_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+
+        let Ok(mut buffer) = self.0.lock() else {
+            return;
+        };
+        let seq = buffer.next_seq;
+        buffer.next_seq += 1;
+        if buffer.entries.len() == MAX_ENTRIES {
+            buffer.entries.pop_front();
+        }
+        buffer.entries.push_back(LogEntry {
+            seq,
+            timestamp_unix_ms,
+            level: metadata.level().into(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        });
+    }
+}
+
+/// Splits an event into its message and the remaining fields.
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: Map<String, Value>,
+}
+
+impl FieldVisitor {
+    fn record_value(&mut self, field: &Field, value: Value) {
+        if field.name() == "message" {
+            self.message = match value {
+                Value::String(message) => message,
+                other => other.to_string(),
+            };
+        } else {
+            self.fields.insert(field.name().to_string(), value);
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_value(field, Value::from(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_value(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record_value(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.record_value(field, Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.record_value(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record_value(field, Value::from(value));
+    }
+}
+
+/// Today's log file in `dir`, deleting the oldest ones so that at most
+/// `MAX_LOG_FILES` are left.
+fn file_appender(dir: &Path) -> Option<RollingFileAppender> {
+    // Created up front, pruning old files fails on a missing directory
+    let _ = std::fs::create_dir_all(dir);
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("sloptester")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(dir)
+        .inspect_err(|e| eprintln!("Failed to open log file in {}: {}", dir.display(), e))
+        .ok()
+}
+
+/// Installs the global subscriber, writing log files to `log_dir` if given.
+pub fn init(log_dir: Option<&Path>, buffer: LogBuffer) {
+    let filter =
+        EnvFilter::try_from_env(LOG_ENV).unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+
+    let file = log_dir.and_then(file_appender);
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(log_fmt::layer().with_writer(std::io::stderr))
+        .with(file.map(|file| log_fmt::layer().with_ansi(false).with_writer(file)))
+        .with(buffer)
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("Failed to set up logging: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing::{debug, info, warn};
+
+    #[test]
+    fn buffer_captures_events() {
+        let buffer = LogBuffer::default();
+        let subscriber = tracing_subscriber::registry().with(buffer.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            info!(controller_type = "sdl", id = 2, "Controller connected");
+            debug!("Polling");
+            warn!(rate_hz = 250.5, "Slow reports");
+        });
+
+        let entries = buffer.entries(None, LogLevel::Trace, 10);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries.iter().map(|entry| entry.seq).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+        let connected = &entries[0];
+        assert_eq!(connected.level, LogLevel::Info);
+        assert_eq!(connected.message, "Controller connected");
+        assert_eq!(connected.target, module_path!());
+        assert_eq!(connected.fields["controller_type"], "sdl");
+        assert_eq!(connected.fields["id"], 2);
+        assert_eq!(entries[2].fields["rate_hz"], 250.5);
+
+        // Filtered by level, sequence number and count, oldest first
+        let info: Vec<String> = buffer
+            .entries(None, LogLevel::Info, 10)
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect();
+        assert_eq!(info, ["Controller connected", "Slow reports"]);
+        assert_eq!(buffer.entries(Some(0), LogLevel::Trace, 10).len(), 2);
+        assert_eq!(buffer.entries(None, LogLevel::Trace, 1)[0].seq, 2);
+    }
+
+    #[test]
+    fn buffer_drops_the_oldest_entries() {
+        let buffer = LogBuffer::default();
+        let subscriber = tracing_subscriber::registry().with(buffer.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..MAX_ENTRIES + 5 {
+                info!("Entry {}", i);
+            }
+        });
+
+        let entries = buffer.entries(None, LogLevel::Trace, usize::MAX);
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].seq, 5);
+        assert_eq!(entries[0].message, "Entry 5");
+    }
+
+    #[test]
+    fn old_log_files_are_pruned() {
+        let dir = std::env::temp_dir().join(format!("sloptester-logs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for day in 1..=10 {
+            std::fs::write(dir.join(format!("sloptester.2020-01-{:02}.log", day)), "").unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let appender = file_appender(&dir);
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        drop(appender);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Today's file and the newest older ones, other files untouched
+        let logs = names
+            .iter()
+            .filter(|name| name.starts_with("sloptester."))
+            .count();
+        assert_eq!(logs, MAX_LOG_FILES);
+        assert!(names.contains(&"notes.txt".to_string()));
+    }
+}
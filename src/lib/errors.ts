@@ -0,0 +1,25 @@
+// Payload of a rejected command, see `ControllerError` in sloptester-core
+export interface ControllerError {
+  kind:
+    | 'backend_unavailable'
+    | 'invalid_controller_type'
+    | 'device_not_found'
+    | 'disconnected'
+    | 'lock_poisoned'
+    | 'file'
+  message: string
+  controller_type?: string
+  id?: number
+  // Only for 'file': the file involved, empty if there was none yet, and
+  // the Rust `io::ErrorKind`, e.g. 'NotFound'
+  path?: string
+  io_kind?: string
+}
+
+export function isControllerError(error: unknown): error is ControllerError {
+  return typeof error === 'object' && error !== null && 'kind' in error && 'message' in error
+}
+
+export function errorMessage(error: unknown): string {
+  return isControllerError(error) ? error.message : String(error)
+}
@@ -0,0 +1,167 @@
+// Types and helpers for `get_report_descriptor` and `get_raw_reports`, see
+// `backends::hid` in sloptester-core
+
+export interface Usage {
+  page: number
+  id: number
+}
+
+export type ReportKind = 'input' | 'output' | 'feature'
+
+export interface ReportField {
+  kind: ReportKind
+  report_id: number | null
+  bit_offset: number
+  size: number
+  count: number
+  usages: Usage[]
+  logical_min: number
+  logical_max: number
+  physical_min: number
+  physical_max: number
+  unit: number
+  unit_exponent: number
+  constant: boolean
+  variable: boolean
+  relative: boolean
+  null_state: boolean
+}
+
+export type CollectionKind =
+  | 'physical'
+  | 'application'
+  | 'logical'
+  | 'report'
+  | 'named_array'
+  | 'usage_switch'
+  | 'usage_modifier'
+  | { other: number }
+
+export interface Collection {
+  kind: CollectionKind
+  usage: Usage | null
+  collections: Collection[]
+  fields: number[]
+}
+
+export interface ReportSize {
+  kind: ReportKind
+  report_id: number | null
+  bits: number
+  bytes: number
+}
+
+export interface ReportDescriptor {
+  collections: Collection[]
+  fields: ReportField[]
+  reports: ReportSize[]
+  uses_report_ids: boolean
+}
+
+export interface RawReport {
+  report_id: number | null
+  bytes: number[]
+}
+
+const PAGE_NAMES: Record<number, string> = {
+  0x01: 'Generic Desktop',
+  0x02: 'Simulation',
+  0x05: 'Game',
+  0x06: 'Generic Device',
+  0x07: 'Keyboard',
+  0x08: 'LED',
+  0x09: 'Button',
+  0x0c: 'Consumer',
+  0x0f: 'PID'
+}
+
+const GENERIC_DESKTOP_NAMES: Record<number, string> = {
+  0x01: 'Pointer',
+  0x02: 'Mouse',
+  0x04: 'Joystick',
+  0x05: 'Game Pad',
+  0x06: 'Keyboard',
+  0x08: 'Multi-axis Controller',
+  0x30: 'X',
+  0x31: 'Y',
+  0x32: 'Z',
+  0x33: 'Rx',
+  0x34: 'Ry',
+  0x35: 'Rz',
+  0x36: 'Slider',
+  0x37: 'Dial',
+  0x38: 'Wheel',
+  0x39: 'Hat Switch',
+  0x3d: 'Start',
+  0x3e: 'Select',
+  0x90: 'D-pad Up',
+  0x91: 'D-pad Down',
+  0x92: 'D-pad Right',
+  0x93: 'D-pad Left'
+}
+
+const SIMULATION_NAMES: Record<number, string> = {
+  0xba: 'Rudder',
+  0xbb: 'Throttle',
+  0xc4: 'Accelerator',
+  0xc5: 'Brake',
+  0xc8: 'Steering'
+}
+
+export function hex(value: number, digits = 2): string {
+  return '0x' + value.toString(16).toUpperCase().padStart(digits, '0')
+}
+
+export function usagePageName(page: number): string {
+  if (page >= 0xff00) return `Vendor ${hex(page, 4)}`
+  return PAGE_NAMES[page] ?? hex(page, 4)
+}
+
+export function usageName(usage: Usage): string {
+  switch (usage.page) {
+    case 0x01:
+      return GENERIC_DESKTOP_NAMES[usage.id] ?? hex(usage.id)
+    case 0x02:
+      return SIMULATION_NAMES[usage.id] ?? hex(usage.id)
+    case 0x09:
+      return `Button ${usage.id}`
+    default:
+      return `${usagePageName(usage.page)} ${hex(usage.id)}`
+  }
+}
+
+export function collectionKindName(kind: CollectionKind): string {
+  return typeof kind === 'string' ? kind.replace('_', ' ') : `vendor ${hex(kind.other)}`
+}
+
+// Short summary of a field's usages, collapsing consecutive ids into ranges
+export function usageSummary(field: ReportField): string {
+  if (field.usages.length === 0) return field.constant ? 'padding' : 'no usage'
+  const parts: string[] = []
+  let start = 0
+  for (let i = 1; i <= field.usages.length; i++) {
+    const previous = field.usages[i - 1]
+    const next = field.usages[i]
+    if (next && next.page === previous.page && next.id === previous.id + 1) continue
+    const first = field.usages[start]
+    parts.push(i - 1 === start ? usageName(first) : `${usageName(first)} – ${usageName(previous)}`)
+    start = i
+  }
+  return parts.join(', ')
+}
+
+// Value of element `index` of `field` in a report payload (without the report
+// ID byte), least significant bit first and sign extended for signed ranges.
+// Mirrors `ReportField::value`.
+export function fieldValue(field: ReportField, payload: number[], index: number): number | null {
+  if (field.size === 0 || field.size > 32) return null
+  const start = field.bit_offset + index * field.size
+  if (start + field.size > payload.length * 8) return null
+  let value = 0
+  for (let bit = 0; bit < field.size; bit++) {
+    const position = start + bit
+    if ((payload[position >> 3] >> (position & 7)) & 1) value += 2 ** bit
+  }
+  if (field.logical_min < 0 && value >= 2 ** (field.size - 1)) value -= 2 ** field.size
+  return value
+}
@@ -0,0 +1,90 @@
+// Per-device button and axis layout, see `DeviceLayout` in sloptester-core
+
+export type ButtonKind = 'face' | 'shoulder' | 'menu' | 'stick' | 'dpad' | 'paddle' | 'touchpad' | 'other'
+
+export type AxisKind = 'stick' | 'trigger' | 'hat' | 'relative' | 'other'
+
+export interface ButtonLayout {
+  name: string
+  kind: ButtonKind
+}
+
+export interface AxisLayout {
+  name: string
+  kind: AxisKind
+}
+
+export interface DeviceLayout {
+  buttons: ButtonLayout[]
+  axes: AxisLayout[]
+}
+
+// XInput and SDL game controllers, and what devices without a layout get
+export const STANDARD_LAYOUT: DeviceLayout = {
+  buttons: [
+    { name: 'a', kind: 'face' },
+    { name: 'b', kind: 'face' },
+    { name: 'x', kind: 'face' },
+    { name: 'y', kind: 'face' },
+    { name: 'left_shoulder', kind: 'shoulder' },
+    { name: 'right_shoulder', kind: 'shoulder' },
+    { name: 'back', kind: 'menu' },
+    { name: 'start', kind: 'menu' },
+    { name: 'guide', kind: 'menu' },
+    { name: 'left_stick', kind: 'stick' },
+    { name: 'right_stick', kind: 'stick' },
+    { name: 'dpad_up', kind: 'dpad' },
+    { name: 'dpad_down', kind: 'dpad' },
+    { name: 'dpad_left', kind: 'dpad' },
+    { name: 'dpad_right', kind: 'dpad' }
+  ],
+  axes: [
+    { name: 'left_x', kind: 'stick' },
+    { name: 'left_y', kind: 'stick' },
+    { name: 'right_x', kind: 'stick' },
+    { name: 'right_y', kind: 'stick' },
+    { name: 'left_trigger', kind: 'trigger' },
+    { name: 'right_trigger', kind: 'trigger' }
+  ]
+}
+
+const BUTTON_LABELS: Record<string, string> = {
+  a: 'A',
+  b: 'B',
+  x: 'X',
+  y: 'Y',
+  left_shoulder: 'LB',
+  right_shoulder: 'RB',
+  back: 'Back',
+  start: 'Start',
+  guide: 'Guide',
+  left_stick: 'L3',
+  right_stick: 'R3',
+  misc1: 'Misc',
+  paddle1: 'P1',
+  paddle2: 'P2',
+  paddle3: 'P3',
+  paddle4: 'P4',
+  touchpad: 'Touchpad'
+}
+
+const AXIS_LABELS: Record<string, string> = {
+  left_trigger: 'Left Trigger',
+  right_trigger: 'Right Trigger'
+}
+
+export function buttonLabel(button: ButtonLayout): string {
+  return BUTTON_LABELS[button.name] ?? button.name
+}
+
+export function axisLabel(axis: AxisLayout): string {
+  return AXIS_LABELS[axis.name] ?? axis.name
+}
+
+// "Left Stick" for left_x/left_y, otherwise both axis names
+export function stickLabel(x: AxisLayout, y: AxisLayout): string {
+  const prefix = x.name.replace(/_x$/, '')
+  if (prefix === 'left' && y.name === 'left_y') return 'Left Stick'
+  if (prefix === 'right' && y.name === 'right_y') return 'Right Stick'
+  return `${x.name} / ${y.name}`
+}